@@ -0,0 +1,79 @@
+//! Measures searches whose payload filter admits 0.1% of the collection
+//! against unfiltered searches, for both vector layouts. Only admitted
+//! candidates are scored, so the filtered blocked search should not pay for
+//! scoring every block.
+//!
+//! Run with `cargo bench --bench filtered_scan [points]`.
+
+use std::time::Instant;
+
+use vectaraft::catalog::{Catalog, IdFilter, PointWrite};
+use vectaraft::types::{Metric, VectorLayout};
+
+const DIM: usize = 768;
+const QUERIES: usize = 50;
+/// One point in this many carries the filtered-for payload value.
+const SELECTIVITY: usize = 1000;
+
+fn main() {
+    let points: usize = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(100_000);
+
+    // Deterministic pseudo-random values in [-1, 1).
+    let mut seed = 0x2545_f491_u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    };
+    let data: Vec<PointWrite> = (0..points)
+        .map(|i| PointWrite {
+            id: format!("p{i}"),
+            vector: (0..DIM).map(|_| next()).collect(),
+            payload_json: format!("{{\"tenant\":\"t{}\"}}", i % SELECTIVITY),
+            expires_at_ms: None,
+        })
+        .collect();
+    let queries: Vec<Vec<f32>> = (0..QUERIES).map(|_| (0..DIM).map(|_| next()).collect()).collect();
+
+    let catalog = Catalog::default();
+    for layout in [VectorLayout::RowMajor, VectorLayout::Blocked] {
+        catalog.create_collection(layout.as_str().into(), DIM, Metric::L2);
+        let handle = catalog.get(layout.as_str()).expect("collection");
+        handle.with_mut(|c| c.index.set_layout(layout));
+        handle.upsert_points(data.clone()).expect("upsert");
+    }
+
+    let filter = vec![("tenant".to_string(), "t7".to_string())];
+    let mut results = Vec::new();
+    for layout in [VectorLayout::RowMajor, VectorLayout::Blocked] {
+        let handle = catalog.get(layout.as_str()).expect("collection");
+        for (label, filters) in [("unfiltered", vec![]), ("0.1%", filter.clone())] {
+            let started = Instant::now();
+            let mut admitted = 0;
+            let hits: Vec<_> = queries
+                .iter()
+                .map(|q| {
+                    let (hits, stats) = handle
+                        .search(q.clone(), 10, None, filters.clone(), IdFilter::default(), None, vec![], None, None)
+                        .expect("search");
+                    admitted = stats.evaluated - stats.rejected;
+                    hits
+                })
+                .collect();
+            let elapsed = started.elapsed();
+            println!(
+                "{:>9} {:>10}: {:.2} ms/query, {admitted} of {points} points scored",
+                layout.as_str(),
+                label,
+                elapsed.as_secs_f64() * 1e3 / QUERIES as f64
+            );
+            results.push(hits);
+        }
+    }
+    let (row_major, blocked) = results.split_at(results.len() / 2);
+    assert_eq!(row_major, blocked, "layouts disagree");
+}
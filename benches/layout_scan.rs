@@ -0,0 +1,68 @@
+//! Compares search latency of row-major and blocked vector layouts.
+//!
+//! Run with `cargo bench --bench layout_scan [points]`.
+
+use std::time::Instant;
+
+use vectaraft::catalog::{Catalog, IdFilter, PointWrite};
+use vectaraft::types::{Metric, VectorLayout};
+
+const DIM: usize = 1536;
+const QUERIES: usize = 50;
+
+fn main() {
+    let points: usize = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(20_000);
+
+    // Deterministic pseudo-random values in [-1, 1).
+    let mut seed = 0x2545_f491_u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    };
+    let data: Vec<PointWrite> = (0..points)
+        .map(|i| PointWrite {
+            id: format!("p{i}"),
+            vector: (0..DIM).map(|_| next()).collect(),
+            payload_json: String::new(),
+            expires_at_ms: None,
+        })
+        .collect();
+    let queries: Vec<Vec<f32>> = (0..QUERIES).map(|_| (0..DIM).map(|_| next()).collect()).collect();
+
+    let catalog = Catalog::default();
+    for layout in [VectorLayout::RowMajor, VectorLayout::Blocked] {
+        catalog.create_collection(layout.as_str().into(), DIM, Metric::L2);
+        let handle = catalog.get(layout.as_str()).expect("collection");
+        handle.with_mut(|c| c.index.set_layout(layout));
+        handle.upsert_points(data.clone()).expect("upsert");
+    }
+
+    let mut results = Vec::new();
+    for layout in [VectorLayout::RowMajor, VectorLayout::Blocked] {
+        let handle = catalog.get(layout.as_str()).expect("collection");
+        for metric in [Metric::L2, Metric::IP, Metric::Cosine] {
+            let started = Instant::now();
+            let hits: Vec<_> = queries
+                .iter()
+                .map(|q| handle.search(q.clone(), 10, Some(metric), vec![], IdFilter::default(), None, vec![], None, None).expect("search").0)
+                .collect();
+            let elapsed = started.elapsed();
+            println!(
+                "{:>9} {:>6}: {:.2} ms/query over {points} points at dim {DIM}",
+                layout.as_str(),
+                metric.as_str(),
+                elapsed.as_secs_f64() * 1e3 / QUERIES as f64
+            );
+            results.push(((layout, metric), hits));
+        }
+    }
+    let (row_major, blocked) = results.split_at(results.len() / 2);
+    for ((_, a), (_, b)) in row_major.iter().zip(blocked) {
+        assert_eq!(a, b, "layouts disagree");
+    }
+}
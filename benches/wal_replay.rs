@@ -0,0 +1,53 @@
+//! Times WAL replay of a single large collection.
+//!
+//! Run with `cargo bench --bench wal_replay [points]`.
+
+use std::io::Write;
+use std::time::Instant;
+
+use vectaraft::server::state::{DbState, DbStateConfig};
+use vectaraft::storage::wal::WalRecord;
+
+fn main() {
+    let points: usize = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(200_000);
+    let dim = 16;
+
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let wal_path = tmp.path().join("wal.log");
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&wal_path).expect("create wal"));
+    let mut write = |rec: &WalRecord| {
+        serde_json::to_writer(&mut file, rec).expect("serialize");
+        file.write_all(b"\n").expect("write");
+    };
+    write(&WalRecord::CreateCollection { name: "bench".into(), dim, metric: "l2".into(), parallel_threshold: None, layout: None, assume_normalized: false, default_top_k: None, default_with_payloads: false, payload_store: None, id_type: None, ts_ms: 0 });
+    for i in 0..points {
+        write(&WalRecord::Upsert {
+            collection: "bench".into(),
+            id: format!("p{i}"),
+            vector: (0..dim).map(|d| (i + d as usize) as f32).collect(),
+            payload_json: String::new(),
+            expires_at_ms: None,
+            ts_ms: 0,
+        });
+    }
+    file.flush().expect("flush");
+    drop(file);
+
+    let started = Instant::now();
+    let state = DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        read_only: true,
+        ..DbStateConfig::default()
+    });
+    let elapsed = started.elapsed();
+    assert_eq!(state.catalog.total_points(), points);
+    println!(
+        "replayed {points} upserts in {:.1} ms ({:.2} us/record)",
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_secs_f64() * 1e6 / points as f64
+    );
+}
@@ -17,14 +17,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         panic!("Missing proto file: {}", proto.display());
     }
 
+    // Vendored google.rpc messages used for structured error details.
+    let rpc_protos = [
+        proto_dir.join("google/rpc/status.proto"),
+        proto_dir.join("google/rpc/error_details.proto"),
+    ];
+
     println!("cargo:rerun-if-changed={}", proto.display());
     println!("cargo:rerun-if-changed={}", proto_dir.display());
+    for rpc_proto in &rpc_protos {
+        println!("cargo:rerun-if-changed={}", rpc_proto.display());
+    }
 
+    let mut protos = vec![proto];
+    protos.extend(rpc_protos);
     tonic_build::configure()
         .build_server(true)
         .include_file("mod.rs")
         .out_dir(&out_dir)
-        .compile_protos(&[proto], &[proto_dir])?;
+        .compile_protos(&protos, &[proto_dir])?;
 
     Ok(())
 }
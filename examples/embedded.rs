@@ -0,0 +1,35 @@
+use vectaraft::catalog::{Catalog, IdFilter, PointWrite};
+use vectaraft::types::Metric;
+
+fn main() {
+    // No server or WAL: the catalog is used directly in-process.
+    let catalog = Catalog::default();
+    catalog.create_collection("docs".into(), 3, Metric::Cosine);
+    let docs = catalog.get("docs").expect("collection was just created");
+
+    let inserted = docs
+        .upsert_points(vec![
+            PointWrite { id: "a".into(), vector: vec![1.0, 0.0, 0.0], payload_json: r#"{"lang":"en"}"#.into(), expires_at_ms: None },
+            PointWrite { id: "b".into(), vector: vec![0.0, 1.0, 0.0], payload_json: r#"{"lang":"de"}"#.into(), expires_at_ms: None },
+            PointWrite { id: "c".into(), vector: vec![0.7, 0.7, 0.0], payload_json: r#"{"lang":"en"}"#.into(), expires_at_ms: None },
+        ])
+        .expect("dimensions match");
+    println!(
+        "inserted {inserted} points into 'docs' (dim={:?}, metric={:?}, len={:?})",
+        docs.dim(),
+        docs.metric(),
+        docs.len()
+    );
+
+    let (hits, _) = docs
+        .search(vec![1.0, 0.1, 0.0], 2, None, vec![("lang".into(), "en".into())], IdFilter::default(), None, vec![], None, None)
+        .expect("query dimension matches");
+    for (id, score, _) in hits {
+        println!("hit {id} score={score:.3}");
+    }
+
+    let view = docs.iter_points().expect("collection exists");
+    for (id, vector, payload) in view.iter() {
+        println!("{id}: {vector:?} {payload}");
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use vectaraft::server::embedded::{point, EmbeddedClient};
+use vectaraft::server::state::{DbState, DbStateConfig};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Full request handling (validation, metrics hooks) without a socket.
+    // Memory-only here; point `wal_path` somewhere to make it durable.
+    let state = Arc::new(DbState::with_config(DbStateConfig { enable_wal: false, ..DbStateConfig::default() }));
+    let client = EmbeddedClient::new(state);
+
+    client.create_collection("docs", 3, "cosine").await?;
+    let resp = client
+        .upsert(
+            "docs",
+            vec![
+                point("a", vec![1.0, 0.0, 0.0], r#"{"lang":"en"}"#),
+                point("b", vec![0.0, 1.0, 0.0], r#"{"lang":"de"}"#),
+                point("", vec![0.7, 0.7, 0.0], r#"{"lang":"en"}"#),
+            ],
+        )
+        .await?;
+    println!("upserted {} points: {:?}", resp.upserted, resp.ids);
+
+    for hit in client.query_filtered("docs", vec![1.0, 0.1, 0.0], 2, &[("lang", "en")]).await? {
+        println!("hit {} score={:.3} {}", hit.id, hit.score, hit.payload_json);
+    }
+    Ok(())
+}
@@ -1,11 +1,32 @@
-use std::collections::HashMap;
-use parking_lot::RwLock;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use parking_lot::lock_api::ArcRwLockReadGuard;
+use parking_lot::{RawRwLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::index::flat::FlatIndex;
-use crate::types::Metric;
+use crate::index::flat::{BatchError, FlatIndex, RemovedRow};
+use crate::storage::snapshot;
+use crate::storage::wal::WalRecord;
+use crate::types::{is_zero_norm, EmptyQuery, IdKind, Metric, PayloadStore, ScorePrecision, VectorLayout, ZeroNorm};
 use rayon::prelude::*;
 use serde_json::Value;
+use thiserror::Error;
+
+/// Collections with at least this many points are scanned in parallel unless
+/// the catalog or the collection configures otherwise.
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 1024;
+
+/// Largest collection `find_duplicates` will scan; the pairwise comparison
+/// is quadratic in the point count.
+pub const MAX_DUPLICATE_SCAN_POINTS: usize = 5_000;
+
+/// A blocked-layout search whose admitted candidates number fewer than
+/// 1/this of the collection scores them row by row instead of scoring every
+/// block. Gathering a row costs roughly a block's worth of strided reads.
+const SPARSE_CANDIDATE_RATIO: usize = 16;
 
 #[derive(Clone)]
 pub struct Collection {
@@ -13,6 +34,38 @@ pub struct Collection {
     pub dim: usize,
     pub metric: Metric,
     pub index: FlatIndex, // v1: flat index only
+    /// Per-collection override of the catalog-wide parallel scan threshold.
+    pub parallel_threshold: Option<usize>,
+    /// Vectors are unit length, so cosine scores as a plain dot product.
+    pub assume_normalized: bool,
+    /// Applied by queries that leave `top_k` unset.
+    pub default_top_k: Option<u32>,
+    /// Applied by queries that leave `with_payloads` unset.
+    pub default_with_payloads: bool,
+    // Catalog-wide threshold, stamped in when the collection is created.
+    default_parallel_threshold: usize,
+    // Catalog-wide, stamped in when the collection is created.
+    score_precision: ScorePrecision,
+    // Bumped on every mutation so optimistic rebuilds can detect concurrent writes.
+    generation: u64,
+    // Rows deleted since the index was last rebuilt. Deletes compact the rows
+    // in place, but their storage stays allocated until a rebuild.
+    freed_rows: usize,
+    // Shared by clones, and bumped under the read lock by searches.
+    access: Arc<AccessCounts>,
+    // Where the points were last spilled, if ever; see `Catalog::with_memory_budget`.
+    spill: Option<SpillFile>,
+    // The points live only in `spill`; `index` is an empty placeholder.
+    evicted: bool,
+}
+
+/// A collection's points written out by an eviction.
+#[derive(Clone, Debug)]
+struct SpillFile {
+    path: PathBuf,
+    // The collection generation the file holds; a later one means it is stale.
+    generation: u64,
+    points: usize,
 }
 
 impl Collection {
@@ -22,6 +75,137 @@ impl Collection {
             dim,
             metric,
             index: FlatIndex::new(dim, metric),
+            parallel_threshold: None,
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            default_parallel_threshold: DEFAULT_PARALLEL_THRESHOLD,
+            score_precision: ScorePrecision::default(),
+            generation: 0,
+            freed_rows: 0,
+            access: Arc::default(),
+            spill: None,
+            evicted: false,
+        }
+    }
+
+    /// False while the collection is evicted to disk. Going through a
+    /// `CollectionHandle` loads it back.
+    pub fn is_resident(&self) -> bool {
+        !self.evicted
+    }
+
+    /// Approximate heap bytes of the points held in memory; 0 while evicted.
+    /// See `FlatIndex::memory_bytes`.
+    pub fn memory_bytes(&self) -> usize {
+        self.index.memory_bytes()
+    }
+
+    /// Live points, counted from the spill file while evicted.
+    fn point_count(&self) -> usize {
+        match &self.spill {
+            Some(spill) if self.evicted => spill.points,
+            _ => self.index.len(),
+        }
+    }
+
+    /// Writes the points to `path` unless the spill file is already current,
+    /// then frees them. Returns the bytes freed.
+    fn evict(&mut self, path: impl FnOnce() -> PathBuf) -> anyhow::Result<usize> {
+        let freed = self.memory_bytes();
+        if self.spill.as_ref().is_none_or(|spill| spill.generation != self.generation) {
+            let path = self.spill.take().map_or_else(path, |spill| spill.path);
+            let points = self.save_snapshot(&path)?;
+            self.spill = Some(SpillFile { path, generation: self.generation, points });
+        }
+        let mut placeholder = FlatIndex::new(self.dim, self.index.metric);
+        placeholder.set_layout(self.index.layout);
+        placeholder.set_payload_store(self.index.payload_store);
+        placeholder.zero_norm = self.index.zero_norm;
+        let _ = placeholder.set_id_kind(self.index.id_kind());
+        self.index = placeholder;
+        self.freed_rows = 0;
+        self.evicted = true;
+        Ok(freed)
+    }
+
+    /// Reads an evicted collection's points back from its spill file, which
+    /// stays current until the next write.
+    fn reload(&mut self) -> anyhow::Result<()> {
+        let Some(spill) = &self.spill else { anyhow::bail!("collection '{}' has no spill file", self.name) };
+        let count = spill.points;
+        let (mut ids, mut vectors, mut payloads, mut expires) =
+            (Vec::with_capacity(count), Vec::with_capacity(count), Vec::with_capacity(count), Vec::with_capacity(count));
+        for record in snapshot::read(&spill.path)? {
+            if let WalRecord::Upsert { id, vector, payload_json, expires_at_ms, .. } = record {
+                ids.push(id);
+                vectors.push(vector);
+                payloads.push(payload_json);
+                expires.push(expires_at_ms);
+            }
+        }
+        self.index.restore_batch(ids, vectors, payloads, expires)?;
+        self.evicted = false;
+        Ok(())
+    }
+
+    /// Searches and upserts served through `CollectionHandle` since the
+    /// collection was created or loaded in this process.
+    pub fn access(&self) -> &AccessCounts {
+        &self.access
+    }
+
+    /// Whether searches scan this collection on the rayon pool rather than
+    /// on the calling thread.
+    pub fn runs_parallel(&self) -> bool {
+        self.index.len() >= self.parallel_threshold.unwrap_or(self.default_parallel_threshold)
+    }
+
+    /// Admitted candidates mapped through `f`, in storage order, plus the
+    /// number of candidates evaluated and whether that was only a sample.
+    /// With an include list only the rows of those ids are visited; otherwise
+    /// every stored point is. `max_scan` thins either set to an evenly
+    /// strided sample of at most that many rows.
+    fn scan<T, F>(
+        &self,
+        filters: &[(String, String)],
+        ids: &IdFilter,
+        max_scan: Option<usize>,
+        f: F,
+    ) -> (Vec<T>, usize, bool)
+    where
+        T: Send,
+        F: Fn(usize) -> T + Sync + Send,
+    {
+        let now = now_ms();
+        let admits = |idx: &usize| !self.index.is_expired(*idx, now) && self.admits(*idx, filters, &ids.exclude);
+        let Some(include) = &ids.include else {
+            let stride = scan_stride(self.index.len(), max_scan);
+            let sampled = self.index.len().div_ceil(stride);
+            let admitted = if self.runs_parallel() {
+                (0..sampled).into_par_iter().map(|i| i * stride).filter(admits).map(f).collect()
+            } else {
+                (0..sampled).map(|i| i * stride).filter(admits).map(f).collect()
+            };
+            return (admitted, sampled, stride > 1);
+        };
+        let mut rows: Vec<usize> = include.iter().flat_map(|id| self.index.positions(id)).copied().collect();
+        rows.sort_unstable();
+        let stride = scan_stride(rows.len(), max_scan);
+        if stride > 1 {
+            rows = rows.into_iter().step_by(stride).collect();
+        }
+        let evaluated = rows.len();
+        (rows.into_iter().filter(admits).map(f).collect(), evaluated, stride > 1)
+    }
+
+    /// The metric actually computed for `metric`: on unit vectors cosine
+    /// equals the inner product, so the norm computation is skipped.
+    fn scoring_metric(&self, metric: Metric) -> Metric {
+        if metric == Metric::Cosine && self.assume_normalized {
+            Metric::IP
+        } else {
+            metric
         }
     }
 
@@ -29,103 +213,942 @@ impl Collection {
         vector.len() == self.dim
     }
 
-    pub fn upsert_batch(
-        &mut self,
-        ids: Vec<String>,
-        vectors: Vec<Vec<f32>>,
-        payloads: Vec<String>,
-    ) -> usize {
-        let count = vectors.len();
+    /// Iterates stored points as `(id, vector, payload_json)` in index order.
+    pub fn iter_points(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, [f32]>, &str)> {
+        (0..self.index.len()).map(move |idx| (self.index.id(idx), self.vector(idx), self.index.payload(idx)))
+    }
+
+    /// Mean of the vectors stored under `ids`, using the latest live row of
+    /// each id. Fails with the ids that have no live row.
+    pub fn centroid(&self, ids: &[String]) -> Result<Vec<f32>, Vec<String>> {
+        let now = now_ms();
+        let mut sum = vec![0.0f32; self.dim];
+        let mut missing = Vec::new();
+        for id in ids {
+            match self.live_row(id, now) {
+                Some(idx) => sum.iter_mut().zip(self.vector(idx).iter()).for_each(|(s, x)| *s += x),
+                None => missing.push(id.clone()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+        let n = ids.len() as f32;
+        sum.iter_mut().for_each(|s| *s /= n);
+        Ok(sum)
+    }
+
+    /// The stored vector of `id`'s most recent unexpired row.
+    pub fn latest_vector(&self, id: &str) -> Option<Vec<f32>> {
+        self.live_row(id, now_ms()).map(|idx| self.vector(idx).into_owned())
+    }
+
+    fn live_row(&self, id: &str, now: i64) -> Option<usize> {
+        self.index.positions(id).iter().rev().copied().find(|&idx| !self.index.is_expired(idx, now))
+    }
+
+    /// Appends `points`, all or none; see `FlatIndex::add_batch`.
+    pub fn upsert_batch(&mut self, points: Vec<PointWrite>) -> Result<usize, BatchError> {
+        self.append(points, true)
+    }
+
+    /// Like `upsert_batch`, for points being replayed; see
+    /// `FlatIndex::restore_batch`.
+    pub fn restore_batch(&mut self, points: Vec<PointWrite>) -> Result<usize, BatchError> {
+        self.append(points, false)
+    }
+
+    /// The error `restore_batch` would fail `points` with, without applying
+    /// any of them.
+    pub fn check_restore(&self, points: &[PointWrite]) -> Result<(), BatchError> {
+        points
+            .iter()
+            .enumerate()
+            .try_for_each(|(position, point)| self.index.check_shape(position, &point.id, &point.vector))
+    }
+
+    fn append(&mut self, points: Vec<PointWrite>, admit: bool) -> Result<usize, BatchError> {
+        let count = points.len();
         if count == 0 {
-            return 0;
+            return Ok(0);
+        }
+        let mut ids = Vec::with_capacity(count);
+        let mut vectors = Vec::with_capacity(count);
+        let mut payloads = Vec::with_capacity(count);
+        let mut expires = Vec::with_capacity(count);
+        for point in points {
+            ids.push(point.id);
+            vectors.push(point.vector);
+            payloads.push(point.payload_json);
+            expires.push(point.expires_at_ms);
+        }
+        if admit {
+            self.index.add_batch(ids, vectors, payloads, expires)?;
+        } else {
+            self.index.restore_batch(ids, vectors, payloads, expires)?;
+        }
+        self.generation += 1;
+        Ok(count)
+    }
+
+    /// Changes the default scoring metric. Stored vectors are left untouched.
+    pub fn set_metric(&mut self, metric: Metric) {
+        self.metric = metric;
+        self.index.metric = metric;
+        self.generation += 1;
+    }
+
+    /// Drops all points but keeps name, dimension, and metric. Returns the
+    /// number of points removed.
+    pub fn clear(&mut self) -> usize {
+        let removed = self.index.len();
+        self.index.clear();
+        self.generation += 1;
+        self.freed_rows += removed;
+        removed
+    }
+
+    /// Share of the rows allocated since the last rebuild that deletes have
+    /// since freed, in [0, 1]. `RebuildIndex` returns their memory.
+    pub fn freed_ratio(&self) -> f64 {
+        let allocated = self.index.len() + self.freed_rows;
+        if allocated == 0 { 0.0 } else { self.freed_rows as f64 / allocated as f64 }
+    }
+
+    /// Returns up to `limit` points starting at `offset` in storage order as
+    /// `(id, vector, payload)`; vectors and payloads are left empty unless requested.
+    /// Expired points the sweeper has not removed yet are skipped, as in
+    /// searches. Also returns the storage position the next page starts at.
+    pub fn scroll(
+        &self,
+        offset: usize,
+        limit: usize,
+        with_vectors: bool,
+        with_payloads: bool,
+    ) -> (Vec<(String, Vec<f32>, String)>, usize) {
+        let now = now_ms();
+        let mut page = Vec::new();
+        let mut next = offset.min(self.index.len());
+        while next < self.index.len() && page.len() < limit {
+            let idx = next;
+            next += 1;
+            if self.index.is_expired(idx, now) {
+                continue;
+            }
+            let vector = if with_vectors {
+                self.index.row(idx).into_owned()
+            } else {
+                Vec::new()
+            };
+            let payload = if with_payloads { self.index.payload(idx).to_string() } else { String::new() };
+            page.push((self.index.id(idx).into_owned(), vector, payload));
+        }
+        (page, next)
+    }
+
+    /// Pages through points whose payload matches every filter, returning
+    /// `(id, payload)` with the payload cut down to `fields` (all of it when
+    /// empty), plus the total number of matches.
+    pub fn fetch_by_filter(
+        &self,
+        filters: &[(String, String)],
+        fields: &[String],
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<(String, String)>, usize) {
+        let (rows, _, _) = self.scan(filters, &IdFilter::default(), None, |idx| idx);
+        let page = rows
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|&idx| (self.index.id(idx).into_owned(), project_payload(self.index.payload(idx), fields)))
+            .collect();
+        (page, rows.len())
+    }
+
+    /// Order-independent hash of the live points, each hashed over its id,
+    /// vector bytes and payload. Per-point hashes are summed rather than
+    /// XORed so that duplicate rows do not cancel out. Returns the checksum
+    /// and the number of points it covers.
+    pub fn checksum(&self) -> (u64, usize) {
+        let now = now_ms();
+        let mut sum = 0u64;
+        let mut points = 0;
+        for (idx, (id, vector, payload)) in self.iter_points().enumerate() {
+            if self.index.is_expired(idx, now) {
+                continue;
+            }
+            let mut h = Fnv1a::default();
+            h.field(id.as_bytes());
+            h.field(&vector.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>());
+            h.field(payload.as_bytes());
+            sum = sum.wrapping_add(h.0);
+            points += 1;
+        }
+        (sum, points)
+    }
+
+    /// Every pair of live points at least as close as `threshold` under the
+    /// collection metric, closest first, as `(id_a, id_b, raw)`. For distance
+    /// metrics `raw` is the distance (squared for L2) and pairs need
+    /// `raw <= threshold`; for similarities they need `raw >= threshold`.
+    /// Compares all pairs, so callers should respect `MAX_DUPLICATE_SCAN_POINTS`.
+    pub fn find_duplicates(&self, threshold: f32) -> Vec<(String, String, f32)> {
+        let metric = self.scoring_metric(self.metric);
+        let sign = if metric.is_distance() { -1.0 } else { 1.0 };
+        let now = now_ms();
+        let live: Vec<usize> = (0..self.index.len()).filter(|idx| !self.index.is_expired(*idx, now)).collect();
+        let rows: Vec<Cow<'_, [f32]>> = live.iter().map(|idx| self.vector(*idx)).collect();
+        let pairs_from = |i: usize| -> Vec<(usize, usize, f32)> {
+            (i + 1..rows.len())
+                .filter_map(|j| {
+                    let raw = sign * score(metric, self.score_precision, &rows[i], &rows[j]);
+                    (sign * raw >= sign * threshold).then_some((live[i], live[j], raw))
+                })
+                .collect()
+        };
+        let mut pairs: Vec<(usize, usize, f32)> = if self.runs_parallel() {
+            (0..rows.len()).into_par_iter().flat_map_iter(pairs_from).collect()
+        } else {
+            (0..rows.len()).flat_map(pairs_from).collect()
+        };
+        pairs.sort_by(|a, b| (sign * b.2).partial_cmp(&(sign * a.2)).unwrap_or(std::cmp::Ordering::Equal));
+        pairs
+            .into_iter()
+            .map(|(a, b, raw)| (self.index.id(a).into_owned(), self.index.id(b).into_owned(), raw))
+            .collect()
+    }
+
+    /// Removes the points with the given ids and returns them.
+    pub fn remove_points(&mut self, ids: &[String]) -> Vec<PointWrite> {
+        let ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        let removed = self.index.remove(&ids);
+        self.removed_rows(removed)
+    }
+
+    /// Drops every point whose TTL ran out at or before `now_ms` and returns
+    /// the ids removed.
+    pub fn remove_expired(&mut self, now_ms: i64) -> Vec<String> {
+        let removed = self.index.remove_expired(now_ms);
+        self.removed_rows(removed).into_iter().map(|p| p.id).collect()
+    }
+
+    fn removed_rows(&mut self, removed: Vec<RemovedRow>) -> Vec<PointWrite> {
+        if !removed.is_empty() {
+            self.generation += 1;
+            self.freed_rows += removed.len();
+        }
+        removed
+            .into_iter()
+            .map(|row| PointWrite {
+                id: row.id,
+                vector: row.vector,
+                payload_json: row.payload,
+                expires_at_ms: row.expires_at_ms,
+            })
+            .collect()
+    }
+
+    /// The records that recreate this collection: its CreateCollection with
+    /// the current config, then an Upsert per point still live at `ts`.
+    pub fn snapshot_records(&self, ts: i64) -> Vec<WalRecord> {
+        let mut records = vec![WalRecord::CreateCollection {
+            name: self.name.clone(),
+            dim: self.dim as u32,
+            metric: self.metric.as_str().to_string(),
+            parallel_threshold: self.parallel_threshold.map(|t| t as u64),
+            layout: (self.index.layout != VectorLayout::RowMajor).then(|| self.index.layout.as_str().to_string()),
+            assume_normalized: self.assume_normalized,
+            default_top_k: self.default_top_k,
+            default_with_payloads: self.default_with_payloads,
+            payload_store: (self.index.payload_store != PayloadStore::Inline)
+                .then(|| self.index.payload_store.as_str().to_string()),
+            id_type: (self.index.id_kind() != IdKind::String).then(|| self.index.id_kind().as_str().to_string()),
+            ts_ms: ts,
+        }];
+        for (idx, (id, vector, payload)) in self.iter_points().enumerate() {
+            if self.index.is_expired(idx, ts) {
+                continue;
+            }
+            records.push(WalRecord::Upsert {
+                collection: self.name.clone(),
+                id: id.to_string(),
+                vector: vector.to_vec(),
+                payload_json: payload.to_string(),
+                expires_at_ms: self.index.expires_at_ms[idx],
+                ts_ms: ts,
+            });
         }
-        self.index.add_batch(ids, vectors, payloads);
-        count
+        records
+    }
+
+    /// Writes this collection alone to `path` in the snapshot file format and
+    /// returns the number of points saved. The file can be restored with
+    /// `DbState::restore_collection` independently of the catalog snapshot.
+    pub fn save_snapshot(&self, path: &Path) -> anyhow::Result<usize> {
+        let records = self.snapshot_records(now_ms());
+        snapshot::write(path, &records)?;
+        Ok(records.len() - 1)
+    }
+
+    /// Builds a fresh index from the current points without touching `self`.
+    pub fn rebuild(&self) -> FlatIndex {
+        self.index.rebuilt()
+    }
+
+    fn admits(&self, idx: usize, filters: &[(String, String)], exclude_ids: &HashSet<String>) -> bool {
+        if !exclude_ids.is_empty() && exclude_ids.contains(self.index.id(idx).as_ref()) {
+            return false;
+        }
+        filters.is_empty() || payload_matches_filters(self.index.payload(idx), filters)
+    }
+
+    fn vector(&self, idx: usize) -> Cow<'_, [f32]> {
+        self.index.row(idx)
     }
 
+    /// Ranks the collection against `query`. With `max_scan`, only a strided
+    /// sample of the collection is scored; see `scan_stride`.
+    #[allow(clippy::too_many_arguments)]
     pub fn search(
         &self,
         query: &[f32],
         top_k: usize,
         metric_override: Option<Metric>,
         filters: Option<&[(String, String)]>,
-    ) -> Vec<(String, f32, String)> {
-        let metric = metric_override.unwrap_or(self.metric);
-        let dim = self.index.dim;
+        ids: &IdFilter,
+        boosts: &[ScoreBoost],
+        max_scan: Option<usize>,
+        group_by: Option<&GroupBy>,
+    ) -> (Vec<SearchHit>, SearchStats) {
+        let requested = metric_override.unwrap_or(self.metric);
+        let metric = self.scoring_metric(requested);
         let filters = filters.unwrap_or(&[]);
 
-        let mut scored: Vec<(usize, f32)> = (0..self.index.len())
-            .into_par_iter()
-            .filter_map(|idx| {
-                if !filters.is_empty() {
-                    let payload = self.index.payloads.get(idx)?.as_str();
-                    if !payload_matches_filters(payload, filters) {
-                        return None;
-                    }
-                }
+        // Blocked storage scores whole blocks at once, so admission runs first:
+        // when filters leave only a few candidates, gathering and scoring just
+        // those rows beats scoring every block. With an include list the scan
+        // already visits only the listed rows, and a sampled scan must not
+        // score the rows it skipped. The block kernels sum in f32 only.
+        let (mut scored, evaluated, sampled) = if self.index.layout == VectorLayout::Blocked && ids.include.is_none() {
+            let (rows, evaluated, sampled) = self.scan(filters, ids, max_scan, |idx| idx);
+            let per_row = sampled || self.score_precision == ScorePrecision::F64;
+            let scored = if per_row || rows.len().saturating_mul(SPARSE_CANDIDATE_RATIO) < self.index.len() {
+                rows.into_iter().map(|idx| (idx, score(metric, self.score_precision, query, &self.vector(idx)))).collect()
+            } else {
+                let scores = self.index.blocked_scores(metric, query, self.runs_parallel());
+                rows.into_iter().map(|idx| (idx, scores[idx])).collect()
+            };
+            (scored, evaluated, sampled)
+        } else {
+            self.scan(filters, ids, max_scan, |idx| (idx, score(metric, self.score_precision, query, &self.vector(idx))))
+        };
+        if self.excludes_zero_norm(&[requested]) {
+            let query_zero = is_zero_norm(query);
+            scored.retain(|(idx, _)| !query_zero && !is_zero_norm(&self.vector(*idx)));
+        }
+        let stats = SearchStats { evaluated, rejected: evaluated - scored.len(), sampled };
+        (self.rank(scored, top_k, boosts, group_by), stats)
+    }
 
-                let offset = idx * dim;
-                let vector = &self.index.vectors[offset..offset + dim];
-                let score = match metric {
-                    Metric::L2 => -query
-                        .iter()
-                        .zip(vector)
-                        .map(|(a, b)| {
-                            let d = a - b;
-                            d * d
-                        })
-                        .sum::<f32>(),
-                    Metric::IP => query.iter().zip(vector).map(|(a, b)| a * b).sum(),
-                    Metric::Cosine => {
-                        let dot: f32 = query.iter().zip(vector).map(|(a, b)| a * b).sum();
-                        let nq = query.iter().map(|x| x * x).sum::<f32>().sqrt();
-                        let nv = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
-                        if nq == 0.0 || nv == 0.0 { 0.0 } else { dot / (nq * nv) }
-                    }
-                };
-                Some((idx, score))
+    /// Scores every candidate under two metrics, min-max normalizes each set of
+    /// scores to [0, 1] over the admitted candidates, and ranks by
+    /// `weight * a + (1 - weight) * b`. A metric whose scores are all equal
+    /// contributes 0 for every candidate. Boosts are added to the blend.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_hybrid(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        hybrid: HybridScore,
+        filters: Option<&[(String, String)]>,
+        ids: &IdFilter,
+        boosts: &[ScoreBoost],
+        max_scan: Option<usize>,
+        group_by: Option<&GroupBy>,
+    ) -> (Vec<SearchHit>, SearchStats) {
+        let filters = filters.unwrap_or(&[]);
+        let (metric_a, metric_b) = (self.scoring_metric(hybrid.metric_a), self.scoring_metric(hybrid.metric_b));
+        let (mut raw, evaluated, sampled): (Vec<(usize, f32, f32)>, usize, bool) = self.scan(filters, ids, max_scan, |idx| {
+            let vector = self.vector(idx);
+            let precision = self.score_precision;
+            (idx, score(metric_a, precision, query, &vector), score(metric_b, precision, query, &vector))
+        });
+        if self.excludes_zero_norm(&[hybrid.metric_a, hybrid.metric_b]) {
+            let query_zero = is_zero_norm(query);
+            raw.retain(|(idx, ..)| !query_zero && !is_zero_norm(&self.vector(*idx)));
+        }
+
+        let range = |pick: fn(&(usize, f32, f32)) -> f32| {
+            raw.iter().map(pick).fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), s| (lo.min(s), hi.max(s)))
+        };
+        let normalize = |s: f32, (lo, hi): (f32, f32)| if hi > lo { (s - lo) / (hi - lo) } else { 0.0 };
+        let (range_a, range_b) = (range(|r| r.1), range(|r| r.2));
+        let scored = raw
+            .iter()
+            .map(|&(idx, a, b)| {
+                let combined = hybrid.weight * normalize(a, range_a) + (1.0 - hybrid.weight) * normalize(b, range_b);
+                (idx, combined)
             })
             .collect();
+        let stats = SearchStats { evaluated, rejected: evaluated - raw.len(), sampled };
+        (self.rank(scored, top_k, boosts, group_by), stats)
+    }
 
+    /// Whether scoring under any of `metrics` drops zero-norm vectors; see
+    /// `ZeroNorm::Exclude`.
+    fn excludes_zero_norm(&self, metrics: &[Metric]) -> bool {
+        self.index.zero_norm == ZeroNorm::Exclude && metrics.contains(&Metric::Cosine)
+    }
+
+    fn rank(
+        &self,
+        mut scored: Vec<(usize, f32)>,
+        top_k: usize,
+        boosts: &[ScoreBoost],
+        group_by: Option<&GroupBy>,
+    ) -> Vec<SearchHit> {
         if scored.is_empty() || top_k == 0 {
             return Vec::new();
         }
+        if !boosts.is_empty() {
+            for (idx, score) in &mut scored {
+                *score += ScoreBoost::total(boosts, self.index.payload(*idx));
+            }
+        }
 
-        let k = top_k.min(scored.len());
-        scored.select_nth_unstable_by(k - 1, |a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        scored.truncate(k);
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        let by_score = |a: &(usize, f32), b: &(usize, f32)| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal);
+        if let Some(group_by) = group_by {
+            // Which candidates survive the cap depends on everything ranked
+            // above them, so the whole list is ordered before taking top_k.
+            scored.sort_by(by_score);
+            let mut taken: HashMap<Option<String>, usize> = HashMap::new();
+            scored.retain(|(idx, _)| {
+                let group = group_by.group_of(self.index.payload(*idx));
+                if group.is_none() && !group_by.cap_ungrouped {
+                    return true;
+                }
+                let count = taken.entry(group).or_default();
+                *count += 1;
+                *count <= group_by.per_group_limit
+            });
+            scored.truncate(top_k);
+        } else {
+            let k = top_k.min(scored.len());
+            scored.select_nth_unstable_by(k - 1, by_score);
+            scored.truncate(k);
+            scored.sort_by(by_score);
+        }
 
         scored
             .into_iter()
             .map(|(idx, score)| {
-                let id = self.index.ids.get(idx).cloned().unwrap_or_default();
-                let payload = self.index.payloads.get(idx).cloned().unwrap_or_default();
+                let id = self.index.id(idx).into_owned();
+                let payload = self.index.payload(idx).to_string();
                 (id, score, payload)
             })
             .collect()
     }
 }
 
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Similarity of `vector` to `query` under `metric`; higher is better.
+fn score(metric: Metric, precision: ScorePrecision, query: &[f32], vector: &[f32]) -> f32 {
+    if precision == ScorePrecision::F64 {
+        if let Some(score) = score_f64(metric, query, vector) {
+            return score;
+        }
+    }
+    match metric {
+        Metric::L2 => -query
+            .iter()
+            .zip(vector)
+            .map(|(a, b)| {
+                let d = a - b;
+                d * d
+            })
+            .sum::<f32>(),
+        Metric::IP => query.iter().zip(vector).map(|(a, b)| a * b).sum(),
+        Metric::Cosine => {
+            let dot: f32 = query.iter().zip(vector).map(|(a, b)| a * b).sum();
+            let nq = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+            let nv = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if nq == 0.0 || nv == 0.0 { 0.0 } else { dot / (nq * nv) }
+        }
+        Metric::Chebyshev => -query
+            .iter()
+            .zip(vector)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max),
+        Metric::Jaccard => {
+            let (inter, union) = query.iter().zip(vector).fold((0u32, 0u32), |(i, u), (a, b)| {
+                let (a, b) = (*a != 0.0, *b != 0.0);
+                (i + (a && b) as u32, u + (a || b) as u32)
+            });
+            if union == 0 { 0.0 } else { inter as f32 / union as f32 }
+        }
+    }
+}
+
+/// `score` with the sums accumulated in `f64`; `None` for the metrics that
+/// involve no sums.
+fn score_f64(metric: Metric, query: &[f32], vector: &[f32]) -> Option<f32> {
+    let dot = || query.iter().zip(vector).map(|(a, b)| *a as f64 * *b as f64).sum::<f64>();
+    let norm = |v: &[f32]| v.iter().map(|x| *x as f64 * *x as f64).sum::<f64>().sqrt();
+    let score = match metric {
+        Metric::L2 => -query
+            .iter()
+            .zip(vector)
+            .map(|(a, b)| {
+                let d = *a as f64 - *b as f64;
+                d * d
+            })
+            .sum::<f64>(),
+        Metric::IP => dot(),
+        Metric::Cosine => {
+            let (nq, nv) = (norm(query), norm(vector));
+            if nq == 0.0 || nv == 0.0 { 0.0 } else { dot() / (nq * nv) }
+        }
+        Metric::Chebyshev | Metric::Jaccard => return None,
+    };
+    Some(score as f32)
+}
+
+/// Step between scanned rows when at most `max_scan` of `len` rows may be
+/// scored: 1 (every row) when unset or not smaller than `len`. Sampling every
+/// n-th row in storage order spreads the sample over old and new points, but
+/// it is not random: a point outside the sample is never returned, however
+/// close, so the best hit is found only with probability of roughly
+/// `max_scan / len`, and hits depend on storage order rather than the query.
+/// `Some(0)` scans a single row.
+pub fn scan_stride(len: usize, max_scan: Option<usize>) -> usize {
+    match max_scan {
+        Some(cap) if cap < len => len.div_ceil(cap.max(1)),
+        _ => 1,
+    }
+}
+
+/// Diversifies results: at most `per_group_limit` hits share a value of the
+/// payload field `field`. Points whose payload lacks the field, or holds
+/// something other than a string, number or bool there, share one
+/// "ungrouped" group, which is capped like any other unless
+/// `cap_ungrouped` is false.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupBy {
+    pub field: String,
+    pub per_group_limit: usize,
+    pub cap_ungrouped: bool,
+}
+
+impl GroupBy {
+    /// Keeps only the best hit per value of `field`; points without one are
+    /// not duplicates of each other, so all of them are kept.
+    pub fn dedup(field: String) -> Self {
+        Self { field, per_group_limit: 1, cap_ungrouped: false }
+    }
+
+    /// The group `payload` falls in; `None` is the ungrouped bucket.
+    fn group_of(&self, payload: &str) -> Option<String> {
+        let Ok(Value::Object(map)) = serde_json::from_str::<Value>(payload) else { return None; };
+        match map.get(&self.field)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Id-based restrictions on which points a search may return.
+#[derive(Clone, Debug, Default)]
+pub struct IdFilter {
+    /// Never returned, and not counted toward `top_k`.
+    pub exclude: HashSet<String>,
+    /// When set, only these ids are scored; the rest of the collection is
+    /// not scanned.
+    pub include: Option<HashSet<String>>,
+}
+
+/// A ranked search result: `(id, score, payload_json)`.
+pub type SearchHit = (String, f32, String);
+
+/// Candidate counts from one scan: every stored point (or every row of an
+/// included id) is evaluated, and those dropped by payload filters, excluded
+/// ids, or an expired TTL count as rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub evaluated: usize,
+    pub rejected: usize,
+    /// `max_scan` left candidates unscored, so a better match may have been
+    /// missed. Every returned score is still computed exactly.
+    pub sampled: bool,
+}
+
+/// Per-collection request tallies for capacity planning. WAL replay and
+/// snapshot loading are not counted.
+#[derive(Debug, Default)]
+pub struct AccessCounts {
+    queries: AtomicU64,
+    upserts: AtomicU64,
+    // Eviction clock reading at the last access through a handle.
+    last_used: AtomicU64,
+}
+
+impl AccessCounts {
+    /// Searches run, hybrid or not.
+    pub fn queries(&self) -> u64 {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    /// Upsert batches applied.
+    pub fn upserts(&self) -> u64 {
+        self.upserts.load(Ordering::Relaxed)
+    }
+}
+
+/// Soft filter: points whose payload matches every filter get `boost` added
+/// to their score before ranking; the rest keep their base score.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreBoost {
+    pub filters: Vec<(String, String)>,
+    pub boost: f32,
+}
+
+impl ScoreBoost {
+    pub fn applies_to(&self, payload: &str) -> bool {
+        payload_matches_filters(payload, &self.filters)
+    }
+
+    /// Sum of the boosts in `boosts` that apply to `payload`.
+    pub fn total(boosts: &[ScoreBoost], payload: &str) -> f32 {
+        boosts.iter().filter(|b| b.applies_to(payload)).map(|b| b.boost).sum()
+    }
+}
+
+/// Two metrics over the collection's vectors, blended by `weight` in [0, 1].
+#[derive(Clone, Copy, Debug)]
+pub struct HybridScore {
+    pub metric_a: Metric,
+    pub metric_b: Metric,
+    pub weight: f32,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MoveError {
+    #[error("source collection not found")]
+    SourceNotFound,
+    #[error("target collection not found")]
+    TargetNotFound,
+    #[error("source and target must be different collections")]
+    SameCollection,
+    #[error("source and target must share dims, metric and id type")]
+    Incompatible,
+    #[error("target rejected the moved points: {0}")]
+    Rejected(BatchError),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum UpsertError {
+    #[error("collection not found")]
+    CollectionNotFound,
+    #[error(transparent)]
+    Batch(#[from] BatchError),
+    /// The commit step of `upsert_points_with` failed; nothing was applied.
+    #[error("{0}")]
+    Commit(String),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CreateError {
+    #[error("collection already exists")]
+    AlreadyExists,
+    #[error("collection limit of {0} reached")]
+    LimitReached(usize),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SwapError {
+    #[error("target collection not found")]
+    TargetNotFound,
+    #[error("source collection not found")]
+    SourceNotFound,
+    #[error("source and target must be different collections")]
+    SameCollection,
+}
+
+/// Points moved by `Catalog::move_points`, plus how many requested ids were absent.
+pub struct MovedPoints {
+    pub points: Vec<PointWrite>,
+    pub missing: usize,
+}
+
+#[derive(Clone, Debug)]
 pub struct PointWrite {
     pub id: String,
     pub vector: Vec<f32>,
     pub payload_json: String,
+    /// Unix milliseconds after which the point is hidden from queries and
+    /// eligible for sweeping; `None` never expires.
+    pub expires_at_ms: Option<i64>,
 }
 
-#[derive(Clone, Default)]
+/// A collection behind its own lock, so writes to different collections
+/// do not contend.
+type SharedCollection = Arc<RwLock<Collection>>;
+
+/// Lock order: the write gate comes first, then the map lock, then
+/// collection locks; the map lock is never taken while a collection lock is
+/// held.
+#[derive(Clone)]
 pub struct Catalog {
-    inner: Arc<RwLock<HashMap<String, Collection>>>,
+    inner: Arc<RwLock<HashMap<String, SharedCollection>>>,
+    parallel_threshold: usize,
+    empty_query: EmptyQuery,
+    score_precision: ScorePrecision,
+    zero_norm: ZeroNorm,
+    // Names, not collections: a pin may precede the collection it names.
+    pinned: Arc<RwLock<HashSet<String>>>,
+    eviction: Option<Arc<Eviction>>,
+    // Shared by writers that log as they apply, before any other lock;
+    // `snapshot_with` takes it exclusively so no logged write is half done
+    // while it marks the log and clones.
+    write_gate: Arc<RwLock<()>>,
+}
+
+/// Spilling of least-recently-used collections; see `Catalog::with_memory_budget`.
+struct Eviction {
+    dir: PathBuf,
+    budget_bytes: usize,
+    // Logical time, ticked by every access through a handle.
+    clock: AtomicU64,
+    next_file: AtomicU64,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::with_parallel_threshold(DEFAULT_PARALLEL_THRESHOLD)
+    }
 }
 
 impl Catalog {
+    /// A catalog whose collections scan in parallel once they hold
+    /// `threshold` points, unless a collection overrides it.
+    pub fn with_parallel_threshold(threshold: usize) -> Self {
+        Self {
+            inner: Arc::default(),
+            parallel_threshold: threshold,
+            empty_query: EmptyQuery::default(),
+            score_precision: ScorePrecision::default(),
+            zero_norm: ZeroNorm::default(),
+            pinned: Arc::default(),
+            eviction: None,
+            write_gate: Arc::default(),
+        }
+    }
+
+    /// Caps the approximate memory of resident collections at `budget_bytes`.
+    /// Over budget, the least recently used unpinned collections are written
+    /// to `spill_dir` and freed; the next access through a handle loads them
+    /// back. The most recently used collection always stays resident, even
+    /// alone over budget. Spill files left in `spill_dir` by an earlier
+    /// process are removed.
+    pub fn with_memory_budget(mut self, budget_bytes: usize, spill_dir: &Path) -> std::io::Result<Self> {
+        std::fs::create_dir_all(spill_dir)?;
+        for entry in std::fs::read_dir(spill_dir)? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if name.ends_with(".spill") || name.ends_with(".spill.tmp") {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        self.eviction = Some(Arc::new(Eviction {
+            dir: spill_dir.to_path_buf(),
+            budget_bytes,
+            clock: AtomicU64::new(0),
+            next_file: AtomicU64::new(0),
+        }));
+        Ok(self)
+    }
+
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.eviction.as_ref().map(|eviction| eviction.budget_bytes)
+    }
+
+    /// Whether the collection `name` is in memory rather than evicted.
+    pub fn is_resident(&self, name: &str) -> Option<bool> {
+        let coll = self.inner.read().get(name).cloned()?;
+        let resident = coll.read().is_resident();
+        Some(resident)
+    }
+
+    /// Approximate heap bytes of every resident collection's points.
+    pub fn resident_bytes(&self) -> usize {
+        let collections: Vec<SharedCollection> = self.inner.read().values().cloned().collect();
+        collections.iter().map(|coll| coll.read().memory_bytes()).sum()
+    }
+
+    /// Evicts least recently used collections until the resident ones fit
+    /// the memory budget, and returns the names evicted. Pinned collections,
+    /// the most recently used one, and any whose lock is held are skipped.
+    /// A no-op without a budget.
+    pub fn enforce_memory_budget(&self) -> Vec<String> {
+        let Some(eviction) = &self.eviction else { return Vec::new(); };
+        let collections: Vec<(String, SharedCollection)> =
+            self.inner.read().iter().map(|(name, coll)| (name.clone(), coll.clone())).collect();
+        let mut resident: Vec<(u64, usize, String, SharedCollection)> = collections
+            .into_iter()
+            .filter_map(|(name, coll)| {
+                let guard = coll.try_read()?;
+                let entry = (guard.access.last_used.load(Ordering::Relaxed), guard.memory_bytes());
+                drop(guard);
+                Some((entry.0, entry.1, name, coll))
+            })
+            .collect();
+        let mut total: usize = resident.iter().map(|(_, bytes, _, _)| bytes).sum();
+        if total <= eviction.budget_bytes {
+            return Vec::new();
+        }
+        resident.sort_by_key(|(last_used, ..)| *last_used);
+        resident.pop();
+        let mut evicted = Vec::new();
+        for (_, _, name, coll) in resident {
+            if total <= eviction.budget_bytes {
+                break;
+            }
+            if self.is_pinned(&name) {
+                continue;
+            }
+            let Some(mut guard) = coll.try_write() else { continue; };
+            if !guard.is_resident() {
+                continue;
+            }
+            let next_path = || eviction.dir.join(format!("{}.spill", eviction.next_file.fetch_add(1, Ordering::Relaxed)));
+            match guard.evict(next_path) {
+                Ok(freed) => {
+                    total = total.saturating_sub(freed);
+                    evicted.push(name);
+                }
+                Err(err) => tracing::error!(collection = %name, ?err, "failed to spill collection; keeping it resident"),
+            }
+        }
+        evicted
+    }
+
+    /// Reloads `coll` if it was evicted and marks it most recently used.
+    /// Returns whether it was reloaded, or `None` if its spill file could
+    /// not be read.
+    fn make_resident(&self, coll: &mut Collection) -> Option<bool> {
+        let reloaded = !coll.is_resident();
+        if reloaded {
+            if let Err(err) = coll.reload() {
+                tracing::error!(collection = %coll.name, ?err, "failed to reload evicted collection");
+                return None;
+            }
+        }
+        self.touch(coll);
+        Some(reloaded)
+    }
+
+    fn touch(&self, coll: &Collection) {
+        if let Some(eviction) = &self.eviction {
+            let now = eviction.clock.fetch_add(1, Ordering::Relaxed) + 1;
+            coll.access.last_used.store(now, Ordering::Relaxed);
+        }
+    }
+
+    /// Sets how searches treat an empty query vector.
+    pub fn with_empty_query(mut self, policy: EmptyQuery) -> Self {
+        self.empty_query = policy;
+        self
+    }
+
+    pub fn empty_query(&self) -> EmptyQuery {
+        self.empty_query
+    }
+
+    /// Marks the collection called `name`, now or once created, as one that
+    /// must stay resident in memory. Returns false if it already was.
+    pub fn pin(&self, name: &str) -> bool {
+        self.pinned.write().insert(name.to_string())
+    }
+
+    /// Returns false if `name` was not pinned.
+    pub fn unpin(&self, name: &str) -> bool {
+        self.pinned.write().remove(name)
+    }
+
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.pinned.read().contains(name)
+    }
+
+    /// Pinned names, sorted.
+    pub fn pinned(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.pinned.read().iter().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Sets the accumulator width collections created from now on score with.
+    pub fn with_score_precision(mut self, precision: ScorePrecision) -> Self {
+        self.score_precision = precision;
+        self
+    }
+
+    /// Sets how collections created from now on treat zero-norm vectors
+    /// under cosine.
+    pub fn with_zero_norm(mut self, policy: ZeroNorm) -> Self {
+        self.zero_norm = policy;
+        self
+    }
+
     pub fn create_collection(&self, name: String, dim: usize, metric: Metric) -> bool {
+        self.create_collection_limited(name, dim, metric, usize::MAX).is_ok()
+    }
+
+    /// Like `create_collection`, but refuses once the catalog already holds
+    /// `max_collections`. The count is checked under the same write lock as
+    /// the insert, so concurrent creates cannot overshoot it.
+    pub fn create_collection_limited(
+        &self,
+        name: String,
+        dim: usize,
+        metric: Metric,
+        max_collections: usize,
+    ) -> Result<(), CreateError> {
+        self.create_collection_with(Collection::new(name, dim, metric), max_collections, || {})
+    }
+
+    /// Like `create_collection_limited`, for a collection the caller has
+    /// already configured (layout, id type, defaults); the catalog-wide
+    /// settings are applied on top. `log` runs under the map write lock once
+    /// the collection is in place, so its WAL record lands before any write
+    /// to the new collection can be logged.
+    pub fn create_collection_with(
+        &self,
+        mut collection: Collection,
+        max_collections: usize,
+        log: impl FnOnce(),
+    ) -> Result<(), CreateError> {
+        let _gate = self.write_gate.read_recursive();
         let mut g = self.inner.write();
-        if g.contains_key(&name) {
-            return false;
+        if g.contains_key(&collection.name) {
+            return Err(CreateError::AlreadyExists);
         }
-        g.insert(name.clone(), Collection::new(name, dim, metric));
-        true
+        if g.len() >= max_collections {
+            return Err(CreateError::LimitReached(max_collections));
+        }
+        collection.default_parallel_threshold = self.parallel_threshold;
+        collection.score_precision = self.score_precision;
+        collection.index.zero_norm = self.zero_norm;
+        g.insert(collection.name.clone(), Arc::new(RwLock::new(collection)));
+        log();
+        Ok(())
     }
 
     pub fn get(&self, name: &str) -> Option<CollectionHandle> {
@@ -136,13 +1159,207 @@ impl Catalog {
         }
     }
 
+    /// Moves points between two compatible collections while holding both
+    /// of their write locks, so readers never observe a point in both or
+    /// neither. The map stays read-locked so neither collection can be
+    /// swapped out mid-move. `log` is called with the moved points before the
+    /// locks are released, so the move reaches the WAL in order with other
+    /// writes to either collection.
+    pub fn move_points_with(
+        &self,
+        source: &str,
+        target: &str,
+        ids: &[String],
+        log: impl FnOnce(&[PointWrite]),
+    ) -> Result<MovedPoints, MoveError> {
+        if source == target {
+            return Err(MoveError::SameCollection);
+        }
+        let gate = self.write_gate.read_recursive();
+        let g = self.inner.read();
+        let src = g.get(source).ok_or(MoveError::SourceNotFound)?;
+        let dst = g.get(target).ok_or(MoveError::TargetNotFound)?;
+        // Lock in name order so two opposite moves cannot deadlock.
+        let (mut src, mut dst) = if source < target {
+            let src = src.write();
+            (src, dst.write())
+        } else {
+            let dst = dst.write();
+            (src.write(), dst)
+        };
+        self.make_resident(&mut src).ok_or(MoveError::SourceNotFound)?;
+        self.make_resident(&mut dst).ok_or(MoveError::TargetNotFound)?;
+        if src.dim != dst.dim || src.metric != dst.metric || src.index.id_kind() != dst.index.id_kind() {
+            return Err(MoveError::Incompatible);
+        }
+
+        let points = src.remove_points(ids);
+        let requested: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        let found: HashSet<&str> = points.iter().map(|p| p.id.as_str()).collect();
+        let missing = requested.len() - found.len();
+
+        if let Err(err) = dst.upsert_batch(points.clone()) {
+            // Put the points back so a refused move loses nothing.
+            let _ = src.upsert_batch(points);
+            return Err(MoveError::Rejected(err));
+        }
+        log(&points);
+        drop((src, dst));
+        drop(g);
+        drop(gate);
+        self.enforce_memory_budget();
+        Ok(MovedPoints { points, missing })
+    }
+
+    /// Replaces `target` with `source` under the map write lock and removes
+    /// `source`. The target keeps its name but takes everything else from the
+    /// source, so readers see either the old or the new contents, never a mix.
+    /// Returns the number of points now in `target`.
+    pub fn swap_collections(&self, target: &str, source: &str) -> Result<usize, SwapError> {
+        self.swap_collections_with(target, source, || {})
+    }
+
+    /// Like `swap_collections`; `log` runs under the map write lock once the
+    /// swap is in place, so its WAL record lands before any write to the
+    /// swapped-in `target` can be logged.
+    pub fn swap_collections_with(&self, target: &str, source: &str, log: impl FnOnce()) -> Result<usize, SwapError> {
+        if target == source {
+            return Err(SwapError::SameCollection);
+        }
+        let _gate = self.write_gate.read_recursive();
+        let mut g = self.inner.write();
+        let old_generation = g.get(target).ok_or(SwapError::TargetNotFound)?.read().generation;
+        let replacement = g.remove(source).ok_or(SwapError::SourceNotFound)?;
+        let points = {
+            let mut coll = replacement.write();
+            coll.name = target.to_string();
+            coll.generation = old_generation + 1;
+            coll.point_count()
+        };
+        if let Some(old) = g.insert(target.to_string(), replacement) {
+            remove_spill_file(&old.read());
+        }
+        log();
+        Ok(points)
+    }
+
+    /// Removes the collection `name`; false if it did not exist.
+    pub fn drop_collection(&self, name: &str) -> bool {
+        self.drop_collection_with(name, || {})
+    }
+
+    /// Like `drop_collection`; `log` runs under the map write lock once the
+    /// collection is gone, so a create of the same name is logged after it.
+    pub fn drop_collection_with(&self, name: &str, log: impl FnOnce()) -> bool {
+        let _gate = self.write_gate.read_recursive();
+        let mut g = self.inner.write();
+        let Some(coll) = g.remove(name) else { return false; };
+        log();
+        drop(g);
+        remove_spill_file(&coll.read());
+        true
+    }
+
+    /// Rebuilds every collection whose `freed_ratio` exceeds `threshold` and
+    /// returns their names. Each rebuild runs under the collection's read
+    /// lock; writers wait only for the final swap (see `rebuild_index`).
+    /// Evicted collections are skipped: reloading one leaves it tightly packed.
+    pub fn compact_sparse(&self, threshold: f64) -> Vec<String> {
+        let mut compacted = Vec::new();
+        for name in self.names() {
+            if self.is_resident(&name) != Some(true) {
+                continue;
+            }
+            let Some(handle) = self.get(&name) else { continue; };
+            if handle.with_ref(|coll| coll.freed_ratio() > threshold) != Some(true) {
+                continue;
+            }
+            if handle.rebuild_index().is_some() {
+                compacted.push(name);
+            }
+        }
+        compacted
+    }
+
+    /// Names of the collections starting with `prefix`, sorted.
+    pub fn names_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let mut names: Vec<String> = self.inner.read().keys().filter(|name| name.starts_with(prefix)).cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Drops every collection.
+    pub fn clear(&self) {
+        let dropped: Vec<SharedCollection> = self.inner.write().drain().map(|(_, coll)| coll).collect();
+        for coll in dropped {
+            remove_spill_file(&coll.read());
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.inner.read().len()
     }
 
+    /// Names of all collections, in no particular order.
+    pub fn names(&self) -> Vec<String> {
+        self.inner.read().keys().cloned().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().is_empty()
+    }
+
+    /// Clones every collection so it can be serialized without blocking
+    /// writers. Each collection is copied under its own read lock, so the copy
+    /// includes every write applied before the call, but writes that land
+    /// during it may appear in some collections and not others. Evicted
+    /// collections are copied from their spill files and stay evicted.
+    pub fn snapshot(&self) -> Vec<Collection> {
+        self.snapshot_with(|| ()).1
+    }
+
+    /// Like `snapshot`, but first runs `mark` and clones with logged writes
+    /// (`with_mut` and the `*_with` map changes) held off,
+    /// so the copy holds exactly the writes logged before `mark` ran. Writers
+    /// wait only for the in-memory clones; evicted collections are read back
+    /// after they resume.
+    pub fn snapshot_with<M>(&self, mark: impl FnOnce() -> M) -> (M, Vec<Collection>) {
+        let gate = self.write_gate.write();
+        let marked = mark();
+        let collections: Vec<Collection> = self.inner.read().values().map(|c| c.read().clone()).collect();
+        drop(gate);
+        let collections = collections
+            .into_iter()
+            .filter_map(|mut coll| {
+                if !coll.is_resident() {
+                    if let Err(err) = coll.reload() {
+                        tracing::error!(collection = %coll.name, ?err, "failed to read evicted collection for snapshot");
+                        return None;
+                    }
+                }
+                Some(coll)
+            })
+            .collect();
+        (marked, collections)
+    }
+
+    /// `(name, len, capacity)` for every collection's index, read under a
+    /// single lock. An evicted collection reports its spilled points and no
+    /// capacity.
+    pub fn index_sizes(&self) -> Vec<(String, usize, usize)> {
+        let guard = self.inner.read();
+        guard
+            .values()
+            .map(|collection| {
+                let collection = collection.read();
+                (collection.name.clone(), collection.point_count(), collection.index.capacity())
+            })
+            .collect()
+    }
+
     pub fn total_points(&self) -> usize {
         let guard = self.inner.read();
-        guard.values().map(|collection| collection.index.len()).sum()
+        guard.values().map(|collection| collection.read().point_count()).sum()
     }
 }
 
@@ -153,33 +1370,63 @@ pub struct CollectionHandle {
 }
 
 impl CollectionHandle {
-    pub fn upsert_points(&self, points: Vec<PointWrite>) -> Option<usize> {
+    /// Appends `points` under one write lock. A point with the wrong
+    /// dimension or an id the collection cannot store rejects the batch.
+    pub fn upsert_points(&self, points: Vec<PointWrite>) -> Result<usize, UpsertError> {
         if points.is_empty() {
-            return Some(0);
-        }
-        let dims_ok = self
-            .with_ref(|coll| points.iter().all(|p| coll.validate_dim(&p.vector)))
-            .unwrap_or(false);
-        if !dims_ok {
-            return None;
+            return Ok(0);
         }
         self.with_mut(|coll| {
-            let ids: Vec<String> = points.iter().map(|p| p.id.clone()).collect();
-            let payloads: Vec<String> = points.iter().map(|p| p.payload_json.clone()).collect();
-            let vectors: Vec<Vec<f32>> = points.into_iter().map(|p| p.vector).collect();
-            coll.upsert_batch(ids, vectors, payloads)
+            let upserted = coll.upsert_batch(points)?;
+            coll.access.upserts.fetch_add(1, Ordering::Relaxed);
+            Ok(upserted)
+        })
+        .ok_or(UpsertError::CollectionNotFound)?
+    }
+
+    /// Like `upsert_points`, but once the whole batch has been validated
+    /// `commit` runs under the same write lock, before any point is applied
+    /// (e.g. to log the batch). If it fails the collection is left untouched.
+    pub fn upsert_points_with(
+        &self,
+        points: Vec<PointWrite>,
+        commit: impl FnOnce() -> anyhow::Result<()>,
+    ) -> Result<usize, UpsertError> {
+        self.with_mut(|coll| {
+            for (position, point) in points.iter().enumerate() {
+                coll.index.check_row(position, &point.id, &point.vector)?;
+            }
+            commit().map_err(|err| UpsertError::Commit(format!("{err:#}")))?;
+            let upserted = coll.upsert_batch(points)?;
+            coll.access.upserts.fetch_add(1, Ordering::Relaxed);
+            Ok(upserted)
         })
+        .ok_or(UpsertError::CollectionNotFound)?
     }
 
+    /// Ranks the collection against `query`. Returns `None` when the
+    /// collection is gone or `query` has the wrong dimension; an empty
+    /// `query` follows the catalog's `EmptyQuery` policy. `max_scan` caps the
+    /// points scored, trading recall for latency (see `scan_stride`), and
+    /// `group_by` caps the hits per payload value.
+    #[allow(clippy::too_many_arguments)]
     pub fn search(
         &self,
         query: Vec<f32>,
         top_k: usize,
         metric_override: Option<Metric>,
         filters: Vec<(String, String)>,
-    ) -> Option<Vec<(String, f32, String)>> {
+        ids: IdFilter,
+        hybrid: Option<HybridScore>,
+        boosts: Vec<ScoreBoost>,
+        max_scan: Option<usize>,
+        group_by: Option<GroupBy>,
+    ) -> Option<(Vec<SearchHit>, SearchStats)> {
         if query.is_empty() {
-            return Some(vec![]);
+            return match self.cat.empty_query {
+                EmptyQuery::Reject => None,
+                EmptyQuery::NoHits => self.with_ref(|_| (vec![], SearchStats::default())),
+            };
         }
         let dim_ok = self
             .with_ref(|coll| coll.validate_dim(&query))
@@ -192,26 +1439,190 @@ impl CollectionHandle {
         } else {
             Some(filters.as_slice())
         };
-        self.with_ref(|coll| coll.search(&query, top_k, metric_override, filters_opt))
+        self.with_ref(|coll| {
+            coll.access.queries.fetch_add(1, Ordering::Relaxed);
+            match hybrid {
+                Some(hybrid) => {
+                    coll.search_hybrid(&query, top_k, hybrid, filters_opt, &ids, &boosts, max_scan, group_by.as_ref())
+                }
+                None => coll.search(&query, top_k, metric_override, filters_opt, &ids, &boosts, max_scan, group_by.as_ref()),
+            }
+        })
+    }
+
+    /// Reads through the collection's storage so it is resident; returns the
+    /// points and bytes read. See `FlatIndex::warmup`.
+    pub fn warmup(&self) -> Option<(usize, usize)> {
+        self.with_ref(|coll| (coll.index.len(), coll.index.warmup()))
     }
 
+    /// Rebuilds the collection index and returns the number of points it holds.
+    ///
+    /// The new index is built under the read lock and swapped in under a short
+    /// write lock; if a write raced the build, the rebuild is redone while
+    /// holding the write lock so no points are lost.
+    pub fn rebuild_index(&self) -> Option<usize> {
+        let (generation, rebuilt) = self.with_ref(|coll| (coll.generation, coll.rebuild()))?;
+        self.with_mut(|coll| {
+            coll.index = if coll.generation == generation { rebuilt } else { coll.rebuild() };
+            coll.freed_rows = 0;
+            coll.index.len()
+        })
+    }
+
+    /// Releases the index's spare capacity; returns the approximate bytes
+    /// freed. See `FlatIndex::shrink_to_fit`.
+    pub fn shrink_to_fit(&self) -> Option<usize> {
+        self.with_mut(|coll| coll.index.shrink_to_fit())
+    }
+
+    pub fn len(&self) -> Option<usize> {
+        self.with_ref(|coll| coll.index.len())
+    }
+
+    pub fn is_empty(&self) -> Option<bool> {
+        self.with_ref(|coll| coll.index.is_empty())
+    }
+
+    pub fn dim(&self) -> Option<usize> {
+        self.with_ref(|coll| coll.dim)
+    }
+
+    pub fn metric(&self) -> Option<Metric> {
+        self.with_ref(|coll| coll.metric)
+    }
+
+    /// Read access to the collection's points. The collection's read lock is
+    /// held until the returned view is dropped, so its writers wait on it.
+    pub fn iter_points(&self) -> Option<PointsView> {
+        let coll = self.shared()?;
+        loop {
+            let guard = coll.read_arc();
+            if guard.is_resident() {
+                self.cat.touch(&guard);
+                return Some(PointsView(guard));
+            }
+            drop(guard);
+            // The view outlives this call, so enforce the budget before
+            // taking its lock: the map lock cannot be taken while it is held.
+            self.cat.make_resident(&mut coll.write())?;
+            self.cat.enforce_memory_budget();
+        }
+    }
+
+    /// Looks the collection up by name. The map lock is released on return,
+    /// so only this collection's lock is held while the caller works on it.
+    fn shared(&self) -> Option<SharedCollection> {
+        self.cat.inner.read().get(&self.name).cloned()
+    }
+
+    /// Runs `f` under the write lock, reloading the collection first if it
+    /// was evicted. Writes can grow it, so the memory budget is enforced
+    /// once the lock is released.
     pub fn with_mut<F, T>(&self, f: F) -> Option<T>
     where
         F: FnOnce(&mut Collection) -> T
     {
-        let mut g = self.cat.inner.write();
-        let coll = g.get_mut(&self.name)?;
-        Some(f(coll))
+        let gate = self.cat.write_gate.read_recursive();
+        let coll = self.shared()?;
+        let mut guard = coll.write();
+        self.cat.make_resident(&mut guard)?;
+        let out = f(&mut guard);
+        drop(guard);
+        drop(gate);
+        self.cat.enforce_memory_budget();
+        Some(out)
     }
 
+    /// Runs `f` under the read lock, reloading the collection first if it
+    /// was evicted.
     pub fn with_ref<F, T>(&self, f: F) -> Option<T>
     where
         F: FnOnce(&Collection) -> T
     {
-        let g = self.cat.inner.read();
-        let coll = g.get(&self.name)?;
-        Some(f(coll))
+        let coll = self.shared()?;
+        let (guard, reloaded) = self.read_resident(&coll)?;
+        let out = f(&guard);
+        drop(guard);
+        if reloaded {
+            self.cat.enforce_memory_budget();
+        }
+        Some(out)
+    }
+
+    /// The collection's read lock, taken after reloading it under the write
+    /// lock if it was evicted. The write lock is downgraded rather than
+    /// released so an eviction cannot slip in between.
+    fn read_resident<'a>(&self, coll: &'a SharedCollection) -> Option<(RwLockReadGuard<'a, Collection>, bool)> {
+        let guard = coll.read();
+        if guard.is_resident() {
+            self.cat.touch(&guard);
+            return Some((guard, false));
+        }
+        drop(guard);
+        let mut guard = coll.write();
+        let reloaded = self.cat.make_resident(&mut guard)?;
+        Some((RwLockWriteGuard::downgrade(guard), reloaded))
+    }
+}
+
+/// A collection borrowed under its read lock; see `CollectionHandle::iter_points`.
+pub struct PointsView(ArcRwLockReadGuard<RawRwLock, Collection>);
+
+impl PointsView {
+    /// The collection the view reads, for settings and per-row metadata.
+    pub fn collection(&self) -> &Collection {
+        &self.0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, [f32]>, &str)> {
+        self.0.iter_points()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.index.is_empty()
+    }
+}
+
+fn remove_spill_file(coll: &Collection) {
+    if let Some(spill) = &coll.spill {
+        let _ = std::fs::remove_file(&spill.path);
+    }
+}
+
+/// 64-bit FNV-1a. Chosen over `DefaultHasher` because checksums are compared
+/// across processes and builds, so the function must never change.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    /// Hashes a length-prefixed field so adjacent fields cannot run together.
+    fn field(&mut self, bytes: &[u8]) {
+        for b in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// Keeps only the listed top-level keys of a JSON object payload. Payloads
+/// that are not objects project to an empty string.
+fn project_payload(payload: &str, fields: &[String]) -> String {
+    if fields.is_empty() {
+        return payload.to_string();
     }
+    let Ok(Value::Object(mut map)) = serde_json::from_str::<Value>(payload) else { return String::new(); };
+    map.retain(|key, _| fields.contains(key));
+    Value::Object(map).to_string()
 }
 
 fn payload_matches_filters(payload: &str, filters: &[(String, String)]) -> bool {
@@ -220,7 +1631,7 @@ fn payload_matches_filters(payload: &str, filters: &[(String, String)]) -> bool
     }
     let Ok(Value::Object(map)) = serde_json::from_str::<Value>(payload) else { return false; };
     filters.iter().all(|(key, expected)| {
-        map.get(key).map_or(false, |value| match value {
+        map.get(key).is_some_and(|value| match value {
             Value::String(s) => s == expected,
             Value::Number(n) => n.to_string() == *expected,
             Value::Bool(b) => b.to_string() == *expected,
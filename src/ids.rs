@@ -0,0 +1,82 @@
+//! Ids minted for points upserted without one.
+
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use uuid::Uuid;
+
+/// Which kind of id `Upsert` generates when a point's `id` is empty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdScheme {
+    /// Random UUID v4, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    #[default]
+    Uuid,
+    /// 26-character ULID: a millisecond timestamp followed by randomness, so
+    /// ids sort lexicographically in creation order.
+    Ulid,
+}
+
+impl IdScheme {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "uuid" => Some(Self::Uuid),
+            "ulid" => Some(Self::Ulid),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Uuid => "uuid",
+            Self::Ulid => "ulid",
+        }
+    }
+
+    pub fn generate(&self) -> String {
+        match self {
+            Self::Uuid => Uuid::new_v4().to_string(),
+            Self::Ulid => ulid(),
+        }
+    }
+}
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// Last ULID handed out as (timestamp ms, 80-bit random part).
+static LAST_ULID: Mutex<(u64, u128)> = Mutex::new((0, 0));
+
+/// Generates a monotonic ULID: within one millisecond the random part is
+/// incremented instead of redrawn, so ids from this process never sort
+/// before an earlier one.
+fn ulid() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let mut last = LAST_ULID.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let (ms, random) = if now <= last.0 {
+        // Same millisecond (or the clock stepped back): bump the random part,
+        // carrying into the timestamp on overflow.
+        let next = (last.1 + 1) & ((1u128 << 80) - 1);
+        (if next == 0 { last.0 + 1 } else { last.0 }, next)
+    } else {
+        (now, random_80())
+    };
+    *last = (ms, random);
+    drop(last);
+
+    let mut value = ((ms as u128 & ((1u128 << 48) - 1)) << 80) | random;
+    let mut out = [0u8; 26];
+    for slot in out.iter_mut().rev() {
+        *slot = CROCKFORD[(value & 31) as usize];
+        value >>= 5;
+    }
+    out.iter().map(|b| *b as char).collect()
+}
+
+/// 80 random bits, taken from the bytes of a v4 UUID that carry no
+/// version or variant bits.
+fn random_80() -> u128 {
+    let bytes = *Uuid::new_v4().as_bytes();
+    bytes[..6].iter().chain(&bytes[10..14]).fold(0u128, |acc, b| (acc << 8) | *b as u128)
+}
@@ -1,28 +1,628 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, TryReserveError};
 use rayon::prelude::*;
+use thiserror::Error;
+
+use crate::types::{is_zero_norm, IdKind, Metric, PayloadStore, VectorLayout, ZeroNorm};
+
+/// Rows per block in `VectorLayout::Blocked`; eight f32 lanes fill one AVX register.
+pub const BLOCK_ROWS: usize = 8;
+
+/// Bookkeeping `memory_bytes` charges per row on top of its text: the id and
+/// payload `String` headers plus the id -> rows map entry.
+const ROW_OVERHEAD_BYTES: usize = 2 * size_of::<String>() + size_of::<Vec<usize>>() + size_of::<usize>();
+
+/// Why `add_batch` refused a batch. The index is left unchanged.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BatchError {
+    #[error("batch columns differ in length: {ids} ids, {vectors} vectors, {payloads} payloads, {expiries} expiries")]
+    LengthMismatch { ids: usize, vectors: usize, payloads: usize, expiries: usize },
+    #[error("vector {position} has dimension {actual}, expected {expected}")]
+    Dimension { position: usize, expected: usize, actual: usize },
+    #[error("id '{0}' is not a valid u64")]
+    InvalidId(String),
+    #[error("vector {position} has zero norm, which cosine cannot score")]
+    ZeroNorm { position: usize },
+}
+
+/// A row taken out of the index by `remove` or `remove_expired`.
+pub struct RemovedRow {
+    pub id: String,
+    pub vector: Vec<f32>,
+    pub payload: String,
+    pub expires_at_ms: Option<i64>,
+}
+
+/// Point ids in row order plus the id -> rows map; an id upserted more than
+/// once occupies several rows.
+#[derive(Clone)]
+enum IdColumn {
+    Strings { ids: Vec<String>, positions: HashMap<String, Vec<usize>> },
+    U64 { ids: Vec<u64>, positions: HashMap<u64, Vec<usize>> },
+}
+
+impl IdColumn {
+    fn new(kind: IdKind) -> Self {
+        match kind {
+            IdKind::String => Self::Strings { ids: Vec::new(), positions: HashMap::new() },
+            IdKind::U64 => Self::U64 { ids: Vec::new(), positions: HashMap::new() },
+        }
+    }
+
+    fn kind(&self) -> IdKind {
+        match self {
+            Self::Strings { .. } => IdKind::String,
+            Self::U64 { .. } => IdKind::U64,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Strings { ids, .. } => ids.len(),
+            Self::U64 { ids, .. } => ids.len(),
+        }
+    }
+
+    fn get(&self, idx: usize) -> Cow<'_, str> {
+        match self {
+            Self::Strings { ids, .. } => Cow::Borrowed(&ids[idx]),
+            Self::U64 { ids, .. } => Cow::Owned(ids[idx].to_string()),
+        }
+    }
+
+    fn positions(&self, id: &str) -> &[usize] {
+        let rows = match self {
+            Self::Strings { positions, .. } => positions.get(id),
+            Self::U64 { positions, .. } => id.parse().ok().and_then(|id: u64| positions.get(&id)),
+        };
+        rows.map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Appends `id` as the next row. Callers validate ids against the kind
+    /// first; see `IdKind::accepts`.
+    fn push(&mut self, id: String) {
+        match self {
+            Self::Strings { ids, positions } => {
+                positions.entry(id.clone()).or_default().push(ids.len());
+                ids.push(id);
+            }
+            Self::U64 { ids, positions } => {
+                let id: u64 = id.parse().unwrap_or_else(|_| panic!("id '{id}' is not a u64"));
+                positions.entry(id).or_default().push(ids.len());
+                ids.push(id);
+            }
+        }
+    }
+
+    /// Row-aligned flags for the rows whose id is in `doomed`.
+    fn matching(&self, doomed: &HashSet<&str>) -> Vec<bool> {
+        match self {
+            Self::Strings { ids, .. } => ids.iter().map(|id| doomed.contains(id.as_str())).collect(),
+            Self::U64 { ids, .. } => {
+                let doomed: HashSet<u64> = doomed.iter().filter_map(|id| id.parse().ok()).collect();
+                ids.iter().map(|id| doomed.contains(id)).collect()
+            }
+        }
+    }
+
+    /// Moves row `read` to row `write` (`write < read`); `positions` is
+    /// stale until `reindex`.
+    fn shift(&mut self, read: usize, write: usize) {
+        match self {
+            Self::Strings { ids, .. } => ids.swap(write, read),
+            Self::U64 { ids, .. } => ids.swap(write, read),
+        }
+    }
+
+    fn truncate(&mut self, rows: usize) {
+        match self {
+            Self::Strings { ids, .. } => ids.truncate(rows),
+            Self::U64 { ids, .. } => ids.truncate(rows),
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(self.kind());
+    }
+
+    fn reindex(&mut self) {
+        match self {
+            Self::Strings { ids, positions } => {
+                positions.clear();
+                for (row, id) in ids.iter().enumerate() {
+                    positions.entry(id.clone()).or_default().push(row);
+                }
+            }
+            Self::U64 { ids, positions } => {
+                positions.clear();
+                for (row, id) in ids.iter().enumerate() {
+                    positions.entry(*id).or_default().push(row);
+                }
+            }
+        }
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        !self.positions(id).is_empty()
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        match self {
+            Self::Strings { ids, positions } => {
+                ids.try_reserve(additional)?;
+                positions.try_reserve(additional)
+            }
+            Self::U64 { ids, positions } => {
+                ids.try_reserve(additional)?;
+                positions.try_reserve(additional)
+            }
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        match self {
+            Self::Strings { ids, positions } => {
+                ids.shrink_to_fit();
+                positions.shrink_to_fit();
+                positions.values_mut().for_each(Vec::shrink_to_fit);
+            }
+            Self::U64 { ids, positions } => {
+                ids.shrink_to_fit();
+                positions.shrink_to_fit();
+                positions.values_mut().for_each(Vec::shrink_to_fit);
+            }
+        }
+    }
+
+    /// Approximate heap bytes held by the ids and the id -> rows map.
+    fn heap_bytes(&self) -> usize {
+        fn map_bytes<K>(positions: &HashMap<K, Vec<usize>>) -> usize {
+            positions.capacity() * (size_of::<K>() + size_of::<Vec<usize>>())
+                + positions.values().map(|rows| rows.capacity() * size_of::<usize>()).sum::<usize>()
+        }
+        match self {
+            Self::Strings { ids, positions } => {
+                ids.capacity() * size_of::<String>()
+                    + ids.iter().map(String::capacity).sum::<usize>()
+                    + map_bytes(positions)
+                    + positions.keys().map(String::capacity).sum::<usize>()
+            }
+            Self::U64 { ids, positions } => ids.capacity() * size_of::<u64>() + map_bytes(positions),
+        }
+    }
+
+    /// Reads every id byte; returns the number read.
+    fn touch(&self) -> usize {
+        match self {
+            Self::Strings { ids, .. } => ids
+                .iter()
+                .map(|s| {
+                    std::hint::black_box(s.bytes().fold(0u8, u8::wrapping_add));
+                    s.len()
+                })
+                .sum(),
+            Self::U64 { ids, .. } => {
+                std::hint::black_box(ids.iter().fold(0u64, |acc, id| acc.wrapping_add(*id)));
+                std::mem::size_of_val(ids.as_slice())
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct FlatIndex {
     pub dim: usize,
-    // Layout: [v0...vdim-1, v1...vdim-1, ...]
+    // RowMajor: [v0...vdim-1, v1...vdim-1, ...]
+    // Blocked: per block of BLOCK_ROWS rows, [d0 of rows 0..8, d1 of rows 0..8, ...];
+    // the last block is padded to full size.
     pub vectors: Vec<f32>,
-    pub ids: Vec<String>,
-    pub payloads: Vec<String>, // JSON strings
+    ids: IdColumn,
+    pub payloads: Vec<String>, // JSON strings; empty with `PayloadStore::ById`
+    /// Per-row expiry as unix milliseconds; `None` never expires.
+    pub expires_at_ms: Vec<Option<i64>>,
     pub metric: crate::types::Metric,
+    pub layout: VectorLayout,
+    pub payload_store: PayloadStore,
+    /// Consulted by `check_row` and by cosine searches; see `ZeroNorm`.
+    pub zero_norm: ZeroNorm,
+    // id -> payload JSON, used instead of `payloads` with `PayloadStore::ById`.
+    payloads_by_id: HashMap<String, String>,
+    // Id and payload bytes added minus those removed; see `memory_bytes`.
+    text_bytes: usize,
 }
 
 impl FlatIndex {
     pub fn new(dim: usize, metric: crate::types::Metric) -> Self {
-        Self { dim, vectors: Vec::new(), ids: Vec::new(), payloads: Vec::new(), metric }
+        Self {
+            dim,
+            vectors: Vec::new(),
+            ids: IdColumn::new(IdKind::String),
+            payloads: Vec::new(),
+            expires_at_ms: Vec::new(),
+            metric,
+            layout: VectorLayout::RowMajor,
+            payload_store: PayloadStore::Inline,
+            zero_norm: ZeroNorm::default(),
+            payloads_by_id: HashMap::new(),
+            text_bytes: 0,
+        }
+    }
+
+    pub fn id_kind(&self) -> IdKind {
+        self.ids.kind()
+    }
+
+    /// Switches how ids are stored, converting any stored ones. Fails with
+    /// the first id the new kind cannot hold, leaving the index unchanged.
+    pub fn set_id_kind(&mut self, kind: IdKind) -> Result<(), String> {
+        if kind == self.id_kind() {
+            return Ok(());
+        }
+        let ids: Vec<String> = (0..self.len()).map(|idx| self.id(idx).into_owned()).collect();
+        if let Some(bad) = ids.iter().find(|id| !kind.accepts(id)) {
+            return Err(bad.clone());
+        }
+        let mut column = IdColumn::new(kind);
+        for id in ids {
+            column.push(id);
+        }
+        if self.payload_store == PayloadStore::ById {
+            let by_row: Vec<String> = (0..self.len()).map(|idx| self.payload(idx).to_string()).collect();
+            self.payloads_by_id = (0..column.len()).map(|idx| column.get(idx).into_owned()).zip(by_row).collect();
+        }
+        self.ids = column;
+        Ok(())
+    }
+
+    /// Id of row `idx`; formatted on the fly for `IdKind::U64`.
+    pub fn id(&self, idx: usize) -> Cow<'_, str> {
+        self.ids.get(idx)
+    }
+
+    /// Whether any row is stored under `id`.
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// Approximate heap bytes used by ids and the id -> rows map.
+    pub fn id_heap_bytes(&self) -> usize {
+        self.ids.heap_bytes()
+    }
+
+    /// Switches where payloads are kept, moving any stored ones. Going to
+    /// `ById` keeps the last payload of an id stored in several rows.
+    pub fn set_payload_store(&mut self, store: PayloadStore) {
+        if store == self.payload_store {
+            return;
+        }
+        match store {
+            PayloadStore::ById => {
+                let payloads = std::mem::take(&mut self.payloads);
+                self.payloads_by_id = (0..self.len()).map(|idx| self.id(idx).into_owned()).zip(payloads).collect();
+            }
+            PayloadStore::Inline => {
+                self.payloads = (0..self.len()).map(|idx| self.payload(idx).to_string()).collect();
+                self.payloads_by_id.clear();
+            }
+        }
+        self.payload_store = store;
+    }
+
+    /// Payload JSON of row `idx`.
+    pub fn payload(&self, idx: usize) -> &str {
+        match self.payload_store {
+            PayloadStore::Inline => &self.payloads[idx],
+            PayloadStore::ById => self.payloads_by_id.get(self.id(idx).as_ref()).map(String::as_str).unwrap_or_default(),
+        }
+    }
+
+    /// Switches the storage layout, rearranging any stored vectors.
+    pub fn set_layout(&mut self, layout: VectorLayout) {
+        if layout == self.layout {
+            return;
+        }
+        let rows: Vec<Vec<f32>> = (0..self.len()).map(|idx| self.row(idx).into_owned()).collect();
+        self.vectors.clear();
+        self.layout = layout;
+        for (idx, row) in rows.iter().enumerate() {
+            self.push_row(idx, row);
+        }
+    }
+
+    /// Offset of dimension `d` of row `idx` in `vectors`.
+    fn slot(&self, idx: usize, d: usize) -> usize {
+        match self.layout {
+            VectorLayout::RowMajor => idx * self.dim + d,
+            VectorLayout::Blocked => (idx / BLOCK_ROWS) * BLOCK_ROWS * self.dim + d * BLOCK_ROWS + idx % BLOCK_ROWS,
+        }
+    }
+
+    /// Floats of `vectors` occupied by the first `rows` rows, including block padding.
+    fn stored_floats(&self, rows: usize) -> usize {
+        match self.layout {
+            VectorLayout::RowMajor => rows * self.dim,
+            VectorLayout::Blocked => rows.div_ceil(BLOCK_ROWS) * BLOCK_ROWS * self.dim,
+        }
+    }
+
+    /// The vector stored at row `idx`; borrowed for row-major storage,
+    /// gathered into a fresh buffer for blocked storage.
+    pub fn row(&self, idx: usize) -> Cow<'_, [f32]> {
+        match self.layout {
+            VectorLayout::RowMajor => Cow::Borrowed(&self.vectors[idx * self.dim..(idx + 1) * self.dim]),
+            VectorLayout::Blocked => Cow::Owned((0..self.dim).map(|d| self.vectors[self.slot(idx, d)]).collect()),
+        }
+    }
+
+    /// Writes `v` as row `idx`, which must be the next row after those stored.
+    fn push_row(&mut self, idx: usize, v: &[f32]) {
+        match self.layout {
+            VectorLayout::RowMajor => self.vectors.extend_from_slice(v),
+            VectorLayout::Blocked => {
+                if idx.is_multiple_of(BLOCK_ROWS) {
+                    self.vectors.resize(self.vectors.len() + BLOCK_ROWS * self.dim, 0.0);
+                }
+                for (d, x) in v.iter().enumerate() {
+                    let slot = self.slot(idx, d);
+                    self.vectors[slot] = *x;
+                }
+            }
+        }
+    }
+
+    fn copy_row(&mut self, from: usize, to: usize) {
+        match self.layout {
+            VectorLayout::RowMajor => self.vectors.copy_within(from * self.dim..(from + 1) * self.dim, to * self.dim),
+            VectorLayout::Blocked => {
+                for d in 0..self.dim {
+                    let (src, dst) = (self.slot(from, d), self.slot(to, d));
+                    self.vectors[dst] = self.vectors[src];
+                }
+            }
+        }
+    }
+
+    /// Rows stored under `id`, in insertion order.
+    pub fn positions(&self, id: &str) -> &[usize] {
+        self.ids.positions(id)
     }
 
     pub fn len(&self) -> usize { self.ids.len() }
 
-    pub fn add_batch(&mut self, ids: Vec<String>, vecs: Vec<Vec<f32>>, payloads: Vec<String>) {
-        assert!(vecs.iter().all(|v| v.len() == self.dim), "all vectors must have dim={}", self.dim);
-        for v in vecs.into_iter() { self.vectors.extend_from_slice(&v); }
-        self.ids.extend(ids);
-        self.payloads.extend(payloads);
+    pub fn is_empty(&self) -> bool { self.ids.len() == 0 }
+
+    /// Number of points the index can hold without reallocating vector storage.
+    pub fn capacity(&self) -> usize {
+        self.vectors.capacity().checked_div(self.dim).unwrap_or(0)
+    }
+
+    /// Preallocates room for `additional` more points. Fails instead of
+    /// aborting if the allocation is impossible.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let floats = additional.saturating_mul(self.dim);
+        self.vectors.try_reserve(floats)?;
+        self.ids.try_reserve(additional)?;
+        match self.payload_store {
+            PayloadStore::Inline => self.payloads.try_reserve(additional)?,
+            PayloadStore::ById => self.payloads_by_id.try_reserve(additional)?,
+        }
+        self.expires_at_ms.try_reserve(additional)?;
+        Ok(())
+    }
+
+    /// Returns spare capacity, e.g. left behind by deletes, to the allocator
+    /// without moving any row. Returns the approximate bytes freed; the
+    /// allocator may keep some of them.
+    pub fn shrink_to_fit(&mut self) -> usize {
+        let before = self.allocated_bytes();
+        self.vectors.shrink_to_fit();
+        self.ids.shrink_to_fit();
+        self.payloads.shrink_to_fit();
+        self.payloads_by_id.shrink_to_fit();
+        self.expires_at_ms.shrink_to_fit();
+        before.saturating_sub(self.allocated_bytes())
+    }
+
+    /// Capacity of every column in bytes, excluding the id and payload text.
+    fn allocated_bytes(&self) -> usize {
+        self.vectors.capacity() * size_of::<f32>()
+            + self.ids.heap_bytes()
+            + self.payloads.capacity() * size_of::<String>()
+            + self.payloads_by_id.capacity() * 2 * size_of::<String>()
+            + self.expires_at_ms.capacity() * size_of::<Option<i64>>()
+    }
+
+    /// Approximate heap bytes held by the index, kept cheap enough to check
+    /// after every write: allocated vector and expiry storage, per-row
+    /// bookkeeping, and the id and payload text stored. Payloads replaced
+    /// under `PayloadStore::ById` are still counted.
+    pub fn memory_bytes(&self) -> usize {
+        self.vectors.capacity() * size_of::<f32>()
+            + self.expires_at_ms.capacity() * size_of::<Option<i64>>()
+            + self.len() * ROW_OVERHEAD_BYTES
+            + self.text_bytes
+    }
+
+    /// Reads every byte of vector, id and payload storage so its pages are
+    /// resident and cached. Returns the number of bytes read.
+    pub fn warmup(&self) -> usize {
+        let floats = &self.vectors[..self.stored_floats(self.len())];
+        std::hint::black_box(floats.iter().fold(0.0f32, |acc, x| acc + x));
+        let mut text = self.ids.touch();
+        for s in self.payloads.iter().chain(self.payloads_by_id.values()) {
+            std::hint::black_box(s.bytes().fold(0u8, u8::wrapping_add));
+            text += s.len();
+        }
+        std::mem::size_of_val(floats) + text
+    }
+
+    /// Whether row `idx` has an expiry at or before `now_ms`.
+    pub fn is_expired(&self, idx: usize, now_ms: i64) -> bool {
+        self.expires_at_ms[idx].is_some_and(|at| at <= now_ms)
+    }
+
+    /// Whether row `position` of a batch could be stored: `vector` must have
+    /// the index dimension and `id` must suit the id kind. Under
+    /// `ZeroNorm::Reject` a cosine index also refuses zero vectors.
+    pub fn check_row(&self, position: usize, id: &str, vector: &[f32]) -> Result<(), BatchError> {
+        self.check_shape(position, id, vector)?;
+        if self.zero_norm == ZeroNorm::Reject && self.metric == Metric::Cosine && is_zero_norm(vector) {
+            return Err(BatchError::ZeroNorm { position });
+        }
+        Ok(())
+    }
+
+    /// The checks `restore_batch` applies to each row: dimension and id type.
+    pub fn check_shape(&self, position: usize, id: &str, vector: &[f32]) -> Result<(), BatchError> {
+        if vector.len() != self.dim {
+            return Err(BatchError::Dimension { position, expected: self.dim, actual: vector.len() });
+        }
+        if !self.id_kind().accepts(id) {
+            return Err(BatchError::InvalidId(id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Appends one row per entry of the (equally long) columns. The whole
+    /// batch is validated first, so a bad row rejects it without writing any.
+    pub fn add_batch(
+        &mut self,
+        ids: Vec<String>,
+        vecs: Vec<Vec<f32>>,
+        payloads: Vec<String>,
+        expires_at_ms: Vec<Option<i64>>,
+    ) -> Result<(), BatchError> {
+        for (position, (id, vector)) in ids.iter().zip(&vecs).enumerate() {
+            self.check_row(position, id, vector)?;
+        }
+        self.restore_batch(ids, vecs, payloads, expires_at_ms)
+    }
+
+    /// Like `add_batch`, for rows admitted when they were first written (WAL
+    /// replay, reloads): the zero-norm policy is not applied again, so
+    /// tightening it never drops stored points.
+    pub fn restore_batch(
+        &mut self,
+        ids: Vec<String>,
+        vecs: Vec<Vec<f32>>,
+        payloads: Vec<String>,
+        expires_at_ms: Vec<Option<i64>>,
+    ) -> Result<(), BatchError> {
+        let n = ids.len();
+        if vecs.len() != n || payloads.len() != n || expires_at_ms.len() != n {
+            return Err(BatchError::LengthMismatch {
+                ids: n,
+                vectors: vecs.len(),
+                payloads: payloads.len(),
+                expiries: expires_at_ms.len(),
+            });
+        }
+        for (position, (id, vector)) in ids.iter().zip(&vecs).enumerate() {
+            self.check_shape(position, id, vector)?;
+        }
+        self.text_bytes += ids.iter().chain(&payloads).map(String::len).sum::<usize>();
+        let start = self.ids.len();
+        for (offset, v) in vecs.iter().enumerate() { self.push_row(start + offset, v); }
+        for id in ids {
+            self.ids.push(id);
+        }
+        match self.payload_store {
+            PayloadStore::Inline => self.payloads.extend(payloads),
+            PayloadStore::ById => {
+                // Keyed by the stored (canonical) id, not the caller's spelling.
+                let keys: Vec<String> = (start..self.len()).map(|idx| self.id(idx).into_owned()).collect();
+                self.payloads_by_id.extend(keys.into_iter().zip(payloads));
+            }
+        }
+        self.expires_at_ms.extend(expires_at_ms);
+        Ok(())
+    }
+
+    /// Drops every point while keeping dimension and metric.
+    pub fn clear(&mut self) {
+        self.vectors.clear();
+        self.ids.clear();
+        self.payloads.clear();
+        self.payloads_by_id.clear();
+        self.expires_at_ms.clear();
+        self.text_bytes = 0;
+    }
+
+    /// Removes every row whose id is in `ids`, compacting the remaining rows.
+    pub fn remove(&mut self, ids: &HashSet<&str>) -> Vec<RemovedRow> {
+        let doomed = self.ids.matching(ids);
+        self.remove_rows(&doomed)
+    }
+
+    /// Removes every row that expired at or before `now_ms`.
+    pub fn remove_expired(&mut self, now_ms: i64) -> Vec<RemovedRow> {
+        let doomed: Vec<bool> = (0..self.len()).map(|idx| self.is_expired(idx, now_ms)).collect();
+        self.remove_rows(&doomed)
+    }
+
+    fn remove_rows(&mut self, doomed: &[bool]) -> Vec<RemovedRow> {
+        let inline = self.payload_store == PayloadStore::Inline;
+        let mut removed = Vec::new();
+        let mut write = 0;
+        for (read, &drop) in doomed.iter().enumerate() {
+            if drop {
+                let payload = if inline {
+                    std::mem::take(&mut self.payloads[read])
+                } else {
+                    self.payload(read).to_string()
+                };
+                removed.push(RemovedRow {
+                    id: self.id(read).into_owned(),
+                    vector: self.row(read).into_owned(),
+                    payload,
+                    expires_at_ms: self.expires_at_ms[read],
+                });
+                continue;
+            }
+            if write != read {
+                self.copy_row(read, write);
+                self.ids.shift(read, write);
+                if inline {
+                    self.payloads.swap(write, read);
+                }
+                self.expires_at_ms.swap(write, read);
+            }
+            write += 1;
+        }
+        self.vectors.truncate(self.stored_floats(write));
+        self.ids.truncate(write);
+        if inline {
+            self.payloads.truncate(write);
+        }
+        self.expires_at_ms.truncate(write);
+        let removed_text: usize = removed.iter().map(|row| row.id.len() + row.payload.len()).sum();
+        self.text_bytes = self.text_bytes.saturating_sub(removed_text);
+        if !removed.is_empty() {
+            self.ids.reindex();
+            if !inline {
+                let ids = &self.ids;
+                self.payloads_by_id.retain(|id, _| ids.contains(id));
+            }
+        }
+        removed
+    }
+
+    /// Copies the live points into a fresh, tightly sized index.
+    pub fn rebuilt(&self) -> Self {
+        Self {
+            dim: self.dim,
+            vectors: self.vectors[..self.stored_floats(self.len())].to_vec(),
+            ids: self.ids.clone(),
+            payloads: self.payloads.clone(),
+            expires_at_ms: self.expires_at_ms.clone(),
+            metric: self.metric,
+            layout: self.layout,
+            payload_store: self.payload_store,
+            zero_norm: self.zero_norm,
+            payloads_by_id: self.payloads_by_id.clone(),
+            text_bytes: self.text_bytes,
+        }
     }
 
     fn l2(q: &[f32], v: &[f32]) -> f32 {
@@ -41,6 +641,22 @@ impl FlatIndex {
         s
     }
 
+    fn chebyshev(q: &[f32], v: &[f32]) -> f32 {
+        let mut m = 0.0f32;
+        for i in 0..q.len() { m = m.max((q[i] - v[i]).abs()); }
+        -m
+    }
+
+    fn jaccard(q: &[f32], v: &[f32]) -> f32 {
+        let (mut inter, mut union) = (0u32, 0u32);
+        for i in 0..q.len() {
+            let (a, b) = (q[i] != 0.0, v[i] != 0.0);
+            if a && b { inter += 1; }
+            if a || b { union += 1; }
+        }
+        if union == 0 { 0.0 } else { inter as f32 / union as f32 }
+    }
+
     fn cosine(q: &[f32], v: &[f32]) -> f32 {
         let dot = Self::dot(q, v);
         let nq = (q.iter().map(|x| x * x).sum::<f32>()).sqrt();
@@ -55,17 +671,18 @@ impl FlatIndex {
         metric_override: Option<crate::types::Metric>,
     ) -> Vec<(usize, f32)> {
         assert_eq!(query.len(), self.dim);
-        if self.len() == 0 || top_k == 0 { return vec![]; }
+        if self.is_empty() || top_k == 0 { return vec![]; }
 
         // Parallel scan
         let mut best: Vec<(usize, f32)> = (0..self.len()).into_par_iter().map(|i| {
-            let off = i * self.dim;
-            let v = &self.vectors[off..off + self.dim];
+            let v = self.row(i);
             let metric = metric_override.unwrap_or(self.metric);
             let score = match metric {
-                crate::types::Metric::L2 => Self::l2(query, v),
-                crate::types::Metric::IP => Self::dot(query, v),
-                crate::types::Metric::Cosine => Self::cosine(query, v),
+                crate::types::Metric::L2 => Self::l2(query, &v),
+                crate::types::Metric::IP => Self::dot(query, &v),
+                crate::types::Metric::Cosine => Self::cosine(query, &v),
+                crate::types::Metric::Chebyshev => Self::chebyshev(query, &v),
+                crate::types::Metric::Jaccard => Self::jaccard(query, &v),
             };
             (i, score)
         }).collect();
@@ -78,4 +695,84 @@ impl FlatIndex {
         }
         best
     }
+
+    /// Scores every row against `query` using the blocked kernels; entry `i`
+    /// is row `i`'s score. Each kernel accumulates dimensions in the same
+    /// order as the row-major scorer, so both layouts produce identical scores.
+    ///
+    /// Only meaningful for `VectorLayout::Blocked`.
+    pub fn blocked_scores(&self, metric: Metric, query: &[f32], parallel: bool) -> Vec<f32> {
+        assert_eq!(self.layout, VectorLayout::Blocked);
+        let block_len = BLOCK_ROWS * self.dim;
+        let blocks = self.vectors[..self.stored_floats(self.len())].chunks_exact(block_len);
+        let mut scores: Vec<f32> = if parallel {
+            blocks.collect::<Vec<_>>().into_par_iter().flat_map_iter(|b| score_block(metric, query, b)).collect()
+        } else {
+            blocks.flat_map(|b| score_block(metric, query, b)).collect()
+        };
+        scores.truncate(self.len());
+        scores
+    }
+}
+
+/// Scores the `BLOCK_ROWS` rows of one dimension-major block. The inner loops
+/// run across rows, so they vectorize regardless of `dim`.
+fn score_block(metric: Metric, query: &[f32], block: &[f32]) -> [f32; BLOCK_ROWS] {
+    let lanes = |d: usize| -> &[f32; BLOCK_ROWS] { block[d * BLOCK_ROWS..(d + 1) * BLOCK_ROWS].try_into().expect("block lane") };
+    let mut acc = [0.0f32; BLOCK_ROWS];
+    match metric {
+        Metric::L2 => {
+            for (d, q) in query.iter().enumerate() {
+                for (a, v) in acc.iter_mut().zip(lanes(d)) {
+                    let diff = q - v;
+                    *a += diff * diff;
+                }
+            }
+            acc.iter_mut().for_each(|a| *a = -*a);
+        }
+        Metric::IP => {
+            for (d, q) in query.iter().enumerate() {
+                for (a, v) in acc.iter_mut().zip(lanes(d)) {
+                    *a += q * v;
+                }
+            }
+        }
+        Metric::Cosine => {
+            let mut norms = [0.0f32; BLOCK_ROWS];
+            for (d, q) in query.iter().enumerate() {
+                for ((a, n), v) in acc.iter_mut().zip(norms.iter_mut()).zip(lanes(d)) {
+                    *a += q * v;
+                    *n += v * v;
+                }
+            }
+            let nq = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+            for (a, n) in acc.iter_mut().zip(norms) {
+                let nv = n.sqrt();
+                *a = if nq == 0.0 || nv == 0.0 { 0.0 } else { *a / (nq * nv) };
+            }
+        }
+        Metric::Chebyshev => {
+            for (d, q) in query.iter().enumerate() {
+                for (a, v) in acc.iter_mut().zip(lanes(d)) {
+                    *a = a.max((q - v).abs());
+                }
+            }
+            acc.iter_mut().for_each(|a| *a = -*a);
+        }
+        Metric::Jaccard => {
+            let (mut inter, mut union) = ([0u32; BLOCK_ROWS], [0u32; BLOCK_ROWS]);
+            for (d, q) in query.iter().enumerate() {
+                let a = *q != 0.0;
+                for ((i, u), v) in inter.iter_mut().zip(union.iter_mut()).zip(lanes(d)) {
+                    let b = *v != 0.0;
+                    *i += (a && b) as u32;
+                    *u += (a || b) as u32;
+                }
+            }
+            for ((out, i), u) in acc.iter_mut().zip(inter).zip(union) {
+                *out = if u == 0 { 0.0 } else { i as f32 / u as f32 };
+            }
+        }
+    }
+    acc
 }
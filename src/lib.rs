@@ -6,9 +6,15 @@ pub mod pb {
             include!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/pbgen/vectordb.v1.rs"));
         }
     }
+    pub mod google {
+        pub mod rpc {
+            include!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/pbgen/google.rpc.rs"));
+        }
+    }
 }
 
 pub mod catalog;
+pub mod ids;
 pub mod index;
 pub mod storage;
 pub mod types;
@@ -16,5 +22,14 @@ pub mod telemetry;
 
 pub mod server {
     pub mod state;
+    pub mod compaction;
+    pub mod embedded;
+    pub mod expiry;
     pub mod grpc;
+    pub mod integrity;
+    pub mod inventory;
+    pub mod rate_limit;
+    pub mod request_id;
+    pub mod shutdown;
+    pub mod snapshot;
 }
@@ -1,32 +1,66 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::server::TcpIncoming;
 use tonic::transport::Server;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use vectaraft::pb::vectordb::v1::vector_db_server::VectorDbServer;
-use vectaraft::server::grpc::VectorDbService;
-use vectaraft::server::state::{DbState, DbStateConfig};
-use vectaraft::telemetry::Metrics;
+use vectaraft::ids::IdScheme;
+use vectaraft::server::grpc::{
+    bind_listener, build_runtime, KeepaliveConfig, VectorDbService, DEFAULT_LISTEN_BACKLOG, DEFAULT_MAX_MESSAGE_BYTES,
+};
+use vectaraft::server::compaction::DEFAULT_COMPACTION_THRESHOLD;
+use vectaraft::server::inventory::INVENTORY_REFRESH_INTERVAL;
+use vectaraft::server::rate_limit::{RateLimit, RateLimiter};
+use vectaraft::server::request_id;
+use vectaraft::server::shutdown::{serve_with_drain, InFlight, DEFAULT_SHUTDOWN_TIMEOUT};
+use vectaraft::server::state::{parse_collection_names, DbState, DbStateConfig, Readiness};
+use vectaraft::telemetry::{otel, Metrics, DEFAULT_METRICS_PATH};
+use vectaraft::types::{EmptyQuery, ScorePrecision, ZeroNorm};
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The worker count has to be known before the runtime exists, so it is
+    // read ahead of the other flags.
+    let runtime_threads = runtime_threads();
+    build_runtime(runtime_threads)?.block_on(run(runtime_threads))
+}
+
+async fn run(runtime_threads: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let otlp_endpoint = otlp_endpoint();
+    let otlp_provider = match otlp_endpoint.as_deref().map(otel::init_otlp) {
+        Some(Ok(provider)) => Some(provider),
+        Some(Err(err)) => {
+            eprintln!("failed to initialize OTLP exporter; traces stay local: {err:?}");
+            None
+        }
+        None => None,
+    };
+    tracing_subscriber::registry()
+        .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
         )
+        .with(tracing_subscriber::fmt::layer())
+        .with(otlp_provider.as_ref().map(otel::layer))
         .init();
+    if let (Some(endpoint), Some(_)) = (&otlp_endpoint, &otlp_provider) {
+        tracing::info!(%endpoint, "exporting traces via OTLP");
+    }
+    if let Some(threads) = runtime_threads {
+        tracing::info!(threads, "tokio worker threads overridden");
+    }
 
     let mut config = RuntimeConfig::default();
     apply_cli_overrides(&mut config);
 
-    let state = Arc::new(DbState::with_config(config.db.clone()));
-
+    // Start the telemetry server before replaying the WAL so /readyz can
+    // report 503 while a large log is still being applied.
+    let readiness = Readiness::default();
     let metrics = if config.metrics.enable {
-        match Metrics::new() {
+        match Metrics::with_namespace(&config.metrics.latency_buckets, &config.metrics.namespace) {
             Ok(metrics) => {
-                metrics.set_collection_count(state.catalog.len());
-                metrics.set_point_count(state.catalog.total_points());
-                vectaraft::telemetry::spawn(metrics.clone(), config.metrics.addr);
+                vectaraft::telemetry::spawn(metrics.clone(), readiness.clone(), config.metrics.addr, &config.metrics.path);
                 Some(metrics)
             }
             Err(err) => {
@@ -38,20 +72,133 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         None
     };
 
-    let svc = VectorDbService { state, metrics: metrics.clone() };
+    let state = Arc::new(DbState::with_readiness(config.db.clone(), readiness));
+    if !state.replay_mismatches.is_empty() {
+        // Each mismatch was logged by the check; serving would hide the loss.
+        return Err(format!("startup integrity check failed for {} collection(s)", state.replay_mismatches.len()).into());
+    }
+    if let Some(metrics) = &metrics {
+        vectaraft::server::inventory::spawn(state.clone(), metrics.clone(), INVENTORY_REFRESH_INTERVAL);
+        metrics.record_wal_replay(&state.wal_replay_counts);
+        if let Some(wal) = &state.wal {
+            metrics.track_wal(wal.clone());
+        }
+    }
+
+    if let Some(interval) = config.snapshot_interval {
+        tracing::info!(interval_secs = interval.as_secs_f64(), "periodic snapshots enabled");
+        vectaraft::server::snapshot::spawn(state.clone(), interval);
+    }
+    if let Some(interval) = config.ttl_sweep_interval {
+        tracing::info!(interval_secs = interval.as_secs_f64(), "expired-point sweeper enabled");
+        vectaraft::server::expiry::spawn(state.clone(), interval);
+    }
+    if let Some(interval) = config.compaction_interval {
+        tracing::info!(
+            interval_secs = interval.as_secs_f64(),
+            threshold = config.compaction_threshold,
+            "automatic compaction enabled"
+        );
+        vectaraft::server::compaction::spawn(state.clone(), interval, config.compaction_threshold);
+    }
+
+    let svc = VectorDbService { state: state.clone(), metrics: metrics.clone() };
 
     let addr: SocketAddr = "127.0.0.1:50051".parse()?;
-    tracing::info!("gRPC listening on {}", addr);
+    let listener = bind_listener(addr, config.listen_backlog)?;
+    let incoming = TcpIncoming::from_listener(listener, true, config.keepalive.tcp)
+        .map_err(|err| err as Box<dyn std::error::Error>)?;
+    tracing::info!(backlog = config.listen_backlog, "gRPC listening on {}", addr);
 
-    Server::builder()
-        .add_service(VectorDbServer::new(svc))
-        .serve(addr)
-        .await?;
+    let in_flight = InFlight::default();
+    let router = config
+        .keepalive
+        .apply(Server::builder())
+        .add_service(in_flight.track(request_id::tag(InterceptedService::new(
+            svc.into_server(config.max_message_bytes),
+            RateLimiter::new(config.rate_limit()),
+        ))));
+    serve_with_drain(router, incoming, shutdown_signal(), config.shutdown_timeout, &in_flight, &state).await?;
+
+    if let Some(provider) = otlp_provider {
+        if let Err(err) = provider.shutdown() {
+            tracing::warn!(?err, "failed to flush OTLP spans on shutdown");
+        }
+    }
     Ok(())
 }
 
+/// Resolves on Ctrl-C, or on SIGTERM where supported.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!(?err, "failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(err) => {
+                tracing::error!(?err, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Resolves the OTLP collector endpoint from `--otlp-endpoint` or
+/// `VECTARAFT_OTLP_ENDPOINT`. Read ahead of the other flags because the
+/// subscriber has to be installed before anything logs.
+fn otlp_endpoint() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    let mut endpoint = std::env::var("VECTARAFT_OTLP_ENDPOINT").ok();
+    while let Some(arg) = args.next() {
+        if arg == "--otlp-endpoint" {
+            endpoint = args.next();
+        } else if let Some(value) = arg.strip_prefix("--otlp-endpoint=") {
+            endpoint = Some(value.to_string());
+        }
+    }
+    endpoint.filter(|e| !e.is_empty())
+}
+
+/// Resolves the tokio worker thread count from `--runtime-threads` or
+/// `VECTARAFT_RUNTIME_THREADS`. Runs before logging is set up, so invalid
+/// values are reported on stderr.
+fn runtime_threads() -> Option<usize> {
+    let mut args = std::env::args().skip(1);
+    let mut value = std::env::var("VECTARAFT_RUNTIME_THREADS").ok();
+    while let Some(arg) = args.next() {
+        if arg == "--runtime-threads" {
+            value = args.next();
+        } else if let Some(v) = arg.strip_prefix("--runtime-threads=") {
+            value = Some(v.to_string());
+        }
+    }
+    let value = value.filter(|v| !v.is_empty())?;
+    match value.parse::<usize>() {
+        Ok(threads) if threads > 0 => Some(threads),
+        _ => {
+            eprintln!("invalid runtime thread count '{value}'; using one worker per CPU");
+            None
+        }
+    }
+}
+
 fn apply_cli_overrides(config: &mut RuntimeConfig) {
     let mut args = std::env::args().skip(1);
+    let mut data_dir: Option<std::path::PathBuf> = None;
+    let mut wal_path_given = false;
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--no-wal" => {
@@ -65,6 +212,7 @@ fn apply_cli_overrides(config: &mut RuntimeConfig) {
                     tracing::info!(wal_path = %path_buf.display(), "WAL path overridden via CLI flag");
                     config.db.enable_wal = true;
                     config.db.wal_path = Some(path_buf);
+                    wal_path_given = true;
                 } else {
                     tracing::warn!("--wal-path flag requires a value; ignoring");
                 }
@@ -79,6 +227,266 @@ fn apply_cli_overrides(config: &mut RuntimeConfig) {
                 tracing::info!(wal_path = %path_buf.display(), "WAL path overridden via CLI flag");
                 config.db.enable_wal = true;
                 config.db.wal_path = Some(path_buf);
+                wal_path_given = true;
+            }
+            "--data-dir" => {
+                if let Some(path) = args.next() {
+                    data_dir = Some(path.into());
+                } else {
+                    tracing::warn!("--data-dir flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--data-dir=") => {
+                let path = &arg["--data-dir=".len()..];
+                if path.is_empty() {
+                    tracing::warn!("--data-dir flag requires a non-empty value; ignoring");
+                    continue;
+                }
+                data_dir = Some(path.into());
+            }
+            "--wal-buffer-bytes" => {
+                if let Some(value) = args.next() {
+                    apply_wal_buffer_bytes(config, &value);
+                } else {
+                    tracing::warn!("--wal-buffer-bytes flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--wal-buffer-bytes=") => {
+                let value = &arg["--wal-buffer-bytes=".len()..];
+                apply_wal_buffer_bytes(config, value);
+            }
+            "--wal-flush-every" => {
+                if let Some(value) = args.next() {
+                    apply_wal_flush_every(config, &value);
+                } else {
+                    tracing::warn!("--wal-flush-every flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--wal-flush-every=") => {
+                let value = &arg["--wal-flush-every=".len()..];
+                apply_wal_flush_every(config, value);
+            }
+            "--wal-commit-window-ms" => {
+                if let Some(value) = args.next() {
+                    apply_wal_commit_window(config, &value);
+                } else {
+                    tracing::warn!("--wal-commit-window-ms flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--wal-commit-window-ms=") => {
+                let value = &arg["--wal-commit-window-ms=".len()..];
+                apply_wal_commit_window(config, value);
+            }
+            "--wal-backlog-limit-bytes" => {
+                if let Some(value) = args.next() {
+                    apply_wal_backlog_limit(config, &value);
+                } else {
+                    tracing::warn!("--wal-backlog-limit-bytes flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--wal-backlog-limit-bytes=") => {
+                let value = &arg["--wal-backlog-limit-bytes=".len()..];
+                apply_wal_backlog_limit(config, value);
+            }
+            "--wal-retention-records" => {
+                if let Some(value) = args.next() {
+                    apply_wal_retention(config, &value);
+                } else {
+                    tracing::warn!("--wal-retention-records flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--wal-retention-records=") => {
+                let value = &arg["--wal-retention-records=".len()..];
+                apply_wal_retention(config, value);
+            }
+            "--id-scheme" => {
+                if let Some(value) = args.next() {
+                    apply_id_scheme(config, &value);
+                } else {
+                    tracing::warn!("--id-scheme flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--id-scheme=") => {
+                let value = &arg["--id-scheme=".len()..];
+                apply_id_scheme(config, value);
+            }
+            "--empty-query" => {
+                if let Some(value) = args.next() {
+                    apply_empty_query(config, &value);
+                } else {
+                    tracing::warn!("--empty-query flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--empty-query=") => {
+                let value = &arg["--empty-query=".len()..];
+                apply_empty_query(config, value);
+            }
+            "--pin-collections" => {
+                if let Some(value) = args.next() {
+                    apply_pin_collections(config, &value);
+                } else {
+                    tracing::warn!("--pin-collections flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--pin-collections=") => {
+                let value = &arg["--pin-collections=".len()..];
+                apply_pin_collections(config, value);
+            }
+            "--memory-budget-bytes" => {
+                if let Some(value) = args.next() {
+                    apply_memory_budget(config, &value);
+                } else {
+                    tracing::warn!("--memory-budget-bytes flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--memory-budget-bytes=") => {
+                let value = &arg["--memory-budget-bytes=".len()..];
+                apply_memory_budget(config, value);
+            }
+            "--score-precision" => {
+                if let Some(value) = args.next() {
+                    apply_score_precision(config, &value);
+                } else {
+                    tracing::warn!("--score-precision flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--score-precision=") => {
+                let value = &arg["--score-precision=".len()..];
+                apply_score_precision(config, value);
+            }
+            "--zero-norm" => {
+                if let Some(value) = args.next() {
+                    apply_zero_norm(config, &value);
+                } else {
+                    tracing::warn!("--zero-norm flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--zero-norm=") => {
+                let value = &arg["--zero-norm=".len()..];
+                apply_zero_norm(config, value);
+            }
+            "--parallel-threshold" => {
+                if let Some(value) = args.next() {
+                    apply_parallel_threshold(config, &value);
+                } else {
+                    tracing::warn!("--parallel-threshold flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--parallel-threshold=") => {
+                let value = &arg["--parallel-threshold=".len()..];
+                apply_parallel_threshold(config, value);
+            }
+            "--max-dims" => {
+                if let Some(value) = args.next() {
+                    apply_max_dims(config, &value);
+                } else {
+                    tracing::warn!("--max-dims flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--max-dims=") => {
+                let value = &arg["--max-dims=".len()..];
+                apply_max_dims(config, value);
+            }
+            "--max-collections" => {
+                if let Some(value) = args.next() {
+                    apply_max_collections(config, &value);
+                } else {
+                    tracing::warn!("--max-collections flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--max-collections=") => {
+                let value = &arg["--max-collections=".len()..];
+                apply_max_collections(config, value);
+            }
+            "--shutdown-timeout" => {
+                if let Some(value) = args.next() {
+                    config.shutdown_timeout = parse_interval_secs("--shutdown-timeout", &value).unwrap_or(config.shutdown_timeout);
+                } else {
+                    tracing::warn!("--shutdown-timeout flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--shutdown-timeout=") => {
+                let value = &arg["--shutdown-timeout=".len()..];
+                config.shutdown_timeout = parse_interval_secs("--shutdown-timeout", value).unwrap_or(config.shutdown_timeout);
+            }
+            "--rate-limit" | "--rate-limit-burst" => {
+                if let Some(value) = args.next() {
+                    apply_rate_limit(config, &arg, &value);
+                } else {
+                    tracing::warn!("{arg} flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--rate-limit=") || arg.starts_with("--rate-limit-burst=") => {
+                if let Some((flag, value)) = arg.split_once('=') {
+                    apply_rate_limit(config, flag, value);
+                }
+            }
+            "--read-only" => {
+                config.db.read_only = true;
+                tracing::info!("read-only mode enabled via CLI flag");
+            }
+            "--verify-on-start" => {
+                config.db.verify_on_start = true;
+                tracing::info!("startup integrity check enabled via CLI flag");
+            }
+            "--snapshot-interval" => {
+                if let Some(value) = args.next() {
+                    apply_snapshot_interval(config, &value);
+                } else {
+                    tracing::warn!("--snapshot-interval flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--snapshot-interval=") => {
+                let value = &arg["--snapshot-interval=".len()..];
+                apply_snapshot_interval(config, value);
+            }
+            "--ttl-sweep-interval" => {
+                if let Some(value) = args.next() {
+                    config.ttl_sweep_interval = parse_interval_secs("--ttl-sweep-interval", &value).or(config.ttl_sweep_interval);
+                } else {
+                    tracing::warn!("--ttl-sweep-interval flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--ttl-sweep-interval=") => {
+                let value = &arg["--ttl-sweep-interval=".len()..];
+                config.ttl_sweep_interval = parse_interval_secs("--ttl-sweep-interval", value).or(config.ttl_sweep_interval);
+            }
+            "--compaction-interval" => {
+                if let Some(value) = args.next() {
+                    config.compaction_interval = parse_interval_secs("--compaction-interval", &value).or(config.compaction_interval);
+                } else {
+                    tracing::warn!("--compaction-interval flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--compaction-interval=") => {
+                let value = &arg["--compaction-interval=".len()..];
+                config.compaction_interval = parse_interval_secs("--compaction-interval", value).or(config.compaction_interval);
+            }
+            "--compaction-threshold" => {
+                if let Some(value) = args.next() {
+                    config.compaction_threshold = parse_ratio("--compaction-threshold", &value).unwrap_or(config.compaction_threshold);
+                } else {
+                    tracing::warn!("--compaction-threshold flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--compaction-threshold=") => {
+                let value = &arg["--compaction-threshold=".len()..];
+                config.compaction_threshold = parse_ratio("--compaction-threshold", value).unwrap_or(config.compaction_threshold);
+            }
+            "--keepalive-interval" | "--keepalive-timeout" | "--tcp-keepalive" => {
+                if let Some(value) = args.next() {
+                    apply_keepalive(config, &arg, &value);
+                } else {
+                    tracing::warn!("{arg} flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--keepalive-interval=")
+                || arg.starts_with("--keepalive-timeout=")
+                || arg.starts_with("--tcp-keepalive=") =>
+            {
+                if let Some((flag, value)) = arg.split_once('=') {
+                    apply_keepalive(config, flag, value);
+                }
             }
             "--no-metrics" => {
                 config.metrics.enable = false;
@@ -109,30 +517,408 @@ fn apply_cli_overrides(config: &mut RuntimeConfig) {
                     Err(err) => tracing::warn!(input = %value, ?err, "invalid --metrics-addr value; ignoring"),
                 }
             }
+            "--metrics-buckets" => {
+                if let Some(value) = args.next() {
+                    config.metrics.latency_buckets = parse_buckets(&value);
+                } else {
+                    tracing::warn!("--metrics-buckets flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--metrics-buckets=") => {
+                let value = &arg["--metrics-buckets=".len()..];
+                config.metrics.latency_buckets = parse_buckets(value);
+            }
+            "--metrics-path" => {
+                if let Some(value) = args.next() {
+                    config.metrics.path = value;
+                } else {
+                    tracing::warn!("--metrics-path flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--metrics-path=") => {
+                config.metrics.path = arg["--metrics-path=".len()..].to_string();
+            }
+            "--metrics-namespace" => {
+                if let Some(value) = args.next() {
+                    config.metrics.namespace = value;
+                } else {
+                    tracing::warn!("--metrics-namespace flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--metrics-namespace=") => {
+                config.metrics.namespace = arg["--metrics-namespace=".len()..].to_string();
+            }
+            "--max-message-bytes" => {
+                if let Some(value) = args.next() {
+                    apply_max_message_bytes(config, &value);
+                } else {
+                    tracing::warn!("--max-message-bytes flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--max-message-bytes=") => {
+                let value = &arg["--max-message-bytes=".len()..];
+                apply_max_message_bytes(config, value);
+            }
+            "--listen-backlog" => {
+                if let Some(value) = args.next() {
+                    apply_listen_backlog(config, &value);
+                } else {
+                    tracing::warn!("--listen-backlog flag requires a value; ignoring");
+                }
+            }
+            _ if arg.starts_with("--listen-backlog=") => {
+                let value = &arg["--listen-backlog=".len()..];
+                apply_listen_backlog(config, value);
+            }
             _ => {}
         }
     }
+
+    // Applied after all flags so an explicit --wal-path wins regardless of order.
+    if let Some(dir) = data_dir {
+        let wal_path = config.db.wal_path.clone();
+        match config.db.apply_data_dir(&dir) {
+            Ok(()) => {
+                if wal_path_given {
+                    config.db.wal_path = wal_path;
+                }
+                tracing::info!(data_dir = %dir.display(), "persistence paths derived from --data-dir");
+            }
+            Err(err) => tracing::warn!(data_dir = %dir.display(), ?err, "failed to create --data-dir; ignoring"),
+        }
+    }
+}
+
+fn apply_max_message_bytes(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(bytes) if bytes > 0 => {
+            config.max_message_bytes = bytes;
+            tracing::info!(bytes, "gRPC message size limit overridden");
+        }
+        _ => tracing::warn!(input = %value, "invalid --max-message-bytes value; ignoring"),
+    }
+}
+
+fn apply_listen_backlog(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<u32>() {
+        Ok(backlog) if backlog > 0 => {
+            config.listen_backlog = backlog;
+            tracing::info!(backlog, "listen backlog overridden");
+        }
+        _ => tracing::warn!(input = %value, "invalid --listen-backlog value; ignoring"),
+    }
+}
+
+fn apply_wal_buffer_bytes(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(bytes) => {
+            config.db.wal_buffer_bytes = bytes;
+            tracing::info!(bytes, "WAL write buffer overridden; unflushed records are lost on crash");
+        }
+        Err(_) => tracing::warn!(input = %value, "invalid --wal-buffer-bytes value; ignoring"),
+    }
+}
+
+fn apply_wal_flush_every(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(records) => {
+            config.db.wal_flush_every_records = records;
+            tracing::info!(records, "WAL records are written and fsynced in groups; a crash loses the partial group");
+        }
+        Err(_) => tracing::warn!(input = %value, "invalid --wal-flush-every value; ignoring"),
+    }
+}
+
+fn apply_wal_commit_window(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<u64>() {
+        Ok(ms) => {
+            config.db.wal_commit_window = Some(Duration::from_millis(ms));
+            tracing::info!(window_ms = ms, "group commit enabled; writes are acknowledged after fsync");
+        }
+        Err(_) => tracing::warn!(input = %value, "invalid --wal-commit-window-ms value; ignoring"),
+    }
+}
+
+fn apply_wal_retention(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(records) if records > 0 => {
+            config.db.wal_retention_records = Some(records);
+            tracing::info!(records, "WAL retention cap set; exceeding it forces a snapshot");
+        }
+        _ => tracing::warn!(input = %value, "invalid --wal-retention-records value; ignoring"),
+    }
+}
+
+fn apply_wal_backlog_limit(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(bytes) => {
+            config.db.wal_backlog_limit_bytes = bytes;
+            tracing::info!(bytes, "WAL backlog limit overridden");
+        }
+        Err(_) => tracing::warn!(input = %value, "invalid --wal-backlog-limit-bytes value; ignoring"),
+    }
+}
+
+fn apply_id_scheme(config: &mut RuntimeConfig, value: &str) {
+    match IdScheme::parse(value) {
+        Some(scheme) => {
+            config.db.id_scheme = scheme;
+            tracing::info!(scheme = scheme.as_str(), "auto-id scheme overridden");
+        }
+        None => tracing::warn!(input = %value, "invalid --id-scheme value (expected uuid or ulid); ignoring"),
+    }
+}
+
+fn apply_empty_query(config: &mut RuntimeConfig, value: &str) {
+    match EmptyQuery::parse(value) {
+        Some(policy) => {
+            config.db.empty_query = policy;
+            tracing::info!(policy = policy.as_str(), "empty query policy overridden");
+        }
+        None => tracing::warn!(input = %value, "invalid --empty-query value (expected reject or empty); ignoring"),
+    }
+}
+
+fn apply_pin_collections(config: &mut RuntimeConfig, value: &str) {
+    config.db.pinned_collections = parse_collection_names(value);
+    tracing::info!(collections = ?config.db.pinned_collections, "pinned collections overridden");
+}
+
+fn apply_memory_budget(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(0) => {
+            config.db.memory_budget_bytes = None;
+            tracing::info!("memory budget disabled");
+        }
+        Ok(bytes) => {
+            config.db.memory_budget_bytes = Some(bytes);
+            tracing::info!(bytes, "memory budget overridden");
+        }
+        Err(_) => tracing::warn!(input = %value, "invalid --memory-budget-bytes value; ignoring"),
+    }
+}
+
+fn apply_score_precision(config: &mut RuntimeConfig, value: &str) {
+    match ScorePrecision::parse(value) {
+        Some(precision) => {
+            config.db.score_precision = precision;
+            tracing::info!(precision = precision.as_str(), "score precision overridden");
+        }
+        None => tracing::warn!(input = %value, "invalid --score-precision value (expected f32 or f64); ignoring"),
+    }
+}
+
+fn apply_zero_norm(config: &mut RuntimeConfig, value: &str) {
+    match ZeroNorm::parse(value) {
+        Some(policy) => {
+            config.db.zero_norm = policy;
+            tracing::info!(policy = policy.as_str(), "zero-norm policy overridden");
+        }
+        None => tracing::warn!(input = %value, "invalid --zero-norm value (expected zero, reject or exclude); ignoring"),
+    }
+}
+
+fn apply_parallel_threshold(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(points) => {
+            config.db.parallel_threshold = points;
+            tracing::info!(points, "parallel search threshold overridden");
+        }
+        Err(_) => tracing::warn!(input = %value, "invalid --parallel-threshold value; ignoring"),
+    }
+}
+
+fn apply_max_dims(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(dims) if dims > 0 => {
+            config.db.max_dims = dims;
+            tracing::info!(dims, "collection dimension limit overridden");
+        }
+        _ => tracing::warn!(input = %value, "invalid --max-dims value; ignoring"),
+    }
+}
+
+fn apply_max_collections(config: &mut RuntimeConfig, value: &str) {
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => {
+            config.db.max_collections = count;
+            tracing::info!(count, "collection count limit overridden");
+        }
+        _ => tracing::warn!(input = %value, "invalid --max-collections value; ignoring"),
+    }
+}
+
+fn apply_rate_limit(config: &mut RuntimeConfig, flag: &str, value: &str) {
+    let Some(parsed) = parse_rate(flag, value) else {
+        return;
+    };
+    if flag == "--rate-limit" {
+        config.rate_limit_per_second = Some(parsed);
+    } else {
+        config.rate_limit_burst = Some(parsed);
+    }
+    tracing::info!(flag, value = parsed, "rate limit overridden");
+}
+
+fn parse_rate(flag: &str, value: &str) -> Option<f64> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Some(rate),
+        _ => {
+            tracing::warn!(input = %value, "invalid {flag} value; ignoring");
+            None
+        }
+    }
+}
+
+fn apply_snapshot_interval(config: &mut RuntimeConfig, value: &str) {
+    config.snapshot_interval = parse_interval_secs("--snapshot-interval", value).or(config.snapshot_interval);
+}
+
+fn apply_keepalive(config: &mut RuntimeConfig, flag: &str, value: &str) {
+    let Some(duration) = parse_keepalive_secs(flag, value) else {
+        return;
+    };
+    match flag {
+        "--keepalive-interval" => config.keepalive.http2_interval = duration,
+        "--keepalive-timeout" => config.keepalive.http2_timeout = duration,
+        _ => config.keepalive.tcp = duration,
+    }
+}
+
+/// Parses a keepalive period in seconds, where `0` disables the probe.
+/// Returns `None` (after warning) on invalid input.
+fn parse_keepalive_secs(flag: &str, value: &str) -> Option<Option<Duration>> {
+    match value.parse::<u64>() {
+        Ok(0) => Some(None),
+        Ok(secs) => Some(Some(Duration::from_secs(secs))),
+        Err(_) => {
+            tracing::warn!(input = %value, "invalid {flag} value; ignoring");
+            None
+        }
+    }
+}
+
+/// Parses a positive whole number of seconds, warning on invalid input.
+fn parse_interval_secs(flag: &str, value: &str) -> Option<Duration> {
+    match value.parse::<u64>() {
+        Ok(secs) if secs > 0 => Some(Duration::from_secs(secs)),
+        _ => {
+            tracing::warn!(input = %value, "invalid {flag} value; ignoring");
+            None
+        }
+    }
+}
+
+/// Parses a fraction in `(0, 1]`, warning on invalid input.
+fn parse_ratio(flag: &str, value: &str) -> Option<f64> {
+    match value.parse::<f64>() {
+        Ok(ratio) if ratio > 0.0 && ratio <= 1.0 => Some(ratio),
+        _ => {
+            tracing::warn!(input = %value, "invalid {flag} value; ignoring");
+            None
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 struct RuntimeConfig {
     db: DbStateConfig,
     metrics: MetricsConfig,
+    max_message_bytes: usize,
+    /// Period of the background snapshot-and-compact task; `None` disables it.
+    snapshot_interval: Option<Duration>,
+    /// Period of the expired-point sweeper; `None` disables it.
+    ttl_sweep_interval: Option<Duration>,
+    /// Period of the automatic compaction check; `None` disables it.
+    compaction_interval: Option<Duration>,
+    /// Share of freed rows above which a collection is rebuilt.
+    compaction_threshold: f64,
+    keepalive: KeepaliveConfig,
+    /// Accept queue length requested for the gRPC socket.
+    listen_backlog: u32,
+    /// How long shutdown waits for in-flight requests before cutting them off.
+    shutdown_timeout: Duration,
+    /// Requests per second allowed per API key; `None` disables limiting.
+    rate_limit_per_second: Option<f64>,
+    /// Bucket size; defaults to one second's worth of requests.
+    rate_limit_burst: Option<f64>,
+}
+
+impl RuntimeConfig {
+    fn rate_limit(&self) -> Option<RateLimit> {
+        let per_second = self.rate_limit_per_second?;
+        let burst = self.rate_limit_burst.unwrap_or(per_second).max(1.0);
+        Some(RateLimit { per_second, burst })
+    }
 }
 
 impl Default for RuntimeConfig {
     fn default() -> Self {
+        let max_message_bytes = std::env::var("VECTARAFT_MAX_MESSAGE_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|bytes| *bytes > 0)
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES);
         Self {
             db: DbStateConfig::default(),
             metrics: MetricsConfig::default(),
+            max_message_bytes,
+            snapshot_interval: None,
+            ttl_sweep_interval: std::env::var("VECTARAFT_TTL_SWEEP_INTERVAL")
+                .ok()
+                .and_then(|v| parse_interval_secs("VECTARAFT_TTL_SWEEP_INTERVAL", &v)),
+            compaction_interval: std::env::var("VECTARAFT_COMPACTION_INTERVAL")
+                .ok()
+                .and_then(|v| parse_interval_secs("VECTARAFT_COMPACTION_INTERVAL", &v)),
+            compaction_threshold: std::env::var("VECTARAFT_COMPACTION_THRESHOLD")
+                .ok()
+                .and_then(|v| parse_ratio("VECTARAFT_COMPACTION_THRESHOLD", &v))
+                .unwrap_or(DEFAULT_COMPACTION_THRESHOLD),
+            keepalive: keepalive_from_env(),
+            listen_backlog: std::env::var("VECTARAFT_LISTEN_BACKLOG")
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .filter(|backlog| *backlog > 0)
+                .unwrap_or(DEFAULT_LISTEN_BACKLOG),
+            shutdown_timeout: std::env::var("VECTARAFT_SHUTDOWN_TIMEOUT")
+                .ok()
+                .and_then(|v| parse_interval_secs("VECTARAFT_SHUTDOWN_TIMEOUT", &v))
+                .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT),
+            rate_limit_per_second: std::env::var("VECTARAFT_RATE_LIMIT")
+                .ok()
+                .and_then(|v| parse_rate("VECTARAFT_RATE_LIMIT", &v)),
+            rate_limit_burst: std::env::var("VECTARAFT_RATE_LIMIT_BURST")
+                .ok()
+                .and_then(|v| parse_rate("VECTARAFT_RATE_LIMIT_BURST", &v)),
         }
     }
 }
 
+fn keepalive_from_env() -> KeepaliveConfig {
+    let mut keepalive = KeepaliveConfig::default();
+    let from_env = |name: &str| std::env::var(name).ok().and_then(|v| parse_keepalive_secs(name, &v));
+    if let Some(interval) = from_env("VECTARAFT_KEEPALIVE_INTERVAL") {
+        keepalive.http2_interval = interval;
+    }
+    if let Some(timeout) = from_env("VECTARAFT_KEEPALIVE_TIMEOUT") {
+        keepalive.http2_timeout = timeout;
+    }
+    if let Some(tcp) = from_env("VECTARAFT_TCP_KEEPALIVE") {
+        keepalive.tcp = tcp;
+    }
+    keepalive
+}
+
 #[derive(Clone, Debug)]
 struct MetricsConfig {
     enable: bool,
     addr: SocketAddr,
+    /// Latency histogram bucket boundaries in seconds; empty means defaults.
+    latency_buckets: Vec<f64>,
+    /// Route serving the exposition.
+    path: String,
+    /// Prefix for every metric name; empty for none.
+    namespace: String,
 }
 
 impl MetricsConfig {
@@ -145,7 +931,13 @@ impl MetricsConfig {
             .ok()
             .and_then(|s| s.parse::<SocketAddr>().ok())
             .unwrap_or_else(|| "127.0.0.1:9100".parse().expect("valid socket address"));
-        Self { enable, addr }
+        let latency_buckets = std::env::var("VECTARAFT_METRICS_BUCKETS")
+            .ok()
+            .map(|v| parse_buckets(&v))
+            .unwrap_or_default();
+        let path = std::env::var("VECTARAFT_METRICS_PATH").unwrap_or_else(|_| DEFAULT_METRICS_PATH.to_string());
+        let namespace = std::env::var("VECTARAFT_METRICS_NAMESPACE").unwrap_or_default();
+        Self { enable, addr, latency_buckets, path, namespace }
     }
 }
 
@@ -162,3 +954,21 @@ fn parse_bool(input: &str) -> Option<bool> {
         _ => None,
     }
 }
+
+/// Parses a comma-separated list of bucket boundaries, e.g. `0.001,0.01,0.1`.
+/// Returns an empty list (meaning "use defaults") if any entry is invalid.
+fn parse_buckets(input: &str) -> Vec<f64> {
+    let parsed: Result<Vec<f64>, _> = input
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::parse::<f64>)
+        .collect();
+    match parsed {
+        Ok(buckets) => buckets,
+        Err(err) => {
+            tracing::warn!(input, ?err, "invalid latency bucket list; using defaults");
+            Vec::new()
+        }
+    }
+}
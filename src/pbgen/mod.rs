@@ -1,4 +1,9 @@
 // This file is @generated by prost-build.
+pub mod google {
+    pub mod rpc {
+        include!("google.rpc.rs");
+    }
+}
 pub mod vectordb {
     pub mod v1 {
         include!("vectordb.v1.rs");
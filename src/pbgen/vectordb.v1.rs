@@ -9,13 +9,92 @@ pub struct CreateCollectionRequest {
     pub name: ::prost::alloc::string::String,
     #[prost(uint32, tag = "2")]
     pub dims: u32,
-    /// l2 | ip | cosine
+    /// l2 | ip | cosine | chebyshev (linf) | jaccard
     #[prost(string, tag = "3")]
     pub metric: ::prost::alloc::string::String,
+    /// Succeed if the collection already exists with the same dims and metric.
+    #[prost(bool, tag = "4")]
+    pub if_not_exists: bool,
+    /// Points needed before searches scan in parallel; unset = server default.
+    #[prost(uint64, optional, tag = "5")]
+    pub parallel_threshold: ::core::option::Option<u64>,
+    /// Preallocate room for this many points; a hint only, 0 = none.
+    #[prost(uint64, tag = "6")]
+    pub expected_count: u64,
+    /// Vector storage layout: "row_major" (default) or "blocked", which can scan
+    /// faster at high dims but makes reading individual vectors slower.
+    #[prost(string, tag = "7")]
+    pub layout: ::prost::alloc::string::String,
+    /// Cosine only: vectors (stored and query) are already unit length, so
+    /// score with a plain dot product instead of recomputing norms. Upserts of
+    /// vectors that are not near unit length are logged.
+    #[prost(bool, tag = "8")]
+    pub assume_normalized: bool,
+    /// Used by queries that leave top_k / with_payloads unset.
+    #[prost(uint32, optional, tag = "9")]
+    pub default_top_k: ::core::option::Option<u32>,
+    #[prost(bool, tag = "10")]
+    pub default_with_payloads: bool,
+    /// Payload storage: "inline" (default) keeps one payload per stored row;
+    /// "by_id" keeps them in a map keyed by point id, so deletes only compact
+    /// vectors. Rows sharing an id then share the payload written last.
+    #[prost(string, tag = "11")]
+    pub payload_store: ::prost::alloc::string::String,
+    /// Point id storage: "string" (default) or "u64", which stores ids as
+    /// integers to save memory. u64 collections reject ids that are not decimal
+    /// u64s (including empty ones, since generated ids are not integers) and
+    /// report ids in canonical form ("007" reads back as "7").
+    #[prost(string, tag = "12")]
+    pub id_type: ::prost::alloc::string::String,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct CreateCollectionResponse {}
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DescribeCollectionRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DescribeCollectionResponse {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub dims: u32,
+    #[prost(string, tag = "3")]
+    pub metric: ::prost::alloc::string::String,
+    /// "flat"; the only index so far
+    #[prost(string, tag = "4")]
+    pub index_type: ::prost::alloc::string::String,
+    /// "row_major" | "blocked"
+    #[prost(string, tag = "5")]
+    pub layout: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "6")]
+    pub points: u64,
+    #[prost(bool, tag = "7")]
+    pub assume_normalized: bool,
+    /// Per-collection parallel scan threshold; unset when the server default applies.
+    #[prost(uint64, optional, tag = "8")]
+    pub parallel_threshold: ::core::option::Option<u64>,
+    #[prost(uint32, optional, tag = "9")]
+    pub default_top_k: ::core::option::Option<u32>,
+    #[prost(bool, tag = "10")]
+    pub default_with_payloads: bool,
+    /// "inline" | "by_id"
+    #[prost(string, tag = "11")]
+    pub payload_store: ::prost::alloc::string::String,
+    /// "string" | "u64"
+    #[prost(string, tag = "12")]
+    pub id_type: ::prost::alloc::string::String,
+    /// Searches and upsert batches served since the collection was created or
+    /// loaded by this process; replayed WAL records are not counted.
+    #[prost(uint64, tag = "13")]
+    pub queries: u64,
+    #[prost(uint64, tag = "14")]
+    pub upserts: u64,
+    #[prost(bool, tag = "15")]
+    pub pinned: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Point {
     #[prost(string, tag = "1")]
     pub id: ::prost::alloc::string::String,
@@ -24,6 +103,10 @@ pub struct Point {
     /// optional JSON string
     #[prost(string, tag = "3")]
     pub payload_json: ::prost::alloc::string::String,
+    /// Upsert only: hide and later delete the point this many ms after the
+    /// write; 0 = never expires.
+    #[prost(uint64, tag = "4")]
+    pub ttl_ms: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpsertRequest {
@@ -31,11 +114,18 @@ pub struct UpsertRequest {
     pub collection: ::prost::alloc::string::String,
     #[prost(message, repeated, tag = "2")]
     pub points: ::prost::alloc::vec::Vec<Point>,
+    /// validate only; nothing is written to the index or WAL
+    #[prost(bool, tag = "3")]
+    pub dry_run: bool,
 }
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpsertResponse {
     #[prost(uint32, tag = "1")]
     pub upserted: u32,
+    /// Final id of each input point, in input order; empty ids are replaced by
+    /// generated UUIDs. For a dry run the generated ids are not stored.
+    #[prost(string, repeated, tag = "2")]
+    pub ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct QueryRequest {
@@ -43,15 +133,94 @@ pub struct QueryRequest {
     pub collection: ::prost::alloc::string::String,
     #[prost(float, repeated, tag = "2")]
     pub vector: ::prost::alloc::vec::Vec<f32>,
-    #[prost(uint32, tag = "3")]
-    pub top_k: u32,
+    /// Unset = the collection's default_top_k, or 0 (no hits) without one.
+    #[prost(uint32, optional, tag = "3")]
+    pub top_k: ::core::option::Option<u32>,
     /// optional override instead of collection default
     #[prost(string, tag = "4")]
     pub metric_override: ::prost::alloc::string::String,
-    #[prost(bool, tag = "5")]
-    pub with_payloads: bool,
+    /// unset = the collection's default_with_payloads
+    #[prost(bool, optional, tag = "5")]
+    pub with_payloads: ::core::option::Option<bool>,
     #[prost(message, repeated, tag = "6")]
     pub filters: ::prost::alloc::vec::Vec<Filter>,
+    /// never returned; do not count toward top_k
+    #[prost(string, repeated, tag = "7")]
+    pub exclude_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// when set, replaces metric_override
+    #[prost(message, optional, tag = "8")]
+    pub hybrid: ::core::option::Option<HybridScore>,
+    /// When non-empty, only these ids are scored (looked up directly rather than
+    /// scanning the collection). exclude_ids and filters still apply.
+    #[prost(string, repeated, tag = "9")]
+    pub include_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Attach a ScoreExplanation to every returned hit.
+    #[prost(bool, tag = "10")]
+    pub explain: bool,
+    /// Soft filters: a point matching a boost's filters has its boost added to
+    /// its score before ranking. Unlike `filters`, non-matching points are kept.
+    #[prost(message, repeated, tag = "11")]
+    pub boosts: ::prost::alloc::vec::Vec<ScoreBoost>,
+    /// Report every score in \[0, 1\], higher = more similar, whatever the metric
+    /// (see Metric::normalize_score for each mapping). Ranking is unchanged.
+    #[prost(bool, tag = "12")]
+    pub normalize_scores: bool,
+    /// Query with the mean of these stored points' vectors instead of `vector`,
+    /// which must then be empty. Fails if any id is missing or expired.
+    #[prost(string, repeated, tag = "13")]
+    pub centroid_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Score at most this many points, an evenly strided sample in storage
+    /// order, instead of the whole collection (or include_ids). Approximate:
+    /// points outside the sample are never returned. 0 = score every point.
+    #[prost(uint32, tag = "14")]
+    pub max_scan: u32,
+    /// Return at most per_group_limit hits per distinct value of this payload
+    /// field (string, number or bool). Points without it share one "ungrouped"
+    /// group. top_k still bounds the total.
+    #[prost(string, tag = "15")]
+    pub group_by: ::prost::alloc::string::String,
+    /// required (> 0) with group_by
+    #[prost(uint32, tag = "16")]
+    pub per_group_limit: u32,
+    /// Return only the best-scoring hit per distinct value of this payload
+    /// field, before top_k is taken. Points without it are each kept. Cannot
+    /// be combined with group_by.
+    #[prost(string, tag = "17")]
+    pub dedup_by: ::prost::alloc::string::String,
+    /// Report every score as the metric's distance, 0 = identical and lower =
+    /// closer (see Metric::distance): 1 - similarity for cosine and jaccard,
+    /// the Euclidean distance for l2, the max absolute difference for
+    /// chebyshev. Ranking is unchanged. Rejected for ip and hybrid, and with
+    /// normalize_scores.
+    #[prost(bool, tag = "18")]
+    pub report_distance: bool,
+    /// Attach the top hit's per-dimension contributions to its score
+    /// (ScoredPoint.dimension_contributions). l2 and ip only; rejected for
+    /// other metrics and hybrid queries.
+    #[prost(bool, tag = "19")]
+    pub explain_dimensions: bool,
+}
+/// Adds `boost` to the score of every point whose payload matches all of
+/// `filters` (same matching rules as QueryRequest.filters; must be non-empty).
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScoreBoost {
+    #[prost(message, repeated, tag = "1")]
+    pub filters: ::prost::alloc::vec::Vec<Filter>,
+    #[prost(float, tag = "2")]
+    pub boost: f32,
+}
+/// Blends two metrics over the same vectors. Each metric's scores are min-max
+/// normalized to \[0, 1\] across the filtered candidates, then combined as
+/// weight * metric_a + (1 - weight) * metric_b.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HybridScore {
+    #[prost(string, tag = "1")]
+    pub metric_a: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub metric_b: ::prost::alloc::string::String,
+    /// in \[0, 1\]
+    #[prost(float, tag = "3")]
+    pub weight: f32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ScoredPoint {
@@ -62,11 +231,52 @@ pub struct ScoredPoint {
     pub score: f32,
     #[prost(string, tag = "3")]
     pub payload_json: ::prost::alloc::string::String,
+    /// only when QueryRequest.explain is set
+    #[prost(message, optional, tag = "4")]
+    pub explanation: ::core::option::Option<ScoreExplanation>,
+    /// Every candidate was scored, so no better match was skipped. False when
+    /// max_scan sampled the collection; the score itself is always exact.
+    #[prost(bool, tag = "5")]
+    pub exact: bool,
+    /// Only on the first hit, when QueryRequest.explain_dimensions is set: one
+    /// term per dimension of the metric's raw value (see ScoreExplanation.raw),
+    /// (q\[i\] - v\[i\])^2 for l2 and q\[i\] * v\[i\] for ip, so they sum to the
+    /// squared distance (the negated score) or the dot product (the score).
+    /// Boosts are not included.
+    #[prost(float, repeated, tag = "6")]
+    pub dimension_contributions: ::prost::alloc::vec::Vec<f32>,
+}
+/// How a hit's score was produced.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScoreExplanation {
+    /// metric name, or "hybrid(<a>*<w>+<b>*<1-w>)"
+    #[prost(string, tag = "1")]
+    pub metric: ::prost::alloc::string::String,
+    /// The metric's natural value before conversion to a higher-is-better score:
+    /// squared distance for l2, max absolute difference for chebyshev, the
+    /// similarity itself for cosine, ip and jaccard; the blend for hybrid. Query
+    /// boosts are not included.
+    #[prost(float, tag = "2")]
+    pub raw: f32,
+    /// as reported on the hit, so normalized when requested
+    #[prost(float, tag = "3")]
+    pub score: f32,
+    /// Each query filter with the payload value it matched; empty when the
+    /// query had no filters.
+    #[prost(message, repeated, tag = "4")]
+    pub matched_filters: ::prost::alloc::vec::Vec<Filter>,
+    /// Sum of the query boosts this point matched; already included in score.
+    #[prost(float, tag = "5")]
+    pub boost: f32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct QueryResponse {
     #[prost(message, repeated, tag = "1")]
     pub hits: ::prost::alloc::vec::Vec<ScoredPoint>,
+    /// The search scored only a sample (see QueryRequest.max_scan), so hits
+    /// may miss closer points. False for a full flat scan.
+    #[prost(bool, tag = "2")]
+    pub approximate: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Filter {
@@ -75,6 +285,303 @@ pub struct Filter {
     #[prost(string, tag = "2")]
     pub equals: ::prost::alloc::string::String,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RebuildIndexRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RebuildIndexResponse {
+    /// points in the rebuilt index
+    #[prost(uint32, tag = "1")]
+    pub points: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShrinkToFitRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ShrinkToFitResponse {
+    /// Approximate; the allocator may not return all of it to the OS.
+    #[prost(uint64, tag = "1")]
+    pub bytes_freed: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetMetricRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+    /// l2 | ip | cosine | chebyshev (linf) | jaccard
+    #[prost(string, tag = "2")]
+    pub metric: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SetMetricResponse {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetCollectionConfigRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+    /// unset = leave unchanged
+    #[prost(uint64, optional, tag = "2")]
+    pub parallel_threshold: ::core::option::Option<u64>,
+    /// fall back to the server default
+    #[prost(bool, tag = "3")]
+    pub reset_parallel_threshold: bool,
+    /// unset = leave unchanged, 0 = clear
+    #[prost(uint32, optional, tag = "4")]
+    pub default_top_k: ::core::option::Option<u32>,
+    /// unset = leave unchanged
+    #[prost(bool, optional, tag = "5")]
+    pub default_with_payloads: ::core::option::Option<bool>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SetCollectionConfigResponse {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TruncateCollectionRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct TruncateCollectionResponse {
+    /// points dropped
+    #[prost(uint32, tag = "1")]
+    pub removed: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MovePointsRequest {
+    #[prost(string, tag = "1")]
+    pub source: ::prost::alloc::string::String,
+    /// must share dims and metric with source
+    #[prost(string, tag = "2")]
+    pub target: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct MovePointsResponse {
+    #[prost(uint32, tag = "1")]
+    pub moved: u32,
+    /// requested ids not present in source
+    #[prost(uint32, tag = "2")]
+    pub missing: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SwapCollectionsRequest {
+    /// keeps its name; takes source's dims, metric, config and points
+    #[prost(string, tag = "1")]
+    pub target: ::prost::alloc::string::String,
+    /// removed once swapped in
+    #[prost(string, tag = "2")]
+    pub source: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SwapCollectionsResponse {
+    /// points now in target
+    #[prost(uint64, tag = "1")]
+    pub points: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteCollectionsByPrefixRequest {
+    /// must be non-empty
+    #[prost(string, tag = "1")]
+    pub prefix: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteCollectionsByPrefixResponse {
+    #[prost(uint64, tag = "1")]
+    pub deleted: u64,
+    /// names of the dropped collections
+    #[prost(string, repeated, tag = "2")]
+    pub collections: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotCollectionRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotCollectionResponse {
+    #[prost(uint64, tag = "1")]
+    pub points: u64,
+    /// server-side file written; overwritten by the next snapshot
+    #[prost(string, tag = "2")]
+    pub path: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreCollectionRequest {
+    /// must not exist; restored from its last SnapshotCollection
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RestoreCollectionResponse {
+    #[prost(uint64, tag = "1")]
+    pub points: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScrollRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+    /// position in storage order; shifts if points are removed
+    #[prost(uint64, tag = "2")]
+    pub offset: u64,
+    /// 0 = server default
+    #[prost(uint32, tag = "3")]
+    pub limit: u32,
+    #[prost(bool, tag = "4")]
+    pub with_payloads: bool,
+    /// off by default; vectors dominate response size
+    #[prost(bool, tag = "5")]
+    pub with_vectors: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScrollResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub points: ::prost::alloc::vec::Vec<Point>,
+    #[prost(uint64, tag = "2")]
+    pub next_offset: u64,
+    #[prost(bool, tag = "3")]
+    pub has_more: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CollectionChecksumRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CollectionChecksumResponse {
+    /// 16 hex digits; equal for equal contents regardless of insertion order
+    #[prost(string, tag = "1")]
+    pub checksum: ::prost::alloc::string::String,
+    /// points covered by the checksum
+    #[prost(uint64, tag = "2")]
+    pub points: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchByFilterRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+    /// all must match; empty matches every point
+    #[prost(message, repeated, tag = "2")]
+    pub filters: ::prost::alloc::vec::Vec<Filter>,
+    /// top-level payload keys to return; empty = whole payload
+    #[prost(string, repeated, tag = "3")]
+    pub payload_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// number of matching points to skip
+    #[prost(uint64, tag = "4")]
+    pub offset: u64,
+    /// 0 = server default
+    #[prost(uint32, tag = "5")]
+    pub limit: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchByFilterResponse {
+    /// vectors are not returned
+    #[prost(message, repeated, tag = "1")]
+    pub points: ::prost::alloc::vec::Vec<Point>,
+    #[prost(uint64, tag = "2")]
+    pub next_offset: u64,
+    #[prost(bool, tag = "3")]
+    pub has_more: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FindDuplicatesRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+    /// Under the collection metric: the maximum distance for l2 (squared) and
+    /// chebyshev, the minimum similarity for ip, cosine and jaccard.
+    #[prost(float, tag = "2")]
+    pub threshold: f32,
+    /// most pairs returned, closest first; 0 = all
+    #[prost(uint32, tag = "3")]
+    pub limit: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DuplicatePair {
+    /// earlier in storage order
+    #[prost(string, tag = "1")]
+    pub id_a: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub id_b: ::prost::alloc::string::String,
+    /// distance or similarity, as compared with the threshold
+    #[prost(float, tag = "3")]
+    pub raw: f32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FindDuplicatesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub pairs: ::prost::alloc::vec::Vec<DuplicatePair>,
+    /// pairs within the threshold before `limit`
+    #[prost(uint64, tag = "2")]
+    pub total_pairs: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WarmupRequest {
+    /// empty = every collection
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct WarmupResponse {
+    #[prost(uint64, tag = "1")]
+    pub points: u64,
+    /// bytes of vector, id and payload storage read
+    #[prost(uint64, tag = "2")]
+    pub bytes: u64,
+    #[prost(double, tag = "3")]
+    pub elapsed_ms: f64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PinCollectionRequest {
+    #[prost(string, tag = "1")]
+    pub collection: ::prost::alloc::string::String,
+    /// release an earlier pin instead; succeeds even if there was none
+    #[prost(bool, tag = "2")]
+    pub unpin: bool,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PinCollectionResponse {
+    /// points warmed; 0 when unpinning
+    #[prost(uint64, tag = "1")]
+    pub points: u64,
+    #[prost(uint64, tag = "2")]
+    pub bytes: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct FlushRequest {}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct FlushResponse {
+    /// bytes that were buffered in memory before the flush
+    #[prost(uint64, tag = "1")]
+    pub flushed_bytes: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ExportAllRequest {}
+/// One frame of a database export.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportRecord {
+    #[prost(oneof = "export_record::Record", tags = "1, 2")]
+    pub record: ::core::option::Option<export_record::Record>,
+}
+/// Nested message and enum types in `ExportRecord`.
+pub mod export_record {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Record {
+        /// Starts a collection; the points that follow belong to it.
+        #[prost(message, tag = "1")]
+        Collection(super::CreateCollectionRequest),
+        /// `ttl_ms` is the time the point had left when it was exported.
+        #[prost(message, tag = "2")]
+        Point(super::Point),
+    }
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ImportAllResponse {
+    #[prost(uint64, tag = "1")]
+    pub collections: u64,
+    #[prost(uint64, tag = "2")]
+    pub points: u64,
+}
 /// Generated client implementations.
 pub mod vector_db_client {
     #![allow(
@@ -210,6 +717,31 @@ pub mod vector_db_client {
                 .insert(GrpcMethod::new("vectordb.v1.VectorDb", "CreateCollection"));
             self.inner.unary(req, path, codec).await
         }
+        /// Shape and settings of an existing collection; NOT_FOUND if there is none.
+        pub async fn describe_collection(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DescribeCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DescribeCollectionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/DescribeCollection",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "DescribeCollection"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn upsert(
             &mut self,
             request: impl tonic::IntoRequest<super::UpsertRequest>,
@@ -252,134 +784,1541 @@ pub mod vector_db_client {
                 .insert(GrpcMethod::new("vectordb.v1.VectorDb", "Query"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod vector_db_server {
-    #![allow(
-        unused_variables,
-        dead_code,
-        missing_docs,
-        clippy::wildcard_imports,
-        clippy::let_unit_value,
-    )]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with VectorDbServer.
-    #[async_trait]
-    pub trait VectorDb: std::marker::Send + std::marker::Sync + 'static {
-        async fn ping(
-            &self,
-            request: tonic::Request<super::PingRequest>,
-        ) -> std::result::Result<tonic::Response<super::PingResponse>, tonic::Status>;
-        async fn create_collection(
-            &self,
-            request: tonic::Request<super::CreateCollectionRequest>,
+        pub async fn rebuild_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RebuildIndexRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::CreateCollectionResponse>,
+            tonic::Response<super::RebuildIndexResponse>,
             tonic::Status,
-        >;
-        async fn upsert(
-            &self,
-            request: tonic::Request<super::UpsertRequest>,
-        ) -> std::result::Result<tonic::Response<super::UpsertResponse>, tonic::Status>;
-        async fn query(
-            &self,
-            request: tonic::Request<super::QueryRequest>,
-        ) -> std::result::Result<tonic::Response<super::QueryResponse>, tonic::Status>;
-    }
-    #[derive(Debug)]
-    pub struct VectorDbServer<T> {
-        inner: Arc<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    impl<T> VectorDbServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/RebuildIndex",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "RebuildIndex"));
+            self.inner.unary(req, path, codec).await
         }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
+        /// Returns a collection's spare buffer capacity (e.g. left by deletes) to
+        /// the allocator, without rebuilding it.
+        pub async fn shrink_to_fit(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ShrinkToFitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShrinkToFitResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/ShrinkToFit",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "ShrinkToFit"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
+        pub async fn set_metric(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetMetricRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetMetricResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/SetMetric",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "SetMetric"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
+        pub async fn truncate_collection(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TruncateCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::TruncateCollectionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/TruncateCollection",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "TruncateCollection"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
-            self
+        pub async fn move_points(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MovePointsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MovePointsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/MovePoints",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "MovePoints"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
-            self
+        /// Atomically replaces `target` with the contents of `source`, then drops `source`.
+        pub async fn swap_collections(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SwapCollectionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SwapCollectionsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/SwapCollections",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "SwapCollections"));
+            self.inner.unary(req, path, codec).await
         }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for VectorDbServer<T>
-    where
-        T: VectorDb,
-        B: Body + std::marker::Send + 'static,
-        B::Error: Into<StdError> + std::marker::Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
+        /// Drops every collection whose name starts with a non-empty prefix.
+        pub async fn delete_collections_by_prefix(
             &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<std::result::Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
+            request: impl tonic::IntoRequest<super::DeleteCollectionsByPrefixRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteCollectionsByPrefixResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/DeleteCollectionsByPrefix",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("vectordb.v1.VectorDb", "DeleteCollectionsByPrefix"),
+                );
+            self.inner.unary(req, path, codec).await
         }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            match req.uri().path() {
-                "/vectordb.v1.VectorDb/Ping" => {
+        /// Saves one collection to its own snapshot file beside the catalog
+        /// snapshot, and recreates a dropped collection from that file.
+        pub async fn snapshot_collection(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SnapshotCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SnapshotCollectionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/SnapshotCollection",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "SnapshotCollection"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn restore_collection(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreCollectionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/RestoreCollection",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "RestoreCollection"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn scroll(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ScrollRequest>,
+        ) -> std::result::Result<tonic::Response<super::ScrollResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/Scroll",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "Scroll"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Order-independent hash of a collection's points, for backup and replica comparison.
+        pub async fn collection_checksum(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CollectionChecksumRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CollectionChecksumResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/CollectionChecksum",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "CollectionChecksum"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns points matching the filters in storage order, without scoring.
+        pub async fn fetch_by_filter(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FetchByFilterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FetchByFilterResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/FetchByFilter",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "FetchByFilter"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Pairs of points within a similarity threshold of each other. Compares
+        /// every pair, so collections above a size cap are refused.
+        pub async fn find_duplicates(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FindDuplicatesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FindDuplicatesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/FindDuplicates",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "FindDuplicates"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Reads through collection storage to fault pages in before serving queries.
+        pub async fn warmup(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WarmupRequest>,
+        ) -> std::result::Result<tonic::Response<super::WarmupResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/Warmup",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "Warmup"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Pins a collection so it is never evicted from memory, and warms it.
+        pub async fn pin_collection(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PinCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PinCollectionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/PinCollection",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "PinCollection"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Writes buffered WAL records and fsyncs; returns once they are durable.
+        pub async fn flush(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FlushRequest>,
+        ) -> std::result::Result<tonic::Response<super::FlushResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/Flush",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "Flush"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn set_collection_config(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetCollectionConfigRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetCollectionConfigResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/SetCollectionConfig",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "SetCollectionConfig"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Applies each streamed batch as it arrives; batches before a failing one stay written.
+        pub async fn upsert_stream(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::UpsertRequest>,
+        ) -> std::result::Result<tonic::Response<super::UpsertResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/UpsertStream",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "UpsertStream"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        /// Same semantics as Query, with hits streamed in ranked order.
+        pub async fn query_stream(
+            &mut self,
+            request: impl tonic::IntoRequest<super::QueryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ScoredPoint>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/QueryStream",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "QueryStream"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Streams every collection as a header followed by its points. Each
+        /// collection is read under its read lock, so its writers wait until its
+        /// last point has been sent.
+        pub async fn export_all(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ExportAllRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ExportRecord>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/ExportAll",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "ExportAll"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Recreates the collections of an ExportAll stream; each must not exist
+        /// yet. Records before a failing one stay written.
+        pub async fn import_all(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::ExportRecord>,
+        ) -> std::result::Result<
+            tonic::Response<super::ImportAllResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/vectordb.v1.VectorDb/ImportAll",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("vectordb.v1.VectorDb", "ImportAll"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod vector_db_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with VectorDbServer.
+    #[async_trait]
+    pub trait VectorDb: std::marker::Send + std::marker::Sync + 'static {
+        async fn ping(
+            &self,
+            request: tonic::Request<super::PingRequest>,
+        ) -> std::result::Result<tonic::Response<super::PingResponse>, tonic::Status>;
+        async fn create_collection(
+            &self,
+            request: tonic::Request<super::CreateCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateCollectionResponse>,
+            tonic::Status,
+        >;
+        /// Shape and settings of an existing collection; NOT_FOUND if there is none.
+        async fn describe_collection(
+            &self,
+            request: tonic::Request<super::DescribeCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DescribeCollectionResponse>,
+            tonic::Status,
+        >;
+        async fn upsert(
+            &self,
+            request: tonic::Request<super::UpsertRequest>,
+        ) -> std::result::Result<tonic::Response<super::UpsertResponse>, tonic::Status>;
+        async fn query(
+            &self,
+            request: tonic::Request<super::QueryRequest>,
+        ) -> std::result::Result<tonic::Response<super::QueryResponse>, tonic::Status>;
+        async fn rebuild_index(
+            &self,
+            request: tonic::Request<super::RebuildIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RebuildIndexResponse>,
+            tonic::Status,
+        >;
+        /// Returns a collection's spare buffer capacity (e.g. left by deletes) to
+        /// the allocator, without rebuilding it.
+        async fn shrink_to_fit(
+            &self,
+            request: tonic::Request<super::ShrinkToFitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShrinkToFitResponse>,
+            tonic::Status,
+        >;
+        async fn set_metric(
+            &self,
+            request: tonic::Request<super::SetMetricRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetMetricResponse>,
+            tonic::Status,
+        >;
+        async fn truncate_collection(
+            &self,
+            request: tonic::Request<super::TruncateCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::TruncateCollectionResponse>,
+            tonic::Status,
+        >;
+        async fn move_points(
+            &self,
+            request: tonic::Request<super::MovePointsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MovePointsResponse>,
+            tonic::Status,
+        >;
+        /// Atomically replaces `target` with the contents of `source`, then drops `source`.
+        async fn swap_collections(
+            &self,
+            request: tonic::Request<super::SwapCollectionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SwapCollectionsResponse>,
+            tonic::Status,
+        >;
+        /// Drops every collection whose name starts with a non-empty prefix.
+        async fn delete_collections_by_prefix(
+            &self,
+            request: tonic::Request<super::DeleteCollectionsByPrefixRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteCollectionsByPrefixResponse>,
+            tonic::Status,
+        >;
+        /// Saves one collection to its own snapshot file beside the catalog
+        /// snapshot, and recreates a dropped collection from that file.
+        async fn snapshot_collection(
+            &self,
+            request: tonic::Request<super::SnapshotCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SnapshotCollectionResponse>,
+            tonic::Status,
+        >;
+        async fn restore_collection(
+            &self,
+            request: tonic::Request<super::RestoreCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreCollectionResponse>,
+            tonic::Status,
+        >;
+        async fn scroll(
+            &self,
+            request: tonic::Request<super::ScrollRequest>,
+        ) -> std::result::Result<tonic::Response<super::ScrollResponse>, tonic::Status>;
+        /// Order-independent hash of a collection's points, for backup and replica comparison.
+        async fn collection_checksum(
+            &self,
+            request: tonic::Request<super::CollectionChecksumRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CollectionChecksumResponse>,
+            tonic::Status,
+        >;
+        /// Returns points matching the filters in storage order, without scoring.
+        async fn fetch_by_filter(
+            &self,
+            request: tonic::Request<super::FetchByFilterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FetchByFilterResponse>,
+            tonic::Status,
+        >;
+        /// Pairs of points within a similarity threshold of each other. Compares
+        /// every pair, so collections above a size cap are refused.
+        async fn find_duplicates(
+            &self,
+            request: tonic::Request<super::FindDuplicatesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FindDuplicatesResponse>,
+            tonic::Status,
+        >;
+        /// Reads through collection storage to fault pages in before serving queries.
+        async fn warmup(
+            &self,
+            request: tonic::Request<super::WarmupRequest>,
+        ) -> std::result::Result<tonic::Response<super::WarmupResponse>, tonic::Status>;
+        /// Pins a collection so it is never evicted from memory, and warms it.
+        async fn pin_collection(
+            &self,
+            request: tonic::Request<super::PinCollectionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PinCollectionResponse>,
+            tonic::Status,
+        >;
+        /// Writes buffered WAL records and fsyncs; returns once they are durable.
+        async fn flush(
+            &self,
+            request: tonic::Request<super::FlushRequest>,
+        ) -> std::result::Result<tonic::Response<super::FlushResponse>, tonic::Status>;
+        async fn set_collection_config(
+            &self,
+            request: tonic::Request<super::SetCollectionConfigRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetCollectionConfigResponse>,
+            tonic::Status,
+        >;
+        /// Applies each streamed batch as it arrives; batches before a failing one stay written.
+        async fn upsert_stream(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::UpsertRequest>>,
+        ) -> std::result::Result<tonic::Response<super::UpsertResponse>, tonic::Status>;
+        /// Server streaming response type for the QueryStream method.
+        type QueryStreamStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::ScoredPoint, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Same semantics as Query, with hits streamed in ranked order.
+        async fn query_stream(
+            &self,
+            request: tonic::Request<super::QueryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::QueryStreamStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ExportAll method.
+        type ExportAllStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::ExportRecord, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams every collection as a header followed by its points. Each
+        /// collection is read under its read lock, so its writers wait until its
+        /// last point has been sent.
+        async fn export_all(
+            &self,
+            request: tonic::Request<super::ExportAllRequest>,
+        ) -> std::result::Result<tonic::Response<Self::ExportAllStream>, tonic::Status>;
+        /// Recreates the collections of an ExportAll stream; each must not exist
+        /// yet. Records before a failing one stay written.
+        async fn import_all(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::ExportRecord>>,
+        ) -> std::result::Result<
+            tonic::Response<super::ImportAllResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct VectorDbServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> VectorDbServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for VectorDbServer<T>
+    where
+        T: VectorDb,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/vectordb.v1.VectorDb/Ping" => {
+                    #[allow(non_camel_case_types)]
+                    struct PingSvc<T: VectorDb>(pub Arc<T>);
+                    impl<T: VectorDb> tonic::server::UnaryService<super::PingRequest>
+                    for PingSvc<T> {
+                        type Response = super::PingResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PingRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::ping(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = PingSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/CreateCollection" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateCollectionSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::CreateCollectionRequest>
+                    for CreateCollectionSvc<T> {
+                        type Response = super::CreateCollectionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateCollectionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::create_collection(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = CreateCollectionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/DescribeCollection" => {
+                    #[allow(non_camel_case_types)]
+                    struct DescribeCollectionSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::DescribeCollectionRequest>
+                    for DescribeCollectionSvc<T> {
+                        type Response = super::DescribeCollectionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DescribeCollectionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::describe_collection(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DescribeCollectionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/Upsert" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpsertSvc<T: VectorDb>(pub Arc<T>);
+                    impl<T: VectorDb> tonic::server::UnaryService<super::UpsertRequest>
+                    for UpsertSvc<T> {
+                        type Response = super::UpsertResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpsertRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::upsert(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UpsertSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/Query" => {
+                    #[allow(non_camel_case_types)]
+                    struct QuerySvc<T: VectorDb>(pub Arc<T>);
+                    impl<T: VectorDb> tonic::server::UnaryService<super::QueryRequest>
+                    for QuerySvc<T> {
+                        type Response = super::QueryResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::QueryRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::query(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = QuerySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/RebuildIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct RebuildIndexSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::RebuildIndexRequest>
+                    for RebuildIndexSvc<T> {
+                        type Response = super::RebuildIndexResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RebuildIndexRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::rebuild_index(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RebuildIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/ShrinkToFit" => {
+                    #[allow(non_camel_case_types)]
+                    struct ShrinkToFitSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::ShrinkToFitRequest>
+                    for ShrinkToFitSvc<T> {
+                        type Response = super::ShrinkToFitResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ShrinkToFitRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::shrink_to_fit(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ShrinkToFitSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/SetMetric" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetMetricSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::SetMetricRequest>
+                    for SetMetricSvc<T> {
+                        type Response = super::SetMetricResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetMetricRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::set_metric(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetMetricSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/TruncateCollection" => {
+                    #[allow(non_camel_case_types)]
+                    struct TruncateCollectionSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::TruncateCollectionRequest>
+                    for TruncateCollectionSvc<T> {
+                        type Response = super::TruncateCollectionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TruncateCollectionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::truncate_collection(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = TruncateCollectionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/MovePoints" => {
+                    #[allow(non_camel_case_types)]
+                    struct MovePointsSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::MovePointsRequest>
+                    for MovePointsSvc<T> {
+                        type Response = super::MovePointsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MovePointsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::move_points(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = MovePointsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/SwapCollections" => {
+                    #[allow(non_camel_case_types)]
+                    struct SwapCollectionsSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::SwapCollectionsRequest>
+                    for SwapCollectionsSvc<T> {
+                        type Response = super::SwapCollectionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SwapCollectionsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::swap_collections(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SwapCollectionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/DeleteCollectionsByPrefix" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteCollectionsByPrefixSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<
+                        super::DeleteCollectionsByPrefixRequest,
+                    > for DeleteCollectionsByPrefixSvc<T> {
+                        type Response = super::DeleteCollectionsByPrefixResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::DeleteCollectionsByPrefixRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::delete_collections_by_prefix(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DeleteCollectionsByPrefixSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/SnapshotCollection" => {
+                    #[allow(non_camel_case_types)]
+                    struct SnapshotCollectionSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::SnapshotCollectionRequest>
+                    for SnapshotCollectionSvc<T> {
+                        type Response = super::SnapshotCollectionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SnapshotCollectionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::snapshot_collection(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SnapshotCollectionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/RestoreCollection" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreCollectionSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::RestoreCollectionRequest>
+                    for RestoreCollectionSvc<T> {
+                        type Response = super::RestoreCollectionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreCollectionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::restore_collection(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RestoreCollectionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/Scroll" => {
+                    #[allow(non_camel_case_types)]
+                    struct ScrollSvc<T: VectorDb>(pub Arc<T>);
+                    impl<T: VectorDb> tonic::server::UnaryService<super::ScrollRequest>
+                    for ScrollSvc<T> {
+                        type Response = super::ScrollResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ScrollRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::scroll(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ScrollSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/CollectionChecksum" => {
+                    #[allow(non_camel_case_types)]
+                    struct CollectionChecksumSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::CollectionChecksumRequest>
+                    for CollectionChecksumSvc<T> {
+                        type Response = super::CollectionChecksumResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CollectionChecksumRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::collection_checksum(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = CollectionChecksumSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/FetchByFilter" => {
                     #[allow(non_camel_case_types)]
-                    struct PingSvc<T: VectorDb>(pub Arc<T>);
-                    impl<T: VectorDb> tonic::server::UnaryService<super::PingRequest>
-                    for PingSvc<T> {
-                        type Response = super::PingResponse;
+                    struct FetchByFilterSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::FetchByFilterRequest>
+                    for FetchByFilterSvc<T> {
+                        type Response = super::FetchByFilterResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PingRequest>,
+                            request: tonic::Request<super::FetchByFilterRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as VectorDb>::ping(&inner, request).await
+                                <T as VectorDb>::fetch_by_filter(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -390,7 +2329,7 @@ pub mod vector_db_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = PingSvc(inner);
+                        let method = FetchByFilterSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -406,25 +2345,25 @@ pub mod vector_db_server {
                     };
                     Box::pin(fut)
                 }
-                "/vectordb.v1.VectorDb/CreateCollection" => {
+                "/vectordb.v1.VectorDb/FindDuplicates" => {
                     #[allow(non_camel_case_types)]
-                    struct CreateCollectionSvc<T: VectorDb>(pub Arc<T>);
+                    struct FindDuplicatesSvc<T: VectorDb>(pub Arc<T>);
                     impl<
                         T: VectorDb,
-                    > tonic::server::UnaryService<super::CreateCollectionRequest>
-                    for CreateCollectionSvc<T> {
-                        type Response = super::CreateCollectionResponse;
+                    > tonic::server::UnaryService<super::FindDuplicatesRequest>
+                    for FindDuplicatesSvc<T> {
+                        type Response = super::FindDuplicatesResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::CreateCollectionRequest>,
+                            request: tonic::Request<super::FindDuplicatesRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as VectorDb>::create_collection(&inner, request).await
+                                <T as VectorDb>::find_duplicates(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -435,7 +2374,7 @@ pub mod vector_db_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = CreateCollectionSvc(inner);
+                        let method = FindDuplicatesSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -451,23 +2390,23 @@ pub mod vector_db_server {
                     };
                     Box::pin(fut)
                 }
-                "/vectordb.v1.VectorDb/Upsert" => {
+                "/vectordb.v1.VectorDb/Warmup" => {
                     #[allow(non_camel_case_types)]
-                    struct UpsertSvc<T: VectorDb>(pub Arc<T>);
-                    impl<T: VectorDb> tonic::server::UnaryService<super::UpsertRequest>
-                    for UpsertSvc<T> {
-                        type Response = super::UpsertResponse;
+                    struct WarmupSvc<T: VectorDb>(pub Arc<T>);
+                    impl<T: VectorDb> tonic::server::UnaryService<super::WarmupRequest>
+                    for WarmupSvc<T> {
+                        type Response = super::WarmupResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::UpsertRequest>,
+                            request: tonic::Request<super::WarmupRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as VectorDb>::upsert(&inner, request).await
+                                <T as VectorDb>::warmup(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -478,7 +2417,7 @@ pub mod vector_db_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = UpsertSvc(inner);
+                        let method = WarmupSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -494,23 +2433,25 @@ pub mod vector_db_server {
                     };
                     Box::pin(fut)
                 }
-                "/vectordb.v1.VectorDb/Query" => {
+                "/vectordb.v1.VectorDb/PinCollection" => {
                     #[allow(non_camel_case_types)]
-                    struct QuerySvc<T: VectorDb>(pub Arc<T>);
-                    impl<T: VectorDb> tonic::server::UnaryService<super::QueryRequest>
-                    for QuerySvc<T> {
-                        type Response = super::QueryResponse;
+                    struct PinCollectionSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::PinCollectionRequest>
+                    for PinCollectionSvc<T> {
+                        type Response = super::PinCollectionResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::QueryRequest>,
+                            request: tonic::Request<super::PinCollectionRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as VectorDb>::query(&inner, request).await
+                                <T as VectorDb>::pin_collection(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -521,7 +2462,96 @@ pub mod vector_db_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = QuerySvc(inner);
+                        let method = PinCollectionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/Flush" => {
+                    #[allow(non_camel_case_types)]
+                    struct FlushSvc<T: VectorDb>(pub Arc<T>);
+                    impl<T: VectorDb> tonic::server::UnaryService<super::FlushRequest>
+                    for FlushSvc<T> {
+                        type Response = super::FlushResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FlushRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::flush(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = FlushSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/SetCollectionConfig" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetCollectionConfigSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::UnaryService<super::SetCollectionConfigRequest>
+                    for SetCollectionConfigSvc<T> {
+                        type Response = super::SetCollectionConfigResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetCollectionConfigRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::set_collection_config(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetCollectionConfigSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -537,6 +2567,192 @@ pub mod vector_db_server {
                     };
                     Box::pin(fut)
                 }
+                "/vectordb.v1.VectorDb/UpsertStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpsertStreamSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::ClientStreamingService<super::UpsertRequest>
+                    for UpsertStreamSvc<T> {
+                        type Response = super::UpsertResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::UpsertRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::upsert_stream(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UpsertStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/QueryStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct QueryStreamSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::ServerStreamingService<super::QueryRequest>
+                    for QueryStreamSvc<T> {
+                        type Response = super::ScoredPoint;
+                        type ResponseStream = T::QueryStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::QueryRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::query_stream(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = QueryStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/ExportAll" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportAllSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::ServerStreamingService<super::ExportAllRequest>
+                    for ExportAllSvc<T> {
+                        type Response = super::ExportRecord;
+                        type ResponseStream = T::ExportAllStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExportAllRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::export_all(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ExportAllSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/vectordb.v1.VectorDb/ImportAll" => {
+                    #[allow(non_camel_case_types)]
+                    struct ImportAllSvc<T: VectorDb>(pub Arc<T>);
+                    impl<
+                        T: VectorDb,
+                    > tonic::server::ClientStreamingService<super::ExportRecord>
+                    for ImportAllSvc<T> {
+                        type Response = super::ImportAllResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::ExportRecord>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as VectorDb>::import_all(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ImportAllSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(empty_body());
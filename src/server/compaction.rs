@@ -0,0 +1,35 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::server::state::DbState;
+
+/// Share of freed rows above which a collection is rebuilt when automatic
+/// compaction is enabled without an explicit threshold.
+pub const DEFAULT_COMPACTION_THRESHOLD: f64 = 0.3;
+
+/// Periodically rebuilds collections whose deletes have freed more than
+/// `threshold` of their rows (see `Collection::freed_ratio`), returning that
+/// memory. Searches and writes keep running during a rebuild; writers wait
+/// only for the swap.
+pub fn spawn(state: Arc<DbState>, interval: Duration, threshold: f64) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        ticker.tick().await; // the first tick fires immediately
+        loop {
+            ticker.tick().await;
+            let state = state.clone();
+            let started = Instant::now();
+            match tokio::task::spawn_blocking(move || state.catalog.compact_sparse(threshold)).await {
+                Ok(compacted) if compacted.is_empty() => {}
+                Ok(compacted) => tracing::info!(
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    collections = ?compacted,
+                    "sparse collections compacted"
+                ),
+                Err(err) => tracing::error!(?err, "compaction panicked"),
+            }
+        }
+    })
+}
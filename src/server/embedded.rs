@@ -0,0 +1,119 @@
+//! In-process client that calls `VectorDbService` directly, without a
+//! socket or tonic transport. Requests still go through the full handler
+//! path (validation, WAL, metrics), so behavior matches the gRPC server.
+
+use std::sync::Arc;
+
+use tonic::{Request, Status};
+
+use crate::pb::vectordb::v1::{
+    vector_db_server::VectorDb, CreateCollectionRequest, Filter, Point, QueryRequest, ScoredPoint,
+    UpsertRequest, UpsertResponse,
+};
+use crate::server::grpc::VectorDbService;
+use crate::server::state::DbState;
+
+#[derive(Clone)]
+pub struct EmbeddedClient {
+    service: VectorDbService,
+}
+
+impl EmbeddedClient {
+    /// Wraps a state without metrics.
+    pub fn new(state: Arc<DbState>) -> Self {
+        Self::from_service(VectorDbService { state, metrics: None })
+    }
+
+    pub fn from_service(service: VectorDbService) -> Self {
+        Self { service }
+    }
+
+    /// The wrapped service, for RPCs without a helper here.
+    pub fn service(&self) -> &VectorDbService {
+        &self.service
+    }
+
+    pub fn state(&self) -> &Arc<DbState> {
+        &self.service.state
+    }
+
+    /// Creates a collection with default options; `metric` accepts the same
+    /// names as CreateCollection ("" uses the server default).
+    pub async fn create_collection(&self, name: &str, dims: u32, metric: &str) -> Result<(), Status> {
+        self.service
+            .create_collection(Request::new(CreateCollectionRequest {
+                name: name.into(),
+                dims,
+                metric: metric.into(),
+                if_not_exists: false,
+                parallel_threshold: None,
+                expected_count: 0,
+                layout: String::new(),
+                assume_normalized: false,
+                default_top_k: None,
+                default_with_payloads: false,
+                payload_store: String::new(),
+                id_type: String::new(),
+            }))
+            .await?;
+        Ok(())
+    }
+
+    /// Upserts `points`; build them with [`point`].
+    pub async fn upsert(&self, collection: &str, points: Vec<Point>) -> Result<UpsertResponse, Status> {
+        let resp = self
+            .service
+            .upsert(Request::new(UpsertRequest { collection: collection.into(), points, dry_run: false }))
+            .await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Returns the `top_k` nearest hits, with payloads.
+    pub async fn query(&self, collection: &str, vector: Vec<f32>, top_k: u32) -> Result<Vec<ScoredPoint>, Status> {
+        self.query_filtered(collection, vector, top_k, &[]).await
+    }
+
+    /// Like `query`, keeping only points whose payload has `key == value`
+    /// for every `(key, value)` pair.
+    pub async fn query_filtered(
+        &self,
+        collection: &str,
+        vector: Vec<f32>,
+        top_k: u32,
+        filters: &[(&str, &str)],
+    ) -> Result<Vec<ScoredPoint>, Status> {
+        let resp = self
+            .service
+            .query(Request::new(QueryRequest {
+                collection: collection.into(),
+                vector,
+                top_k: Some(top_k),
+                metric_override: String::new(),
+                with_payloads: Some(true),
+                filters: filters
+                    .iter()
+                    .map(|(key, equals)| Filter { key: (*key).into(), equals: (*equals).into() })
+                    .collect(),
+                exclude_ids: vec![],
+                hybrid: None,
+                include_ids: vec![],
+                explain: false,
+                boosts: vec![],
+                normalize_scores: false,
+                centroid_ids: vec![],
+                max_scan: 0,
+                group_by: String::new(),
+                per_group_limit: 0,
+                dedup_by: String::new(),
+                report_distance: false,
+                explain_dimensions: false,
+            }))
+            .await?;
+        Ok(resp.into_inner().hits)
+    }
+}
+
+/// Builds a non-expiring point; an empty `id` is generated on upsert.
+pub fn point(id: &str, vector: Vec<f32>, payload_json: &str) -> Point {
+    Point { id: id.into(), vector, payload_json: payload_json.into(), ttl_ms: 0 }
+}
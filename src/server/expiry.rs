@@ -0,0 +1,29 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::server::state::DbState;
+
+/// Periodically deletes points whose TTL has run out. Queries already skip
+/// expired points, so the sweep only reclaims memory and logs the deletes.
+pub fn spawn(state: Arc<DbState>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        ticker.tick().await; // the first tick fires immediately
+        loop {
+            ticker.tick().await;
+            let state = state.clone();
+            let started = Instant::now();
+            match tokio::task::spawn_blocking(move || state.sweep_expired()).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!(
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    removed,
+                    "expired points swept"
+                ),
+                Err(err) => tracing::error!(?err, "expiry sweep panicked"),
+            }
+        }
+    })
+}
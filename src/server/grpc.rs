@@ -1,22 +1,51 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use tonic::{Request, Response, Status};
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+use tokio_stream::Stream;
+use prost::Message;
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status, Streaming};
 
-use crate::catalog::PointWrite;
+use crate::catalog::{Collection, CreateError, GroupBy, HybridScore, IdFilter, MoveError, PointWrite, ScoreBoost, SwapError, UpsertError, MAX_DUPLICATE_SCAN_POINTS};
+use crate::index::flat::BatchError;
+use crate::server::request_id::request_id;
+use crate::pb::google::rpc::{ErrorInfo, Status as RpcStatus};
 use crate::pb::vectordb::v1::{
-    vector_db_server::VectorDb,
+    vector_db_server::{VectorDb, VectorDbServer},
     CreateCollectionRequest, CreateCollectionResponse,
+    DescribeCollectionRequest, DescribeCollectionResponse,
     PingRequest, PingResponse,
     QueryRequest, QueryResponse,
-    ScoredPoint,
+    RebuildIndexRequest, RebuildIndexResponse, ShrinkToFitRequest, ShrinkToFitResponse,
+    SetMetricRequest, SetMetricResponse,
+    SetCollectionConfigRequest, SetCollectionConfigResponse,
+    TruncateCollectionRequest, TruncateCollectionResponse,
+    MovePointsRequest, MovePointsResponse,
+    SwapCollectionsRequest, SwapCollectionsResponse,
+    DeleteCollectionsByPrefixRequest, DeleteCollectionsByPrefixResponse,
+    SnapshotCollectionRequest, SnapshotCollectionResponse,
+    RestoreCollectionRequest, RestoreCollectionResponse,
+    Point, ScrollRequest, ScrollResponse,
+    CollectionChecksumRequest, CollectionChecksumResponse,
+    FetchByFilterRequest, FetchByFilterResponse,
+    FlushRequest, FlushResponse,
+    DuplicatePair, FindDuplicatesRequest, FindDuplicatesResponse,
+    export_record::Record, ExportAllRequest, ExportRecord, ImportAllResponse,
+    WarmupRequest, WarmupResponse, PinCollectionRequest, PinCollectionResponse,
+    Filter, ScoreExplanation, ScoredPoint,
     UpsertRequest, UpsertResponse,
 };
-use crate::server::state::DbState;
+use crate::server::state::{DbState, RestoreError};
+use crate::storage::snapshot::SnapshotError;
 use crate::storage::wal::WalRecord;
-use crate::types::Metric;
+use crate::types::{EmptyQuery, IdKind, Metric, PayloadStore, VectorLayout};
 use crate::telemetry::Metrics;
-use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct VectorDbService {
@@ -24,6 +53,135 @@ pub struct VectorDbService {
     pub metrics: Option<Arc<Metrics>>,
 }
 
+/// How far a squared norm may stray from 1 before an upsert into an
+/// `assume_normalized` collection is logged. Loose enough for f32 rounding
+/// of normalized embeddings at any realistic dimension.
+const UNIT_NORM_TOLERANCE: f32 = 1e-3;
+
+/// Default cap on encoded/decoded gRPC messages. Large batch upserts routinely
+/// exceed tonic's 4 MiB default; each in-flight request may buffer up to this
+/// much, so size it against expected concurrency and available memory.
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Connection keepalive applied to the gRPC server. `None` disables the
+/// corresponding probe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// Interval between HTTP/2 PING frames sent to idle clients.
+    pub http2_interval: Option<Duration>,
+    /// How long to wait for a PING ack before closing the connection.
+    pub http2_timeout: Option<Duration>,
+    /// TCP-level keepalive probe interval on accepted sockets.
+    pub tcp: Option<Duration>,
+}
+
+impl Default for KeepaliveConfig {
+    /// Pings well inside the ~60s idle timeout common to cloud load balancers.
+    fn default() -> Self {
+        Self {
+            http2_interval: Some(Duration::from_secs(20)),
+            http2_timeout: Some(Duration::from_secs(20)),
+            tcp: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl KeepaliveConfig {
+    pub fn apply(&self, builder: Server) -> Server {
+        builder
+            .http2_keepalive_interval(self.http2_interval)
+            .http2_keepalive_timeout(self.http2_timeout)
+            .tcp_keepalive(self.tcp)
+    }
+}
+
+/// `listen(2)` backlog used when none is configured: connections the kernel
+/// queues before they are accepted. Linux silently caps it at
+/// `net.core.somaxconn`.
+pub const DEFAULT_LISTEN_BACKLOG: u32 = 1024;
+
+/// Binds the gRPC listener with an explicit accept backlog. Must be called
+/// from within a tokio runtime.
+pub fn bind_listener(addr: SocketAddr, backlog: u32) -> std::io::Result<TcpListener> {
+    let socket = if addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)?;
+    socket.listen(backlog)
+}
+
+/// Builds the multi-threaded runtime that accepts connections and runs the
+/// RPC handlers. `None` keeps tokio's default of one worker per CPU. Search
+/// scans run on the rayon pool, which this does not size.
+pub fn build_runtime(worker_threads: Option<usize>) -> std::io::Result<Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    if let Some(threads) = worker_threads {
+        builder.worker_threads(threads);
+    }
+    builder.enable_all().build()
+}
+
+/// Page size used when a Scroll request leaves `limit` unset.
+const DEFAULT_SCROLL_LIMIT: usize = 100;
+
+/// ExportAll frames queued ahead of a slow client before the exporter waits.
+const EXPORT_BUFFER_FRAMES: usize = 256;
+
+/// Ids whose points ExportAll copies per collection read lock.
+const EXPORT_CHUNK_IDS: usize = 512;
+
+/// Points ImportAll applies per upsert batch.
+const IMPORT_BATCH_POINTS: usize = 1024;
+
+/// `google.rpc.ErrorInfo` domain and reasons attached to error statuses so
+/// clients can branch on the failure without parsing the message.
+pub const ERROR_DOMAIN: &str = "vectaraft";
+pub const REASON_COLLECTION_NOT_FOUND: &str = "COLLECTION_NOT_FOUND";
+pub const REASON_DIMENSION_MISMATCH: &str = "DIMENSION_MISMATCH";
+
+const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+fn status_with_reason(code: Code, message: impl Into<String>, reason: &str) -> Status {
+    let message = message.into();
+    let info = ErrorInfo {
+        reason: reason.to_string(),
+        domain: ERROR_DOMAIN.to_string(),
+        metadata: HashMap::new(),
+    };
+    let details = RpcStatus {
+        code: code as i32,
+        message: message.clone(),
+        details: vec![prost_types::Any {
+            type_url: ERROR_INFO_TYPE_URL.to_string(),
+            value: info.encode_to_vec(),
+        }],
+    };
+    Status::with_details(code, message, details.encode_to_vec().into())
+}
+
+/// Decodes the `google.rpc.ErrorInfo` attached to `status`, if any.
+pub fn error_info(status: &Status) -> Option<ErrorInfo> {
+    let details = RpcStatus::decode(status.details()).ok()?;
+    details
+        .details
+        .iter()
+        .find(|any| any.type_url == ERROR_INFO_TYPE_URL)
+        .and_then(|any| ErrorInfo::decode(any.value.as_slice()).ok())
+}
+
+fn collection_not_found(message: impl Into<String>) -> Status {
+    status_with_reason(Code::NotFound, message, REASON_COLLECTION_NOT_FOUND)
+}
+
+fn no_collection_snapshot_path() -> Status {
+    Status::failed_precondition(
+        "collection snapshots need a configured snapshot path and a collection name usable as a file name",
+    )
+}
+
+fn dimension_mismatch(message: impl Into<String>) -> Status {
+    status_with_reason(Code::InvalidArgument, message, REASON_DIMENSION_MISMATCH)
+}
+
 fn now_ms() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -31,162 +189,1642 @@ fn now_ms() -> i64 {
         .unwrap_or_default()
 }
 
-impl VectorDbService {
-    fn record_metric<S: AsRef<str>>(&self, method: &str, status: S) {
-        if let Some(metrics) = &self.metrics {
-            metrics.record_grpc(method, status.as_ref());
+/// Checks a point before it is written: non-empty, matching dimension,
+/// finite components, and a payload that is either empty or valid JSON.
+#[allow(clippy::result_large_err)]
+fn validate_point(label: &str, vector: &[f32], payload_json: &str, dim: usize) -> Result<(), Status> {
+    if vector.is_empty() {
+        return Err(Status::invalid_argument("point vector must not be empty"));
+    }
+    if vector.len() != dim {
+        return Err(dimension_mismatch(format!(
+            "point {label} vector dimension mismatch: expected {dim}, got {}",
+            vector.len()
+        )));
+    }
+    if vector.iter().any(|x| !x.is_finite()) {
+        return Err(Status::invalid_argument("point vector must contain only finite values"));
+    }
+    if !payload_json.is_empty() && serde_json::from_str::<serde_json::Value>(payload_json).is_err() {
+        return Err(Status::invalid_argument("payload_json must be valid JSON"));
+    }
+    Ok(())
+}
+
+/// Builds a hit's explanation from its reported score and payload; no
+/// vectors are touched, so the cost is bounded by top_k.
+fn explain_hit(
+    name: &str,
+    metric: Option<Metric>,
+    score: f32,
+    payload: &str,
+    filters: &[(String, String)],
+    boosts: &[ScoreBoost],
+) -> ScoreExplanation {
+    let boost = ScoreBoost::total(boosts, payload);
+    let base = score - boost;
+    let raw = if metric.is_some_and(|m| m.is_distance()) { -base } else { base };
+    let matched_filters = if filters.is_empty() {
+        Vec::new()
+    } else {
+        let fields = serde_json::from_str::<serde_json::Value>(payload).unwrap_or_default();
+        filters
+            .iter()
+            .map(|(key, _)| Filter {
+                key: key.clone(),
+                equals: match fields.get(key) {
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(other) => other.to_string(),
+                    None => String::new(),
+                },
+            })
+            .collect()
+    };
+    ScoreExplanation { metric: name.to_string(), raw, score, matched_filters, boost }
+}
+
+/// Sends the collections in `names` to `tx` as ExportAll frames. Each
+/// collection's ids are listed under one read lock, then its points are
+/// copied `EXPORT_CHUNK_IDS` ids at a time, each chunk under its own read
+/// lock that is released before the chunk is sent, so a slow client never
+/// holds up writers. Points deleted mid-export are skipped and points
+/// upserted after the listing are left out. Blocks on a full channel, so it
+/// runs on a blocking thread; stops once the client goes away.
+fn export_frames(state: &DbState, names: Vec<String>, tx: &mpsc::Sender<Result<ExportRecord, Status>>) {
+    let send = |record| tx.blocking_send(Ok(ExportRecord { record: Some(record) })).is_ok();
+    for name in names {
+        // Dropped since it was listed.
+        let Some(handle) = state.catalog.get(&name) else { continue; };
+        let listed = handle.with_ref(|coll| {
+            let now = now_ms();
+            let live = (0..coll.index.len()).filter(|&idx| !coll.index.is_expired(idx, now)).count();
+            // Each id once, at its first row; a chunk exports all of its rows.
+            let ids: Vec<String> = (0..coll.index.len())
+                .map(|idx| (idx, coll.index.id(idx)))
+                .filter(|(idx, id)| coll.index.positions(id).first() == Some(idx))
+                .map(|(_, id)| id.into_owned())
+                .collect();
+            let header = CreateCollectionRequest {
+                name: coll.name.clone(),
+                dims: coll.dim as u32,
+                metric: coll.metric.as_str().to_string(),
+                if_not_exists: false,
+                parallel_threshold: coll.parallel_threshold.map(|t| t as u64),
+                expected_count: live as u64,
+                layout: coll.index.layout.as_str().to_string(),
+                assume_normalized: coll.assume_normalized,
+                default_top_k: coll.default_top_k,
+                default_with_payloads: coll.default_with_payloads,
+                payload_store: coll.index.payload_store.as_str().to_string(),
+                id_type: coll.index.id_kind().as_str().to_string(),
+            };
+            (header, ids)
+        });
+        let Some((header, ids)) = listed else { continue; };
+        if !send(Record::Collection(header)) {
+            return;
+        }
+        for chunk in ids.chunks(EXPORT_CHUNK_IDS) {
+            let Some(points) = handle.with_ref(|coll| export_points(coll, chunk)) else { break; };
+            for point in points {
+                if !send(Record::Point(point)) {
+                    return;
+                }
+            }
         }
     }
+}
 
-    fn refresh_inventory_metrics(&self) {
-        if let Some(metrics) = &self.metrics {
-            metrics.set_collection_count(self.state.catalog.len());
-            metrics.set_point_count(self.state.catalog.total_points());
+/// The unexpired rows of `ids` in `coll`, with their remaining TTLs.
+fn export_points(coll: &Collection, ids: &[String]) -> Vec<Point> {
+    let now = now_ms();
+    let mut points = Vec::with_capacity(ids.len());
+    for id in ids {
+        for &idx in coll.index.positions(id) {
+            let ttl_ms = match coll.index.expires_at_ms[idx] {
+                Some(at) if at <= now => continue,
+                Some(at) => (at - now) as u64,
+                None => 0,
+            };
+            points.push(Point {
+                id: id.clone(),
+                vector: coll.index.row(idx).into_owned(),
+                payload_json: coll.index.payload(idx).to_string(),
+                ttl_ms,
+            });
         }
     }
+    points
+}
 
-    fn fail<T>(&self, method: &str, status: Status) -> Result<T, Status> {
-        self.record_metric(method, status.code().to_string());
-        Err(status)
+/// Canonical upper-case name of a status code, as used in the gRPC spec.
+fn code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
     }
 }
 
-#[tonic::async_trait]
-impl VectorDb for VectorDbService {
-    async fn ping(
-        &self,
-        _req: Request<PingRequest>,
-    ) -> Result<Response<PingResponse>, Status> {
-        self.record_metric("Ping", "OK");
-        Ok(Response::new(PingResponse {}))
+/// Strictly resolves a client-supplied metric name so typos are reported
+/// instead of silently falling back to L2.
+#[allow(clippy::result_large_err)]
+fn parse_metric(name: &str) -> Result<Metric, Status> {
+    Metric::parse(name).ok_or_else(|| {
+        Status::invalid_argument(format!(
+            "unrecognized metric '{name}' (expected l2, ip, cosine, chebyshev, or jaccard)"
+        ))
+    })
+}
+
+impl VectorDbService {
+    /// Wraps the service in a tonic server with the given message size cap
+    /// applied to both directions.
+    pub fn into_server(self, max_message_bytes: usize) -> VectorDbServer<Self> {
+        VectorDbServer::new(self)
+            .max_decoding_message_size(max_message_bytes)
+            .max_encoding_message_size(max_message_bytes)
     }
 
-    async fn create_collection(
-        &self,
-        req: Request<CreateCollectionRequest>,
-    ) -> Result<Response<CreateCollectionResponse>, Status> {
-        let req = req.into_inner();
+    /// Holds the response until the request's WAL records are fsynced, when
+    /// group commit is enabled.
+    async fn commit(&self) -> Result<(), Status> {
+        self.state
+            .commit_wal()
+            .await
+            .map_err(|err| Status::internal(format!("write applied but not yet durable: {err:#}")))
+    }
+
+    fn record_metric<S: AsRef<str>>(&self, method: &str, status: S, started: Instant) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_grpc(method, status.as_ref());
+            metrics.observe_latency(method, started.elapsed());
+        }
+    }
+
+    /// Validates and applies a CreateCollection request, logging it to the
+    /// WAL without committing. Returns `false` when `if_not_exists` matched
+    /// an existing collection and nothing was created.
+    #[allow(clippy::result_large_err)]
+    fn apply_create_collection(&self, req: CreateCollectionRequest) -> Result<bool, Status> {
+        self.ensure_writable()?;
         if req.name.is_empty() {
-            return self.fail("CreateCollection", Status::invalid_argument("collection name must be provided"));
+            return Err(Status::invalid_argument("collection name must be provided"));
         }
         if req.dims == 0 {
-            return self.fail("CreateCollection", Status::invalid_argument("dims must be greater than zero"));
-        }
-        let metric = Metric::from_str(&req.metric);
-        let created = self
-            .state
-            .catalog
-            .create_collection(req.name.clone(), req.dims as usize, metric);
-        if !created {
-            return self.fail("CreateCollection", Status::already_exists("collection already exists"));
-        }
-        self.state.append_wal(WalRecord::CreateCollection {
-            name: req.name,
+            return Err(Status::invalid_argument("dims must be greater than zero"));
+        }
+        if req.dims as usize > self.state.max_dims {
+            return Err(Status::invalid_argument(format!(
+                "dims {} exceeds the limit of {}",
+                req.dims, self.state.max_dims
+            )));
+        }
+        let metric = if req.metric.is_empty() {
+            self.state.default_metric
+        } else {
+            parse_metric(&req.metric)?
+        };
+        let layout = if req.layout.is_empty() {
+            VectorLayout::RowMajor
+        } else {
+            match VectorLayout::parse(&req.layout) {
+                Some(layout) => layout,
+                None => return Err(Status::invalid_argument(format!("unknown layout '{}'", req.layout))),
+            }
+        };
+        let payload_store = if req.payload_store.is_empty() {
+            PayloadStore::Inline
+        } else {
+            match PayloadStore::parse(&req.payload_store) {
+                Some(store) => store,
+                None => return Err(Status::invalid_argument(format!("unknown payload_store '{}'", req.payload_store))),
+            }
+        };
+        let id_kind = if req.id_type.is_empty() {
+            IdKind::String
+        } else {
+            match IdKind::parse(&req.id_type) {
+                Some(kind) => kind,
+                None => return Err(Status::invalid_argument(format!("unknown id_type '{}'", req.id_type))),
+            }
+        };
+        let default_top_k = req.default_top_k.filter(|&k| k > 0);
+        // Fully configured before it is visible, so no write can reach it
+        // with the default layout or id type.
+        let mut collection = Collection::new(req.name.clone(), req.dims as usize, metric);
+        collection.index.set_layout(layout);
+        collection.index.set_payload_store(payload_store);
+        if let Err(id) = collection.index.set_id_kind(id_kind) {
+            return Err(Status::internal(format!("cannot store id '{id}' as {}", id_kind.as_str())));
+        }
+        collection.assume_normalized = req.assume_normalized;
+        collection.default_top_k = default_top_k;
+        collection.default_with_payloads = req.default_with_payloads;
+        collection.parallel_threshold = req.parallel_threshold.map(|threshold| threshold as usize);
+        if req.expected_count > 0 {
+            if let Err(err) = collection.index.reserve(req.expected_count as usize) {
+                tracing::warn!(expected_count = req.expected_count, %err, "ignoring expected_count hint");
+            }
+        }
+        let record = WalRecord::CreateCollection {
+            name: req.name.clone(),
             dim: req.dims,
-            metric: req.metric,
+            metric: metric.as_str().to_string(),
+            parallel_threshold: req.parallel_threshold,
+            layout: (layout != VectorLayout::RowMajor).then(|| layout.as_str().to_string()),
+            assume_normalized: req.assume_normalized,
+            default_top_k,
+            default_with_payloads: req.default_with_payloads,
+            payload_store: (payload_store != PayloadStore::Inline).then(|| payload_store.as_str().to_string()),
+            id_type: (id_kind != IdKind::String).then(|| id_kind.as_str().to_string()),
             ts_ms: now_ms(),
+        };
+        let created = self.state.catalog.create_collection_with(collection, self.state.max_collections, || {
+            self.state.append_wal(record)
         });
-        self.refresh_inventory_metrics();
-        self.record_metric("CreateCollection", "OK");
-        Ok(Response::new(CreateCollectionResponse {}))
+        match created {
+            Ok(()) => {}
+            Err(err @ CreateError::LimitReached(_)) => return Err(Status::resource_exhausted(err.to_string())),
+            Err(CreateError::AlreadyExists) => {
+                let existing = self
+                    .state
+                    .catalog
+                    .get(&req.name)
+                    .and_then(|handle| handle.with_ref(|coll| (coll.dim, coll.metric)));
+                return match existing {
+                    Some((dim, existing_metric)) if req.if_not_exists => {
+                        if dim == req.dims as usize && existing_metric == metric {
+                            Ok(false)
+                        } else {
+                            Err(Status::failed_precondition(format!(
+                                "collection exists with dims={dim} metric={}",
+                                existing_metric.as_str()
+                            )))
+                        }
+                    }
+                    _ => Err(Status::already_exists("collection already exists")),
+                };
+            }
+        }
+        Ok(true)
     }
 
-    async fn upsert(
-        &self,
-        req: Request<UpsertRequest>,
-    ) -> Result<Response<UpsertResponse>, Status> {
-        let req = req.into_inner();
+    /// Upserts the points ImportAll has buffered for `collection`.
+    #[allow(clippy::result_large_err)]
+    fn flush_import(&self, collection: Option<&String>, pending: &mut Vec<Point>) -> Result<usize, Status> {
+        let (Some(collection), false) = (collection, pending.is_empty()) else {
+            return Ok(0);
+        };
+        let points = std::mem::take(pending);
+        let upserted = points.len();
+        self.apply_upsert(UpsertRequest { collection: collection.clone(), points, dry_run: false })?;
+        Ok(upserted)
+    }
+
+    /// Validates and applies one upsert batch, logging it to the WAL. Returns
+    /// the ids of the points written (or that would be, for a dry run), in
+    /// input order.
+    #[allow(clippy::result_large_err)]
+    fn apply_upsert(&self, req: UpsertRequest) -> Result<Vec<String>, Status> {
+        self.ensure_writable()?;
+        if !req.dry_run {
+            if let Some(backlog) = self.state.wal_backlog_exceeded() {
+                return Err(Status::resource_exhausted(format!(
+                    "WAL backlog of {backlog} bytes exceeds the limit of {} bytes; retry after it drains",
+                    self.state.wal_backlog_limit_bytes
+                )));
+            }
+        }
         if req.collection.is_empty() {
-            return self.fail("Upsert", Status::invalid_argument("collection must be specified"));
+            return Err(Status::invalid_argument("collection must be specified"));
         }
         let Some(handle) = self.state.catalog.get(&req.collection) else {
-            return self.fail("Upsert", Status::not_found("collection not found"));
+            return Err(collection_not_found("collection not found"));
+        };
+        let Some((dim, assume_normalized, id_kind)) =
+            handle.with_ref(|coll| (coll.dim, coll.assume_normalized, coll.index.id_kind()))
+        else {
+            return Err(collection_not_found("collection not found"));
         };
 
         if req.points.is_empty() {
-            self.record_metric("Upsert", "OK");
-            return Ok(Response::new(UpsertResponse { upserted: 0 }));
+            return Ok(Vec::new());
+        }
+        if assume_normalized {
+            let off_unit = req
+                .points
+                .iter()
+                .filter(|p| (p.vector.iter().map(|x| x * x).sum::<f32>() - 1.0).abs() > UNIT_NORM_TOLERANCE)
+                .count();
+            if off_unit > 0 {
+                tracing::warn!(
+                    collection = %req.collection,
+                    off_unit,
+                    "assume_normalized collection received vectors that are not unit length; their cosine scores will be wrong"
+                );
+            }
         }
 
         let mut prepared = Vec::with_capacity(req.points.len());
         let mut wal_records = Vec::with_capacity(req.points.len());
+        let mut payload_sizes = Vec::with_capacity(req.points.len());
         let ts = now_ms();
-        for point in req.points.into_iter() {
-            let id = if point.id.is_empty() {
-                Uuid::new_v4().to_string()
+        for (position, point) in req.points.into_iter().enumerate() {
+            // Name the point by id when the client supplied one, else by position.
+            let label = if point.id.is_empty() {
+                format!("#{position}")
             } else {
-                point.id
+                format!("'{}'", point.id)
+            };
+            validate_point(&label, &point.vector, &point.payload_json, dim)?;
+            let id = match id_kind {
+                IdKind::String if point.id.is_empty() => self.state.id_scheme.generate(),
+                IdKind::String => point.id,
+                IdKind::U64 => match point.id.parse::<u64>() {
+                    Ok(id) => id.to_string(),
+                    Err(_) => {
+                        return Err(Status::invalid_argument(format!(
+                            "point {label} needs a u64 id; this collection stores integer ids"
+                        )));
+                    }
+                },
             };
-            if point.vector.is_empty() {
-                return self.fail("Upsert", Status::invalid_argument("point vector must not be empty"));
-            }
             let payload = point.payload_json;
+            payload_sizes.push(payload.len());
+            let expires_at_ms = (point.ttl_ms > 0).then(|| ts.saturating_add(point.ttl_ms as i64));
             wal_records.push(WalRecord::Upsert {
                 collection: req.collection.clone(),
                 id: id.clone(),
                 vector: point.vector.clone(),
                 payload_json: payload.clone(),
+                expires_at_ms,
                 ts_ms: ts,
             });
             prepared.push(PointWrite {
                 id,
                 vector: point.vector,
                 payload_json: payload,
+                expires_at_ms,
             });
         }
 
-        let inserted = match handle.upsert_points(prepared) {
-            Some(v) => v,
-            None => return self.fail("Upsert", Status::invalid_argument("vector dimension mismatch")),
-        };
+        let ids: Vec<String> = prepared.iter().map(|p| p.id.clone()).collect();
+        if req.dry_run {
+            return Ok(ids);
+        }
 
-        for record in wal_records {
-            self.state.append_wal(record);
+        // The batch is logged in one write before it is applied, both under
+        // the collection lock: it is either logged and applied whole or not
+        // at all.
+        let inserted = handle
+            .upsert_points_with(prepared, || self.state.append_wal_batch(&wal_records))
+            .map_err(|err| match err {
+                UpsertError::CollectionNotFound => collection_not_found("collection not found"),
+                UpsertError::Batch(BatchError::Dimension { .. }) => dimension_mismatch(err.to_string()),
+                UpsertError::Batch(BatchError::InvalidId(_) | BatchError::ZeroNorm { .. }) => {
+                    Status::invalid_argument(err.to_string())
+                }
+                UpsertError::Batch(BatchError::LengthMismatch { .. }) => Status::internal(err.to_string()),
+                UpsertError::Commit(err) => Status::internal(format!("failed to log upsert; nothing was written: {err}")),
+            })?;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_collection_upsert(&req.collection);
+            metrics.record_points_upserted(inserted);
+            for bytes in payload_sizes {
+                metrics.observe_payload_bytes(bytes);
+            }
         }
-        self.refresh_inventory_metrics();
-        self.record_metric("Upsert", "OK");
-        Ok(Response::new(UpsertResponse {
-            upserted: inserted as u32,
-        }))
+        Ok(ids)
     }
 
-    async fn query(
-        &self,
-        req: Request<QueryRequest>,
-    ) -> Result<Response<QueryResponse>, Status> {
-        let req = req.into_inner();
+    /// Validates a query and returns its ranked hits, plus whether they came
+    /// from a sampled (approximate) scan.
+    #[allow(clippy::result_large_err)]
+    fn run_query(&self, mut req: QueryRequest) -> Result<(Vec<ScoredPoint>, bool), Status> {
         if req.collection.is_empty() {
             return Err(Status::invalid_argument("collection must be specified"));
         }
         let Some(handle) = self.state.catalog.get(&req.collection) else {
-            return Err(Status::not_found("collection not found"));
+            return Err(collection_not_found("collection not found"));
         };
+        if !req.centroid_ids.is_empty() {
+            if !req.vector.is_empty() {
+                return Err(Status::invalid_argument("set either vector or centroid_ids, not both"));
+            }
+            match handle.with_ref(|coll| coll.centroid(&req.centroid_ids)) {
+                Some(Ok(centroid)) => req.vector = centroid,
+                Some(Err(missing)) => {
+                    return Err(Status::not_found(format!("centroid ids not found: {}", missing.join(", "))));
+                }
+                None => return Err(collection_not_found("collection not found")),
+            }
+        }
         if req.vector.is_empty() {
-            return Err(Status::invalid_argument("query vector must not be empty"));
+            return match self.state.catalog.empty_query() {
+                EmptyQuery::Reject => Err(Status::invalid_argument("query vector must not be empty")),
+                EmptyQuery::NoHits => Ok((Vec::new(), false)),
+            };
+        }
+        let Some((dim, collection_metric, default_top_k, default_with_payloads)) =
+            handle.with_ref(|coll| (coll.dim, coll.metric, coll.default_top_k, coll.default_with_payloads))
+        else {
+            return Err(collection_not_found("collection not found"));
+        };
+        let top_k = req.top_k.or(default_top_k).unwrap_or(0) as usize;
+        let with_payloads = req.with_payloads.unwrap_or(default_with_payloads);
+        tracing::Span::current().record("top_k", top_k);
+        if req.vector.len() != dim {
+            return Err(dimension_mismatch(format!(
+                "query vector dimension mismatch: expected {dim}, got {}",
+                req.vector.len()
+            )));
         }
         let metric_override = if req.metric_override.is_empty() {
             None
         } else {
-            Some(Metric::from_str(&req.metric_override))
+            Some(parse_metric(&req.metric_override)?)
+        };
+        let hybrid = match req.hybrid {
+            Some(h) if !(0.0..=1.0).contains(&h.weight) => {
+                return Err(Status::invalid_argument("hybrid weight must be within [0, 1]"));
+            }
+            Some(h) => Some(HybridScore {
+                metric_a: parse_metric(&h.metric_a)?,
+                metric_b: parse_metric(&h.metric_b)?,
+                weight: h.weight,
+            }),
+            None => None,
         };
         let filters: Vec<(String, String)> = req
             .filters
             .into_iter()
             .map(|f| (f.key, f.equals))
             .collect();
-        let hits = match handle.search(req.vector, req.top_k as usize, metric_override, filters) {
-            Some(h) => h,
-            None => return self.fail("Query", Status::invalid_argument("query vector dimension mismatch")),
+        let explained_metric = match &hybrid {
+            _ if !req.explain => None,
+            Some(h) => Some((
+                format!("hybrid({}*{}+{}*{})", h.metric_a.as_str(), h.weight, h.metric_b.as_str(), 1.0 - h.weight),
+                None,
+            )),
+            None => {
+                let metric = metric_override.unwrap_or(collection_metric);
+                Some((metric.as_str().to_string(), Some(metric)))
+            }
+        };
+        // Hybrid blends are already in [0, 1] before boosts, so they are only clamped.
+        let normalized_metric = match &hybrid {
+            Some(_) => None,
+            None => Some(metric_override.unwrap_or(collection_metric)),
+        };
+        if req.report_distance {
+            if req.normalize_scores {
+                return Err(Status::invalid_argument("report_distance cannot be combined with normalize_scores"));
+            }
+            match normalized_metric {
+                None => return Err(Status::invalid_argument("report_distance is not supported for hybrid queries")),
+                Some(metric) if metric.distance(0.0).is_none() => {
+                    return Err(Status::invalid_argument(format!("metric {} has no distance to report", metric.as_str())));
+                }
+                Some(_) => {}
+            }
+        }
+        let dimension_metric = match normalized_metric {
+            _ if !req.explain_dimensions => None,
+            None => return Err(Status::invalid_argument("explain_dimensions is not supported for hybrid queries")),
+            Some(metric) if metric.contributions(&[], &[]).is_none() => {
+                return Err(Status::invalid_argument(format!(
+                    "metric {} has no per-dimension contributions; use l2 or ip",
+                    metric.as_str()
+                )));
+            }
+            Some(metric) => Some(metric),
         };
-        let mut resp = QueryResponse { hits: Vec::with_capacity(hits.len()) };
-        for (id, score, payload) in hits {
-            resp.hits.push(ScoredPoint {
+        let report = |score: f32| match normalized_metric {
+            Some(metric) if req.report_distance => metric.distance(score).unwrap_or(score),
+            _ if !req.normalize_scores => score,
+            Some(metric) => metric.normalize_score(score),
+            None => score.clamp(0.0, 1.0),
+        };
+        let mut boosts = Vec::with_capacity(req.boosts.len());
+        for boost in req.boosts {
+            if boost.filters.is_empty() {
+                return Err(Status::invalid_argument("each boost must have at least one filter"));
+            }
+            if !boost.boost.is_finite() {
+                return Err(Status::invalid_argument("boost must be a finite number"));
+            }
+            boosts.push(ScoreBoost {
+                filters: boost.filters.into_iter().map(|f| (f.key, f.equals)).collect(),
+                boost: boost.boost,
+            });
+        }
+        let explain_filters = if req.explain { filters.clone() } else { Vec::new() };
+        let explain_boosts = if req.explain { boosts.clone() } else { Vec::new() };
+        let max_scan = (req.max_scan > 0).then_some(req.max_scan as usize);
+        let group_by = match (req.group_by.is_empty(), req.per_group_limit) {
+            (true, 0) => None,
+            (true, _) => return Err(Status::invalid_argument("per_group_limit requires group_by")),
+            (false, 0) => return Err(Status::invalid_argument("group_by requires a positive per_group_limit")),
+            (false, limit) => Some(GroupBy { field: req.group_by, per_group_limit: limit as usize, cap_ungrouped: true }),
+        };
+        let group_by = match (group_by, req.dedup_by.is_empty()) {
+            (group_by, true) => group_by,
+            (None, false) => Some(GroupBy::dedup(req.dedup_by)),
+            (Some(_), false) => return Err(Status::invalid_argument("dedup_by cannot be combined with group_by")),
+        };
+        let ids = IdFilter {
+            exclude: req.exclude_ids.into_iter().collect(),
+            include: (!req.include_ids.is_empty()).then(|| req.include_ids.into_iter().collect()),
+        };
+        let dimension_query = dimension_metric.map(|_| req.vector.clone());
+        let Some((hits, stats)) = handle.search(req.vector, top_k, metric_override, filters, ids, hybrid, boosts, max_scan, group_by) else {
+            return Err(dimension_mismatch("query vector dimension mismatch"));
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.record_collection_query(&req.collection);
+            metrics.record_search_candidates(&req.collection, stats.evaluated, stats.rejected);
+        }
+        tracing::Span::current().record("hits", hits.len());
+        if hits.len() < top_k {
+            tracing::debug!(hits = hits.len(), top_k, "query returned fewer hits than top_k");
+        }
+        let mut hits: Vec<ScoredPoint> = hits
+            .into_iter()
+            .map(|(id, score, payload)| ScoredPoint {
                 id,
-                score,
-                payload_json: if req.with_payloads { payload } else { String::new() },
+                score: report(score),
+                explanation: explained_metric.as_ref().map(|(name, metric)| ScoreExplanation {
+                    score: report(score),
+                    ..explain_hit(name, *metric, score, &payload, &explain_filters, &explain_boosts)
+                }),
+                payload_json: if with_payloads { payload } else { String::new() },
+                exact: !stats.sampled,
+                dimension_contributions: Vec::new(),
+            })
+            .collect();
+        // Only the top hit: its stored vector is looked up again by id.
+        if let (Some(metric), Some(query), Some(top)) = (dimension_metric, &dimension_query, hits.first_mut()) {
+            let stored = handle.with_ref(|coll| coll.latest_vector(&top.id)).flatten();
+            top.dimension_contributions = stored.and_then(|v| metric.contributions(query, &v)).unwrap_or_default();
+        }
+        Ok((hits, stats.sampled))
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn ensure_writable(&self) -> Result<(), Status> {
+        if self.state.read_only {
+            return Err(Status::failed_precondition("server is in read-only mode"));
+        }
+        Ok(())
+    }
+
+    /// Records a successful call and emits the completion event for the current span.
+    fn succeed(&self, method: &str, started: Instant, results: usize) {
+        self.record_metric(method, "OK", started);
+        tracing::info!(
+            elapsed_ms = started.elapsed().as_secs_f64() * 1000.0,
+            results,
+            "rpc completed"
+        );
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn fail<T>(&self, method: &str, started: Instant, status: Status) -> Result<T, Status> {
+        self.record_metric(method, status.code().to_string(), started);
+        if let Some(metrics) = &self.metrics {
+            metrics.record_grpc_error(method, code_name(status.code()));
+        }
+        tracing::warn!(
+            elapsed_ms = started.elapsed().as_secs_f64() * 1000.0,
+            code = ?status.code(),
+            error = status.message(),
+            "rpc failed"
+        );
+        Err(status)
+    }
+}
+
+#[tonic::async_trait]
+impl VectorDb for VectorDbService {
+    type QueryStreamStream = Pin<Box<dyn Stream<Item = Result<ScoredPoint, Status>> + Send + 'static>>;
+    type ExportAllStream = Pin<Box<dyn Stream<Item = Result<ExportRecord, Status>> + Send + 'static>>;
+
+    #[tracing::instrument(name = "Ping", skip_all, fields(method = "Ping", request_id = %request_id(_req.metadata())))]
+    async fn ping(
+        &self,
+        _req: Request<PingRequest>,
+    ) -> Result<Response<PingResponse>, Status> {
+        let started = Instant::now();
+        self.succeed("Ping", started, 0);
+        Ok(Response::new(PingResponse {}))
+    }
+
+    #[tracing::instrument(
+        name = "CreateCollection",
+        skip_all,
+        fields(
+            method = "CreateCollection",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().name,
+            dims = req.get_ref().dims,
+        ),
+    )]
+    async fn create_collection(
+        &self,
+        req: Request<CreateCollectionRequest>,
+    ) -> Result<Response<CreateCollectionResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("CreateCollection", started, status);
+        }
+        let req = req.into_inner();
+        match self.apply_create_collection(req) {
+            Ok(created) => {
+                if let Err(status) = self.commit().await {
+                    return self.fail("CreateCollection", started, status);
+                }
+                self.succeed("CreateCollection", started, usize::from(created));
+                Ok(Response::new(CreateCollectionResponse {}))
+            }
+            Err(status) => self.fail("CreateCollection", started, status),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "DescribeCollection",
+        skip_all,
+        fields(method = "DescribeCollection", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn describe_collection(
+        &self,
+        req: Request<DescribeCollectionRequest>,
+    ) -> Result<Response<DescribeCollectionResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("DescribeCollection", started, Status::invalid_argument("collection must be specified"));
+        }
+        let pinned = self.state.catalog.is_pinned(&req.collection);
+        let described = self.state.catalog.get(&req.collection).and_then(|h| {
+            h.with_ref(|c| DescribeCollectionResponse {
+                name: c.name.clone(),
+                dims: c.dim as u32,
+                metric: c.metric.as_str().to_string(),
+                index_type: "flat".to_string(),
+                layout: c.index.layout.as_str().to_string(),
+                points: c.index.len() as u64,
+                assume_normalized: c.assume_normalized,
+                parallel_threshold: c.parallel_threshold.map(|t| t as u64),
+                default_top_k: c.default_top_k,
+                default_with_payloads: c.default_with_payloads,
+                payload_store: c.index.payload_store.as_str().to_string(),
+                id_type: c.index.id_kind().as_str().to_string(),
+                queries: c.access().queries(),
+                upserts: c.access().upserts(),
+                pinned,
+            })
+        });
+        let Some(described) = described else {
+            return self.fail("DescribeCollection", started, collection_not_found("collection not found"));
+        };
+        self.succeed("DescribeCollection", started, 1);
+        Ok(Response::new(described))
+    }
+
+    #[tracing::instrument(
+        name = "Upsert",
+        skip_all,
+        fields(
+            method = "Upsert",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().collection,
+            points = req.get_ref().points.len(),
+        ),
+    )]
+    async fn upsert(
+        &self,
+        req: Request<UpsertRequest>,
+    ) -> Result<Response<UpsertResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        let dry_run = req.dry_run;
+        match self.apply_upsert(req) {
+            Ok(ids) => {
+                if !dry_run {
+                    if let Err(status) = self.commit().await {
+                        return self.fail("Upsert", started, status);
+                    }
+                }
+                self.succeed("Upsert", started, ids.len());
+                Ok(Response::new(UpsertResponse {
+                    upserted: ids.len() as u32,
+                    ids,
+                }))
+            }
+            Err(status) => self.fail("Upsert", started, status),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "Query",
+        skip_all,
+        fields(
+            method = "Query",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().collection,
+            top_k = tracing::field::Empty,
+            filters = req.get_ref().filters.len(),
+            hits = tracing::field::Empty,
+        ),
+    )]
+    async fn query(
+        &self,
+        req: Request<QueryRequest>,
+    ) -> Result<Response<QueryResponse>, Status> {
+        let started = Instant::now();
+        match self.run_query(req.into_inner()) {
+            Ok((hits, approximate)) => {
+                let response = QueryResponse { hits, approximate };
+                let returned = response.hits.len();
+                if let Some(metrics) = &self.metrics {
+                    metrics.observe_query_hits(returned);
+                }
+                self.succeed("Query", started, returned);
+                Ok(Response::new(response))
+            }
+            Err(status) => self.fail("Query", started, status),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "RebuildIndex",
+        skip_all,
+        fields(method = "RebuildIndex", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn rebuild_index(
+        &self,
+        req: Request<RebuildIndexRequest>,
+    ) -> Result<Response<RebuildIndexResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("RebuildIndex", started, status);
+        }
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("RebuildIndex", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("RebuildIndex", started, collection_not_found("collection not found"));
+        };
+        let Some(points) = handle.rebuild_index() else {
+            return self.fail("RebuildIndex", started, collection_not_found("collection not found"));
+        };
+        self.succeed("RebuildIndex", started, points);
+        Ok(Response::new(RebuildIndexResponse {
+            points: points as u32,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "ShrinkToFit",
+        skip_all,
+        fields(method = "ShrinkToFit", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn shrink_to_fit(
+        &self,
+        req: Request<ShrinkToFitRequest>,
+    ) -> Result<Response<ShrinkToFitResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("ShrinkToFit", started, status);
+        }
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("ShrinkToFit", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(bytes_freed) = self.state.catalog.get(&req.collection).and_then(|handle| handle.shrink_to_fit()) else {
+            return self.fail("ShrinkToFit", started, collection_not_found("collection not found"));
+        };
+        self.succeed("ShrinkToFit", started, 0);
+        Ok(Response::new(ShrinkToFitResponse { bytes_freed: bytes_freed as u64 }))
+    }
+
+    #[tracing::instrument(
+        name = "SetMetric",
+        skip_all,
+        fields(method = "SetMetric", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn set_metric(
+        &self,
+        req: Request<SetMetricRequest>,
+    ) -> Result<Response<SetMetricResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("SetMetric", started, status);
+        }
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("SetMetric", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("SetMetric", started, collection_not_found("collection not found"));
+        };
+        let metric = match parse_metric(&req.metric) {
+            Ok(metric) => metric,
+            Err(status) => return self.fail("SetMetric", started, status),
+        };
+        // Logged under the collection lock so upserts stay ordered around it.
+        let updated = handle.with_mut(|coll| {
+            coll.set_metric(metric);
+            self.state.append_wal(WalRecord::SetMetric {
+                collection: req.collection.clone(),
+                metric: metric.as_str().to_string(),
+                ts_ms: now_ms(),
             });
+        });
+        if updated.is_none() {
+            return self.fail("SetMetric", started, collection_not_found("collection not found"));
+        }
+        if let Err(status) = self.commit().await {
+            return self.fail("SetMetric", started, status);
+        }
+        self.succeed("SetMetric", started, 1);
+        Ok(Response::new(SetMetricResponse {}))
+    }
+
+    #[tracing::instrument(
+        name = "SetCollectionConfig",
+        skip_all,
+        fields(method = "SetCollectionConfig", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn set_collection_config(
+        &self,
+        req: Request<SetCollectionConfigRequest>,
+    ) -> Result<Response<SetCollectionConfigResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("SetCollectionConfig", started, status);
+        }
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("SetCollectionConfig", started, Status::invalid_argument("collection must be specified"));
+        }
+        if req.reset_parallel_threshold && req.parallel_threshold.is_some() {
+            return self.fail(
+                "SetCollectionConfig",
+                started,
+                Status::invalid_argument("parallel_threshold and reset_parallel_threshold are mutually exclusive"),
+            );
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("SetCollectionConfig", started, collection_not_found("collection not found"));
+        };
+        // Logged under the collection lock so upserts stay ordered around it.
+        let updated = handle.with_mut(|coll| {
+            if req.reset_parallel_threshold {
+                coll.parallel_threshold = None;
+            } else if let Some(threshold) = req.parallel_threshold {
+                coll.parallel_threshold = Some(threshold as usize);
+            }
+            if let Some(top_k) = req.default_top_k {
+                coll.default_top_k = (top_k > 0).then_some(top_k);
+            }
+            if let Some(with_payloads) = req.default_with_payloads {
+                coll.default_with_payloads = with_payloads;
+            }
+            self.state.append_wal(WalRecord::SetCollectionConfig {
+                collection: req.collection.clone(),
+                parallel_threshold: coll.parallel_threshold.map(|t| t as u64),
+                default_top_k: coll.default_top_k,
+                default_with_payloads: coll.default_with_payloads,
+                ts_ms: now_ms(),
+            });
+        });
+        if updated.is_none() {
+            return self.fail("SetCollectionConfig", started, collection_not_found("collection not found"));
+        }
+        if let Err(status) = self.commit().await {
+            return self.fail("SetCollectionConfig", started, status);
+        }
+        self.succeed("SetCollectionConfig", started, 1);
+        Ok(Response::new(SetCollectionConfigResponse {}))
+    }
+
+    #[tracing::instrument(
+        name = "TruncateCollection",
+        skip_all,
+        fields(method = "TruncateCollection", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn truncate_collection(
+        &self,
+        req: Request<TruncateCollectionRequest>,
+    ) -> Result<Response<TruncateCollectionResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("TruncateCollection", started, status);
+        }
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("TruncateCollection", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("TruncateCollection", started, collection_not_found("collection not found"));
+        };
+        // Logged under the collection lock, as upserts are, so no upsert
+        // applied after the clear can reach the WAL ahead of it.
+        let truncated = handle.with_mut(|coll| {
+            let removed = coll.clear();
+            self.state.append_wal(WalRecord::Truncate {
+                collection: req.collection.clone(),
+                ts_ms: now_ms(),
+            });
+            removed
+        });
+        let Some(removed) = truncated else {
+            return self.fail("TruncateCollection", started, collection_not_found("collection not found"));
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.record_points_deleted(removed);
+        }
+        if let Err(status) = self.commit().await {
+            return self.fail("TruncateCollection", started, status);
+        }
+        self.succeed("TruncateCollection", started, removed);
+        Ok(Response::new(TruncateCollectionResponse {
+            removed: removed as u32,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "MovePoints",
+        skip_all,
+        fields(
+            method = "MovePoints",
+            request_id = %request_id(req.metadata()),
+            source = %req.get_ref().source,
+            target = %req.get_ref().target,
+            ids = req.get_ref().ids.len(),
+        ),
+    )]
+    async fn move_points(
+        &self,
+        req: Request<MovePointsRequest>,
+    ) -> Result<Response<MovePointsResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("MovePoints", started, status);
+        }
+        let req = req.into_inner();
+        if req.source.is_empty() || req.target.is_empty() {
+            return self.fail("MovePoints", started, Status::invalid_argument("source and target must be specified"));
+        }
+        // Log the target inserts before the source delete: a crash in between
+        // leaves a duplicate rather than losing the point.
+        let log = |points: &[PointWrite]| {
+            let ts = now_ms();
+            for point in points {
+                self.state.append_wal(WalRecord::Upsert {
+                    collection: req.target.clone(),
+                    id: point.id.clone(),
+                    vector: point.vector.clone(),
+                    payload_json: point.payload_json.clone(),
+                    expires_at_ms: point.expires_at_ms,
+                    ts_ms: ts,
+                });
+            }
+            if !points.is_empty() {
+                self.state.append_wal(WalRecord::Delete {
+                    collection: req.source.clone(),
+                    ids: points.iter().map(|p| p.id.clone()).collect(),
+                    ts_ms: ts,
+                });
+            }
+        };
+        let moved = match self.state.catalog.move_points_with(&req.source, &req.target, &req.ids, log) {
+            Ok(moved) => moved,
+            Err(err @ (MoveError::SourceNotFound | MoveError::TargetNotFound)) => {
+                return self.fail("MovePoints", started, collection_not_found(err.to_string()));
+            }
+            Err(err) => return self.fail("MovePoints", started, Status::invalid_argument(err.to_string())),
+        };
+
+        let count = moved.points.len();
+        if let Err(status) = self.commit().await {
+            return self.fail("MovePoints", started, status);
+        }
+        self.succeed("MovePoints", started, count);
+        Ok(Response::new(MovePointsResponse {
+            moved: count as u32,
+            missing: moved.missing as u32,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "SwapCollections",
+        skip_all,
+        fields(
+            method = "SwapCollections",
+            request_id = %request_id(req.metadata()),
+            target = %req.get_ref().target,
+            source = %req.get_ref().source,
+        ),
+    )]
+    async fn swap_collections(
+        &self,
+        req: Request<SwapCollectionsRequest>,
+    ) -> Result<Response<SwapCollectionsResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("SwapCollections", started, status);
+        }
+        let req = req.into_inner();
+        if req.source.is_empty() || req.target.is_empty() {
+            return self.fail("SwapCollections", started, Status::invalid_argument("source and target must be specified"));
+        }
+        let logged = || {
+            self.state.append_wal(WalRecord::SwapCollections {
+                target: req.target.clone(),
+                source: req.source.clone(),
+                ts_ms: now_ms(),
+            })
+        };
+        let points = match self.state.catalog.swap_collections_with(&req.target, &req.source, logged) {
+            Ok(points) => points,
+            Err(err @ (SwapError::SourceNotFound | SwapError::TargetNotFound)) => {
+                return self.fail("SwapCollections", started, collection_not_found(err.to_string()));
+            }
+            Err(err) => return self.fail("SwapCollections", started, Status::invalid_argument(err.to_string())),
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.forget_collection(&req.source);
+        }
+        if let Err(status) = self.commit().await {
+            return self.fail("SwapCollections", started, status);
+        }
+        self.succeed("SwapCollections", started, points);
+        Ok(Response::new(SwapCollectionsResponse { points: points as u64 }))
+    }
+
+    #[tracing::instrument(
+        name = "DeleteCollectionsByPrefix",
+        skip_all,
+        fields(method = "DeleteCollectionsByPrefix", request_id = %request_id(req.metadata()), prefix = %req.get_ref().prefix),
+    )]
+    async fn delete_collections_by_prefix(
+        &self,
+        req: Request<DeleteCollectionsByPrefixRequest>,
+    ) -> Result<Response<DeleteCollectionsByPrefixResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("DeleteCollectionsByPrefix", started, status);
+        }
+        let req = req.into_inner();
+        if req.prefix.is_empty() {
+            return self.fail(
+                "DeleteCollectionsByPrefix",
+                started,
+                Status::invalid_argument("prefix must be non-empty"),
+            );
+        }
+        let mut deleted = Vec::new();
+        for name in self.state.catalog.names_with_prefix(&req.prefix) {
+            // Another request may have dropped or swapped it away since listing.
+            let logged = || self.state.append_wal(WalRecord::DropCollection { collection: name.clone(), ts_ms: now_ms() });
+            if !self.state.catalog.drop_collection_with(&name, logged) {
+                continue;
+            }
+            if let Some(metrics) = &self.metrics {
+                metrics.forget_collection(&name);
+            }
+            deleted.push(name);
+        }
+        if let Err(status) = self.commit().await {
+            return self.fail("DeleteCollectionsByPrefix", started, status);
+        }
+        tracing::info!(deleted = deleted.len(), "collections dropped by prefix");
+        self.succeed("DeleteCollectionsByPrefix", started, deleted.len());
+        Ok(Response::new(DeleteCollectionsByPrefixResponse { deleted: deleted.len() as u64, collections: deleted }))
+    }
+
+    #[tracing::instrument(
+        name = "SnapshotCollection",
+        skip_all,
+        fields(method = "SnapshotCollection", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn snapshot_collection(
+        &self,
+        req: Request<SnapshotCollectionRequest>,
+    ) -> Result<Response<SnapshotCollectionResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("SnapshotCollection", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(path) = self.state.collection_snapshot_path(&req.collection) else {
+            return self.fail("SnapshotCollection", started, no_collection_snapshot_path());
+        };
+        // Clone under the read lock so writers only wait for the copy, not the file I/O.
+        let Some(collection) = self.state.catalog.get(&req.collection).and_then(|h| h.with_ref(|c| c.clone())) else {
+            return self.fail("SnapshotCollection", started, collection_not_found("collection not found"));
+        };
+        let saved = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| collection.save_snapshot(&path));
+        let points = match saved {
+            Ok(points) => points,
+            Err(err) => {
+                return self.fail(
+                    "SnapshotCollection",
+                    started,
+                    Status::internal(format!("failed to write collection snapshot: {err}")),
+                );
+            }
+        };
+        tracing::info!(points, path = %path.display(), "collection snapshot written");
+        self.succeed("SnapshotCollection", started, points);
+        Ok(Response::new(SnapshotCollectionResponse { points: points as u64, path: path.display().to_string() }))
+    }
+
+    #[tracing::instrument(
+        name = "RestoreCollection",
+        skip_all,
+        fields(method = "RestoreCollection", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn restore_collection(
+        &self,
+        req: Request<RestoreCollectionRequest>,
+    ) -> Result<Response<RestoreCollectionResponse>, Status> {
+        let started = Instant::now();
+        if let Err(status) = self.ensure_writable() {
+            return self.fail("RestoreCollection", started, status);
+        }
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("RestoreCollection", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(path) = self.state.collection_snapshot_path(&req.collection) else {
+            return self.fail("RestoreCollection", started, no_collection_snapshot_path());
+        };
+        let points = match self.state.restore_collection(&path) {
+            Ok((_, points)) => points,
+            Err(RestoreError::AlreadyExists(name)) => {
+                return self.fail(
+                    "RestoreCollection",
+                    started,
+                    Status::already_exists(format!("collection '{name}' already exists; drop it before restoring")),
+                );
+            }
+            Err(err @ RestoreError::DimsExceeded { .. }) => {
+                return self.fail("RestoreCollection", started, Status::invalid_argument(err.to_string()));
+            }
+            Err(err @ RestoreError::LimitReached(_)) => {
+                return self.fail("RestoreCollection", started, Status::resource_exhausted(err.to_string()));
+            }
+            Err(RestoreError::Snapshot(SnapshotError::Io(err))) if err.kind() == std::io::ErrorKind::NotFound => {
+                return self.fail("RestoreCollection", started, Status::not_found("no snapshot saved for this collection"));
+            }
+            Err(err) => {
+                return self.fail(
+                    "RestoreCollection",
+                    started,
+                    Status::data_loss(format!("cannot restore collection snapshot: {err}")),
+                );
+            }
+        };
+        if let Err(status) = self.commit().await {
+            return self.fail("RestoreCollection", started, status);
+        }
+        tracing::info!(points, "collection restored from snapshot");
+        self.succeed("RestoreCollection", started, points);
+        Ok(Response::new(RestoreCollectionResponse { points: points as u64 }))
+    }
+
+    #[tracing::instrument(
+        name = "Scroll",
+        skip_all,
+        fields(
+            method = "Scroll",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().collection,
+            offset = req.get_ref().offset,
+            limit = req.get_ref().limit,
+        ),
+    )]
+    async fn scroll(
+        &self,
+        req: Request<ScrollRequest>,
+    ) -> Result<Response<ScrollResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("Scroll", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("Scroll", started, collection_not_found("collection not found"));
+        };
+        let offset = req.offset as usize;
+        let limit = if req.limit == 0 { DEFAULT_SCROLL_LIMIT } else { req.limit as usize };
+        let Some(((page, next_offset), total)) = handle.with_ref(|coll| {
+            (coll.scroll(offset, limit, req.with_vectors, req.with_payloads), coll.index.len())
+        }) else {
+            return self.fail("Scroll", started, collection_not_found("collection not found"));
+        };
+
+        let points: Vec<Point> = page
+            .into_iter()
+            .map(|(id, vector, payload_json)| Point { id, vector, payload_json, ttl_ms: 0 })
+            .collect();
+        self.succeed("Scroll", started, points.len());
+        Ok(Response::new(ScrollResponse {
+            points,
+            next_offset: next_offset as u64,
+            has_more: next_offset < total,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "CollectionChecksum",
+        skip_all,
+        fields(method = "CollectionChecksum", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn collection_checksum(
+        &self,
+        req: Request<CollectionChecksumRequest>,
+    ) -> Result<Response<CollectionChecksumResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("CollectionChecksum", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some((checksum, points)) = self.state.catalog.get(&req.collection).and_then(|h| h.with_ref(|c| c.checksum()))
+        else {
+            return self.fail("CollectionChecksum", started, collection_not_found("collection not found"));
+        };
+        self.succeed("CollectionChecksum", started, points);
+        Ok(Response::new(CollectionChecksumResponse {
+            checksum: format!("{checksum:016x}"),
+            points: points as u64,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "FetchByFilter",
+        skip_all,
+        fields(
+            method = "FetchByFilter",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().collection,
+            filters = req.get_ref().filters.len(),
+            offset = req.get_ref().offset,
+        ),
+    )]
+    async fn fetch_by_filter(
+        &self,
+        req: Request<FetchByFilterRequest>,
+    ) -> Result<Response<FetchByFilterResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("FetchByFilter", started, Status::invalid_argument("collection must be specified"));
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("FetchByFilter", started, collection_not_found("collection not found"));
+        };
+        let filters: Vec<(String, String)> = req.filters.into_iter().map(|f| (f.key, f.equals)).collect();
+        let offset = req.offset as usize;
+        let limit = if req.limit == 0 { DEFAULT_SCROLL_LIMIT } else { req.limit as usize };
+        let Some((page, matched)) =
+            handle.with_ref(|coll| coll.fetch_by_filter(&filters, &req.payload_fields, offset, limit))
+        else {
+            return self.fail("FetchByFilter", started, collection_not_found("collection not found"));
+        };
+
+        let next_offset = offset.min(matched) + page.len();
+        let points: Vec<Point> = page
+            .into_iter()
+            .map(|(id, payload_json)| Point { id, vector: Vec::new(), payload_json, ttl_ms: 0 })
+            .collect();
+        self.succeed("FetchByFilter", started, points.len());
+        Ok(Response::new(FetchByFilterResponse {
+            points,
+            next_offset: next_offset as u64,
+            has_more: next_offset < matched,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "UpsertStream",
+        skip_all,
+        fields(method = "UpsertStream", request_id = %request_id(req.metadata()), batches = tracing::field::Empty),
+    )]
+    async fn upsert_stream(
+        &self,
+        req: Request<Streaming<UpsertRequest>>,
+    ) -> Result<Response<UpsertResponse>, Status> {
+        let started = Instant::now();
+        let mut stream = req.into_inner();
+        let mut batches = 0usize;
+        let mut ids = Vec::new();
+        loop {
+            let batch = match stream.message().await {
+                Ok(Some(batch)) => batch,
+                Ok(None) => break,
+                Err(status) => return self.fail("UpsertStream", started, status),
+            };
+            batches += 1;
+            match self.apply_upsert(batch) {
+                Ok(upserted) => ids.extend(upserted),
+                Err(status) => {
+                    let message =
+                        format!("batch {batches} rejected after {} points upserted: {}", ids.len(), status.message());
+                    return self.fail("UpsertStream", started, Status::new(status.code(), message));
+                }
+            }
+        }
+        tracing::Span::current().record("batches", batches);
+        if let Err(status) = self.commit().await {
+            return self.fail("UpsertStream", started, status);
+        }
+        self.succeed("UpsertStream", started, ids.len());
+        Ok(Response::new(UpsertResponse {
+            upserted: ids.len() as u32,
+            ids,
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "FindDuplicates",
+        skip_all,
+        fields(
+            method = "FindDuplicates",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().collection,
+            threshold = req.get_ref().threshold,
+        ),
+    )]
+    async fn find_duplicates(
+        &self,
+        req: Request<FindDuplicatesRequest>,
+    ) -> Result<Response<FindDuplicatesResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("FindDuplicates", started, Status::invalid_argument("collection must be specified"));
+        }
+        if !req.threshold.is_finite() {
+            return self.fail("FindDuplicates", started, Status::invalid_argument("threshold must be finite"));
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("FindDuplicates", started, collection_not_found("collection not found"));
+        };
+        let found = handle.with_ref(|c| {
+            if c.index.len() > MAX_DUPLICATE_SCAN_POINTS {
+                Err(c.index.len())
+            } else {
+                Ok(c.find_duplicates(req.threshold))
+            }
+        });
+        let pairs = match found {
+            Some(Ok(pairs)) => pairs,
+            Some(Err(points)) => {
+                return self.fail(
+                    "FindDuplicates",
+                    started,
+                    Status::failed_precondition(format!(
+                        "collection has {points} points; FindDuplicates compares every pair and is limited to {MAX_DUPLICATE_SCAN_POINTS}"
+                    )),
+                );
+            }
+            None => return self.fail("FindDuplicates", started, collection_not_found("collection not found")),
+        };
+        let total_pairs = pairs.len() as u64;
+        let limit = if req.limit == 0 { pairs.len() } else { req.limit as usize };
+        let pairs: Vec<DuplicatePair> =
+            pairs.into_iter().take(limit).map(|(id_a, id_b, raw)| DuplicatePair { id_a, id_b, raw }).collect();
+        self.succeed("FindDuplicates", started, pairs.len());
+        Ok(Response::new(FindDuplicatesResponse { pairs, total_pairs }))
+    }
+
+    #[tracing::instrument(
+        name = "Warmup",
+        skip_all,
+        fields(method = "Warmup", request_id = %request_id(req.metadata()), collection = %req.get_ref().collection),
+    )]
+    async fn warmup(
+        &self,
+        req: Request<WarmupRequest>,
+    ) -> Result<Response<WarmupResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        let names = if req.collection.is_empty() {
+            self.state.catalog.names()
+        } else if self.state.catalog.get(&req.collection).is_some() {
+            vec![req.collection]
+        } else {
+            return self.fail("Warmup", started, collection_not_found("collection not found"));
+        };
+        let (mut points, mut bytes) = (0usize, 0usize);
+        for name in names {
+            // A collection dropped since `names()` is simply skipped.
+            if let Some((len, read)) = self.state.catalog.get(&name).and_then(|h| h.warmup()) {
+                points += len;
+                bytes += read;
+            }
+        }
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        tracing::info!(points, bytes, elapsed_ms, "warmup finished");
+        self.succeed("Warmup", started, points);
+        Ok(Response::new(WarmupResponse { points: points as u64, bytes: bytes as u64, elapsed_ms }))
+    }
+
+    #[tracing::instrument(
+        name = "PinCollection",
+        skip_all,
+        fields(
+            method = "PinCollection",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().collection,
+            unpin = req.get_ref().unpin,
+        ),
+    )]
+    async fn pin_collection(
+        &self,
+        req: Request<PinCollectionRequest>,
+    ) -> Result<Response<PinCollectionResponse>, Status> {
+        let started = Instant::now();
+        let req = req.into_inner();
+        if req.collection.is_empty() {
+            return self.fail("PinCollection", started, Status::invalid_argument("collection must be specified"));
+        }
+        if req.unpin {
+            if self.state.catalog.unpin(&req.collection) {
+                tracing::info!("collection unpinned");
+            }
+            self.succeed("PinCollection", started, 0);
+            return Ok(Response::new(PinCollectionResponse { points: 0, bytes: 0 }));
+        }
+        let Some(handle) = self.state.catalog.get(&req.collection) else {
+            return self.fail("PinCollection", started, collection_not_found("collection not found"));
+        };
+        self.state.catalog.pin(&req.collection);
+        // Dropped between the pin and the warmup: the pin stays for a
+        // collection recreated under the same name.
+        let (points, bytes) = handle.warmup().unwrap_or_default();
+        tracing::info!(points, bytes, "collection pinned");
+        self.succeed("PinCollection", started, points);
+        Ok(Response::new(PinCollectionResponse { points: points as u64, bytes: bytes as u64 }))
+    }
+
+    #[tracing::instrument(name = "Flush", skip_all, fields(method = "Flush", request_id = %request_id(_req.metadata())))]
+    async fn flush(
+        &self,
+        _req: Request<FlushRequest>,
+    ) -> Result<Response<FlushResponse>, Status> {
+        let started = Instant::now();
+        match self.state.flush_wal() {
+            Ok(flushed) => {
+                self.succeed("Flush", started, flushed);
+                Ok(Response::new(FlushResponse {
+                    flushed_bytes: flushed as u64,
+                }))
+            }
+            Err(err) => self.fail("Flush", started, Status::internal(format!("failed to flush WAL: {err}"))),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "QueryStream",
+        skip_all,
+        fields(
+            method = "QueryStream",
+            request_id = %request_id(req.metadata()),
+            collection = %req.get_ref().collection,
+            top_k = tracing::field::Empty,
+            filters = req.get_ref().filters.len(),
+            hits = tracing::field::Empty,
+        ),
+    )]
+    async fn query_stream(
+        &self,
+        req: Request<QueryRequest>,
+    ) -> Result<Response<Self::QueryStreamStream>, Status> {
+        let started = Instant::now();
+        match self.run_query(req.into_inner()) {
+            Ok((hits, _)) => {
+                self.succeed("QueryStream", started, hits.len());
+                Ok(Response::new(Box::pin(tokio_stream::iter(hits.into_iter().map(Ok)))))
+            }
+            Err(status) => self.fail("QueryStream", started, status),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "ExportAll",
+        skip_all,
+        fields(method = "ExportAll", request_id = %request_id(_req.metadata()), collections = tracing::field::Empty),
+    )]
+    async fn export_all(
+        &self,
+        _req: Request<ExportAllRequest>,
+    ) -> Result<Response<Self::ExportAllStream>, Status> {
+        let started = Instant::now();
+        let mut names = self.state.catalog.names();
+        names.sort();
+        tracing::Span::current().record("collections", names.len());
+        self.succeed("ExportAll", started, names.len());
+        let (tx, rx) = mpsc::channel(EXPORT_BUFFER_FRAMES);
+        let state = self.state.clone();
+        tokio::task::spawn_blocking(move || export_frames(&state, names, &tx));
+        Ok(Response::new(Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx))))
+    }
+
+    #[tracing::instrument(
+        name = "ImportAll",
+        skip_all,
+        fields(
+            method = "ImportAll",
+            request_id = %request_id(req.metadata()),
+            collections = tracing::field::Empty,
+            points = tracing::field::Empty,
+        ),
+    )]
+    async fn import_all(
+        &self,
+        req: Request<Streaming<ExportRecord>>,
+    ) -> Result<Response<ImportAllResponse>, Status> {
+        let started = Instant::now();
+        let mut stream = req.into_inner();
+        let mut current: Option<String> = None;
+        let mut pending = Vec::new();
+        let (mut collections, mut points) = (0usize, 0usize);
+        let stopped = |status: Status, collections: usize, points: usize| {
+            let message =
+                format!("import stopped after {collections} collections and {points} points: {}", status.message());
+            Status::new(status.code(), message)
+        };
+        loop {
+            let record = match stream.message().await {
+                Ok(Some(frame)) => frame.record,
+                Ok(None) => break,
+                Err(status) => return self.fail("ImportAll", started, status),
+            };
+            let applied = match record {
+                Some(Record::Collection(header)) => match self.flush_import(current.as_ref(), &mut pending) {
+                    Ok(flushed) => {
+                        points += flushed;
+                        let name = header.name.clone();
+                        self.apply_create_collection(CreateCollectionRequest { if_not_exists: false, ..header }).map(|_| {
+                            collections += 1;
+                            current = Some(name);
+                        })
+                    }
+                    Err(status) => Err(status),
+                },
+                Some(Record::Point(_)) if current.is_none() => {
+                    Err(Status::invalid_argument("point received before any collection header"))
+                }
+                Some(Record::Point(point)) => {
+                    pending.push(point);
+                    if pending.len() < IMPORT_BATCH_POINTS {
+                        Ok(())
+                    } else {
+                        self.flush_import(current.as_ref(), &mut pending).map(|flushed| points += flushed)
+                    }
+                }
+                None => Err(Status::invalid_argument("export record is empty")),
+            };
+            if let Err(status) = applied {
+                return self.fail("ImportAll", started, stopped(status, collections, points));
+            }
+        }
+        match self.flush_import(current.as_ref(), &mut pending) {
+            Ok(flushed) => points += flushed,
+            Err(status) => return self.fail("ImportAll", started, stopped(status, collections, points)),
+        }
+        let span = tracing::Span::current();
+        span.record("collections", collections);
+        span.record("points", points);
+        if let Err(status) = self.commit().await {
+            return self.fail("ImportAll", started, status);
         }
-        self.record_metric("Query", "OK");
-        Ok(Response::new(resp))
+        self.succeed("ImportAll", started, points);
+        Ok(Response::new(ImportAllResponse { collections: collections as u64, points: points as u64 }))
     }
 }
@@ -0,0 +1,117 @@
+//! Startup check that replay applied every record: the points each
+//! collection should hold are recounted from the snapshot and WAL records,
+//! independently of the catalog, and compared with what was loaded.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::catalog::Catalog;
+use crate::storage::wal::WalRecord;
+use crate::types::IdKind;
+
+/// Expected rows per collection, tallied from records in log order with the
+/// rules replay applies: upserts with the wrong dimension or an id the
+/// collection cannot store are skipped, and an id upserted twice occupies
+/// two rows.
+#[derive(Debug, Default)]
+pub struct ReplayTally {
+    collections: HashMap<String, TalliedCollection>,
+}
+
+#[derive(Debug)]
+struct TalliedCollection {
+    dim: usize,
+    id_kind: IdKind,
+    // Rows per canonical id.
+    rows: HashMap<String, usize>,
+}
+
+impl TalliedCollection {
+    fn points(&self) -> usize {
+        self.rows.values().sum()
+    }
+
+    /// The id as the collection stores it, so "007" and "7" match in a u64
+    /// collection.
+    fn key(&self, id: &str) -> String {
+        match self.id_kind {
+            IdKind::String => id.to_string(),
+            IdKind::U64 => id.parse::<u64>().map(|id| id.to_string()).unwrap_or_else(|_| id.to_string()),
+        }
+    }
+}
+
+/// A collection whose loaded point count differs from its records. A
+/// collection missing on either side counts as holding no points there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayMismatch {
+    pub collection: String,
+    pub expected: Option<usize>,
+    pub loaded: Option<usize>,
+}
+
+impl ReplayTally {
+    pub fn record(&mut self, rec: &WalRecord) {
+        match rec {
+            WalRecord::CreateCollection { name, dim, id_type, .. } => {
+                self.collections.entry(name.clone()).or_insert_with(|| TalliedCollection {
+                    dim: *dim as usize,
+                    id_kind: id_type.as_deref().and_then(IdKind::parse).unwrap_or_default(),
+                    rows: HashMap::new(),
+                });
+            }
+            WalRecord::Upsert { collection, id, vector, .. } => {
+                let Some(coll) = self.collections.get_mut(collection) else { return; };
+                if vector.len() == coll.dim && coll.id_kind.accepts(id) {
+                    *coll.rows.entry(coll.key(id)).or_default() += 1;
+                }
+            }
+            WalRecord::Delete { collection, ids, .. } => {
+                let Some(coll) = self.collections.get_mut(collection) else { return; };
+                for id in ids {
+                    let key = coll.key(id);
+                    coll.rows.remove(&key);
+                }
+            }
+            WalRecord::Truncate { collection, .. } => {
+                if let Some(coll) = self.collections.get_mut(collection) {
+                    coll.rows.clear();
+                }
+            }
+            WalRecord::SwapCollections { target, source, .. } => {
+                if target != source && self.collections.contains_key(target) {
+                    if let Some(coll) = self.collections.remove(source) {
+                        self.collections.insert(target.clone(), coll);
+                    }
+                }
+            }
+            WalRecord::DropCollection { collection, .. } => {
+                self.collections.remove(collection);
+            }
+            WalRecord::SetCollectionConfig { .. } | WalRecord::SetMetric { .. } => {}
+        }
+    }
+
+    /// Expected points per collection, by name.
+    pub fn expected(&self) -> BTreeMap<String, usize> {
+        self.collections.iter().map(|(name, coll)| (name.clone(), coll.points())).collect()
+    }
+
+    /// Every collection whose point count in `catalog` differs from the
+    /// tally, sorted by name.
+    pub fn verify(&self, catalog: &Catalog) -> Vec<ReplayMismatch> {
+        let mut loaded: BTreeMap<String, usize> =
+            catalog.index_sizes().into_iter().map(|(name, len, _)| (name, len)).collect();
+        let mut mismatches = Vec::new();
+        for (name, expected) in self.expected() {
+            let actual = loaded.remove(&name);
+            if actual != Some(expected) {
+                mismatches.push(ReplayMismatch { collection: name, expected: Some(expected), loaded: actual });
+            }
+        }
+        mismatches.extend(
+            loaded.into_iter().map(|(name, len)| ReplayMismatch { collection: name, expected: None, loaded: Some(len) }),
+        );
+        mismatches.sort_by(|a, b| a.collection.cmp(&b.collection));
+        mismatches
+    }
+}
@@ -0,0 +1,23 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::server::state::DbState;
+use crate::telemetry::Metrics;
+
+/// How often the server refreshes the inventory gauges.
+pub const INVENTORY_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keeps the collection, point and index-size gauges current by reading the
+/// catalog once per tick, so write RPCs never scan it for metrics. The first
+/// refresh runs immediately.
+pub fn spawn(state: Arc<DbState>, metrics: Arc<Metrics>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            metrics.refresh_inventory(&state.catalog);
+        }
+    })
+}
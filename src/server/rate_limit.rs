@@ -0,0 +1,90 @@
+//! Token-bucket rate limiting per client, applied as a tonic interceptor.
+//!
+//! Clients are told apart by the `x-api-key` metadata value; requests without
+//! one share a single global bucket. The key is not authenticated, so this
+//! keeps well-behaved tenants from starving each other rather than enforcing
+//! a quota against a hostile client.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+/// Metadata key that identifies the client.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Above this many tracked keys, buckets that have refilled completely are
+/// dropped; they would behave the same when recreated.
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Tokens added per second; one request costs one token.
+    pub per_second: f64,
+    /// Bucket capacity: how many requests may arrive at once after idling.
+    pub burst: f64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst);
+        self.updated = now;
+    }
+}
+
+/// Cloning shares the buckets. With no limit configured every request passes.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: Option<RateLimit>,
+    buckets: Arc<Mutex<HashMap<Option<String>, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: Option<RateLimit>) -> Self {
+        Self { limit, buckets: Arc::default() }
+    }
+
+    /// Takes a token from `key`'s bucket (`None` is the global bucket).
+    /// Returns false when the bucket is empty.
+    pub fn try_acquire(&self, key: Option<&str>) -> bool {
+        let Some(limit) = self.limit else { return true };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= MAX_TRACKED_KEYS && !buckets.contains_key(&key.map(str::to_string)) {
+            buckets.retain(|_, bucket| {
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst
+            });
+        }
+        let bucket = buckets
+            .entry(key.map(str::to_string))
+            .or_insert(Bucket { tokens: limit.burst, updated: now });
+        bucket.refill(limit, now);
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+impl Interceptor for RateLimiter {
+    fn call(&mut self, req: Request<()>) -> Result<Request<()>, Status> {
+        let key = req.metadata().get(API_KEY_HEADER).and_then(|v| v.to_str().ok());
+        if self.try_acquire(key) {
+            Ok(req)
+        } else {
+            tracing::debug!(keyed = key.is_some(), "request rate limited");
+            Err(Status::resource_exhausted("rate limit exceeded; retry later"))
+        }
+    }
+}
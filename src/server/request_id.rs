@@ -0,0 +1,69 @@
+//! Per-request ids for correlating client logs with server spans. A client
+//! may send its own in the `x-request-id` header; otherwise one is generated.
+//! Either way the id is recorded on the RPC's span and echoed back in the
+//! response headers.
+
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Context, Poll, Service};
+use tonic::metadata::MetadataMap;
+use tonic::server::NamedService;
+
+/// Metadata key carrying the request id, both ways.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied id kept as is; longer ones are replaced.
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The request's id, or "" when it has none (a handler called without the
+/// [`tag`] wrapper and without the header).
+pub fn request_id(metadata: &MetadataMap) -> &str {
+    metadata.get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()).unwrap_or("")
+}
+
+/// Wraps `service` so every request carries an id by the time it reaches a
+/// handler, and every response returns it.
+pub fn tag<S>(service: S) -> Tagged<S> {
+    Tagged { inner: service }
+}
+
+/// Service wrapper returned by [`tag`].
+#[derive(Clone)]
+pub struct Tagged<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for Tagged<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<http::Request<B>> for Tagged<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let supplied = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN && value.to_str().is_ok())
+            .cloned();
+        let id = supplied.unwrap_or_else(|| {
+            http::HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()).expect("uuids are valid header values")
+        });
+        req.headers_mut().insert(REQUEST_ID_HEADER, id.clone());
+        let response = self.inner.call(req);
+        Box::pin(async move {
+            let mut response = response.await?;
+            response.headers_mut().insert(REQUEST_ID_HEADER, id);
+            Ok(response)
+        })
+    }
+}
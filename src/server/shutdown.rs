@@ -0,0 +1,148 @@
+//! Graceful shutdown: stop accepting connections, let in-flight requests
+//! finish for a bounded time, then fail whatever is left and sync the WAL.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{oneshot, watch};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Context, Poll, Service};
+use tonic::server::NamedService;
+use tonic::transport::server::{Router, TcpIncoming};
+use tonic::Status;
+
+use crate::server::state::DbState;
+
+/// How long shutdown waits for in-flight requests when none is configured.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Counts requests whose handler has not returned yet, and can cut them off.
+/// Streaming responses count until the handler hands back the stream.
+#[derive(Clone)]
+pub struct InFlight {
+    count: Arc<AtomicUsize>,
+    cancel: Arc<watch::Sender<bool>>,
+}
+
+impl Default for InFlight {
+    fn default() -> Self {
+        Self { count: Arc::default(), cancel: Arc::new(watch::channel(false).0) }
+    }
+}
+
+impl InFlight {
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Wraps `service` so its requests are counted here.
+    pub fn track<S>(&self, service: S) -> Tracked<S> {
+        Tracked { inner: service, in_flight: self.clone() }
+    }
+
+    /// Fails every tracked request, current and future, with UNAVAILABLE.
+    pub fn cancel(&self) {
+        self.cancel.send_replace(true);
+    }
+}
+
+struct Guard(Arc<AtomicUsize>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Service wrapper returned by [`InFlight::track`].
+#[derive(Clone)]
+pub struct Tracked<S> {
+    inner: S,
+    in_flight: InFlight,
+}
+
+impl<S: NamedService> NamedService for Tracked<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<http::Request<B>> for Tracked<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        self.in_flight.count.fetch_add(1, Ordering::Relaxed);
+        let guard = Guard(self.in_flight.count.clone());
+        let mut cancelled = self.in_flight.cancel.subscribe();
+        let response = self.inner.call(req);
+        Box::pin(async move {
+            let _guard = guard;
+            tokio::select! {
+                response = response => response,
+                _ = cancelled.wait_for(|cancelled| *cancelled) => {
+                    Ok(Status::unavailable("server shut down before the request finished").into_http())
+                }
+            }
+        })
+    }
+}
+
+/// Serves `router` until `signal` resolves, then stops accepting connections
+/// and gives in-flight requests up to `drain_timeout` to finish. Requests
+/// still running after that fail with UNAVAILABLE. The WAL is synced however
+/// the server stops. Returns how many requests the timeout cut off.
+///
+/// The served service should be wrapped with `in_flight.track` so the drain
+/// can see and cancel its requests.
+pub async fn serve_with_drain(
+    router: Router,
+    incoming: TcpIncoming,
+    signal: impl Future<Output = ()>,
+    drain_timeout: Duration,
+    in_flight: &InFlight,
+    state: &DbState,
+) -> Result<usize, tonic::transport::Error> {
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = router.serve_with_incoming_shutdown(incoming, async {
+        let _ = stop_rx.await;
+    });
+    tokio::pin!(server);
+
+    let mut cut_off = 0;
+    let result = tokio::select! {
+        result = &mut server => result,
+        _ = signal => {
+            tracing::info!(
+                in_flight = in_flight.count(),
+                timeout_ms = drain_timeout.as_millis() as u64,
+                "shutdown requested; draining in-flight requests"
+            );
+            let _ = stop_tx.send(());
+            match tokio::time::timeout(drain_timeout, &mut server).await {
+                Ok(result) => result,
+                Err(_) => {
+                    cut_off = in_flight.count();
+                    tracing::warn!(in_flight = cut_off, "drain timeout elapsed; cancelling remaining requests");
+                    in_flight.cancel();
+                    Ok(())
+                }
+            }
+        }
+    };
+
+    match state.flush_wal() {
+        Ok(bytes) => tracing::info!(bytes, "WAL synced on shutdown"),
+        Err(err) => tracing::error!(?err, "failed to sync WAL on shutdown"),
+    }
+    result.map(|()| cut_off)
+}
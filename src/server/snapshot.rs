@@ -0,0 +1,34 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::server::state::DbState;
+
+/// Periodically snapshots the catalog and compacts the WAL behind it.
+///
+/// Each cycle runs on the blocking pool; a tick that arrives while the
+/// previous cycle is still running is skipped.
+pub fn spawn(state: Arc<DbState>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        ticker.tick().await; // the first tick fires immediately
+        loop {
+            ticker.tick().await;
+            let state = state.clone();
+            let started = Instant::now();
+            match tokio::task::spawn_blocking(move || state.save_snapshot()).await {
+                Ok(Ok(Some(report))) => tracing::info!(
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    collections = report.collections,
+                    points = report.points,
+                    reclaimed_bytes = report.wal_bytes_reclaimed,
+                    "snapshot written and WAL compacted"
+                ),
+                Ok(Ok(None)) => tracing::debug!("snapshot already in progress; skipping cycle"),
+                Ok(Err(err)) => tracing::warn!(?err, "snapshot cycle failed"),
+                Err(err) => tracing::error!(?err, "snapshot task panicked"),
+            }
+        }
+    })
+}
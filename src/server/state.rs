@@ -1,15 +1,129 @@
-use std::{env, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    io,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
-use crate::catalog::{Catalog, PointWrite};
+use crate::catalog::{Catalog, Collection, CreateError, PointWrite, DEFAULT_PARALLEL_THRESHOLD};
+use crate::ids::IdScheme;
+use crate::server::integrity::{ReplayMismatch, ReplayTally};
+use crate::storage::group_commit::GroupCommit;
+use crate::storage::snapshot::{self, SnapshotError};
 use crate::storage::wal::{Wal, WalRecord};
-use crate::types::Metric;
-use tracing::{error, warn};
+use crate::types::{EmptyQuery, IdKind, Metric, PayloadStore, ScorePrecision, VectorLayout, ZeroNorm};
+use rayon::prelude::*;
+use thiserror::Error;
+use tracing::{error, info, warn};
 
 /// Central database state: catalog and optional write-ahead log.
 #[derive(Clone)]
 pub struct DbState {
     pub catalog: Catalog,
     pub wal: Option<Wal>,
+    pub readiness: Readiness,
+    /// Metric applied when CreateCollection leaves it unset.
+    pub default_metric: Metric,
+    /// Reject every mutating RPC; the WAL is only replayed, never appended.
+    pub read_only: bool,
+    /// Where `save_snapshot` writes and startup loads the catalog snapshot.
+    pub snapshot_path: Option<PathBuf>,
+    /// Largest `dims` CreateCollection accepts.
+    pub max_dims: usize,
+    /// Most collections CreateCollection will let the catalog hold.
+    pub max_collections: usize,
+    /// Upserts are refused while the WAL backlog exceeds this many bytes;
+    /// 0 disables the check.
+    pub wal_backlog_limit_bytes: usize,
+    /// Snapshot (and compact the WAL) once it holds more than this many
+    /// records; see `enforce_wal_retention`.
+    pub wal_retention_records: Option<usize>,
+    /// Kind of id generated for points upserted without one.
+    pub id_scheme: IdScheme,
+    /// Set when writes must be fsynced before they are acknowledged; see
+    /// `DbStateConfig::wal_commit_window`.
+    pub wal_commit: Option<Arc<GroupCommit>>,
+    /// Records applied by the startup WAL replay, keyed by `WalRecord::kind`.
+    /// Records loaded from the snapshot are not included.
+    pub wal_replay_counts: BTreeMap<&'static str, u64>,
+    /// Wall-clock time the startup WAL replay took, reading included.
+    pub wal_replay_duration: Duration,
+    /// Collections whose loaded point count disagreed with the snapshot and
+    /// WAL records at startup; always empty unless
+    /// `DbStateConfig::verify_on_start` was set.
+    pub replay_mismatches: Vec<ReplayMismatch>,
+    snapshot_running: Arc<AtomicBool>,
+}
+
+/// Default cap on collection dimensions; well above any embedding model,
+/// low enough that one vector stays under a megabyte.
+pub const DEFAULT_MAX_DIMS: usize = 65_536;
+
+/// Default cap on the number of collections; far above legitimate use, but
+/// stops a runaway client from exhausting memory with tiny collections.
+pub const DEFAULT_MAX_COLLECTIONS: usize = 10_000;
+
+/// Outcome of one `DbState::save_snapshot` run.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SnapshotReport {
+    pub collections: usize,
+    pub points: usize,
+    /// Bytes dropped from the head of the WAL after the snapshot was written.
+    pub wal_bytes_reclaimed: u64,
+}
+
+/// Catalog contents captured by `DbState::snapshot_in_memory`, as the same
+/// records a snapshot file holds.
+#[derive(Clone, Debug)]
+pub struct CatalogSnapshot {
+    records: Vec<WalRecord>,
+}
+
+impl CatalogSnapshot {
+    /// Number of points captured.
+    pub fn points(&self) -> usize {
+        self.records.iter().filter(|rec| matches!(rec, WalRecord::Upsert { .. })).count()
+    }
+}
+
+/// Why `DbState::restore_collection` refused a collection snapshot.
+#[derive(Debug, Error)]
+pub enum RestoreError {
+    #[error("collection '{0}' already exists; drop it before restoring")]
+    AlreadyExists(String),
+    #[error("file does not hold a single-collection snapshot")]
+    NotSingleCollection,
+    #[error("dims {dims} exceeds the limit of {max}")]
+    DimsExceeded { dims: usize, max: usize },
+    #[error("collection limit of {0} reached")]
+    LimitReached(usize),
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+}
+
+/// Shared flag flipped once WAL replay has finished and the state can serve traffic.
+#[derive(Clone, Debug, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn mark_ready(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+impl Default for DbState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DbState {
@@ -18,11 +132,27 @@ impl DbState {
     }
 
     pub fn with_config(config: DbStateConfig) -> Self {
-        let catalog = Catalog::default();
+        Self::with_readiness(config, Readiness::default())
+    }
+
+    /// Like `with_config`, but reports replay completion through an existing
+    /// readiness handle (e.g. one already wired into the probe endpoints).
+    pub fn with_readiness(config: DbStateConfig, readiness: Readiness) -> Self {
+        let catalog = Catalog::with_parallel_threshold(config.parallel_threshold)
+            .with_empty_query(config.empty_query)
+            .with_score_precision(config.score_precision)
+            .with_zero_norm(config.zero_norm);
         let wal = if config.enable_wal {
             match &config.wal_path {
-                Some(path) => match Wal::open(path.clone()) {
+                Some(path) if config.read_only => match Wal::open_read_only(path.clone()) {
                     Ok(wal) => Some(wal),
+                    Err(err) => {
+                        warn!(path = %path.display(), ?err, "failed to open WAL for read-only replay; starting empty");
+                        None
+                    }
+                },
+                Some(path) => match Wal::open(path.clone()) {
+                    Ok(wal) => Some(wal.with_buffer(config.wal_buffer_bytes).with_flush_every(config.wal_flush_every_records)),
                     Err(err) => {
                         warn!(path = %path.display(), ?err, "failed to open WAL; continuing without durability");
                         None
@@ -34,40 +164,524 @@ impl DbState {
             None
         };
 
-        let state = Self { catalog, wal };
-        state.replay_wal();
+        // Without an explicit path the snapshot sits next to the WAL, so a
+        // restart finds it no matter which flags the snapshotting run used.
+        let snapshot_path = config.snapshot_path.clone().or_else(|| {
+            config.wal_path.as_ref().filter(|_| config.enable_wal).map(|p| p.with_extension("snapshot"))
+        });
+        if config.wal_retention_records.is_some() && wal.is_some() && snapshot_path.is_none() {
+            warn!("WAL retention needs a snapshot path to compact into; the cap is not enforced");
+        }
+        // Spill files are scratch space, not durability: they sit with the
+        // other persistent files but are discarded on startup.
+        let catalog = match config.memory_budget_bytes {
+            Some(budget) => {
+                let spill_dir = match (&config.data_dir, &snapshot_path) {
+                    (Some(dir), _) => dir.join("spill"),
+                    (None, Some(path)) => path.with_extension("spill"),
+                    (None, None) => env::temp_dir().join(format!("vectaraft-spill-{}", std::process::id())),
+                };
+                match catalog.clone().with_memory_budget(budget, &spill_dir) {
+                    Ok(catalog) => catalog,
+                    Err(err) => {
+                        warn!(dir = %spill_dir.display(), ?err, "failed to prepare spill directory; memory budget not enforced");
+                        catalog
+                    }
+                }
+            }
+            None => catalog,
+        };
+        let wal_commit = match (&wal, config.wal_commit_window) {
+            (Some(wal), Some(window)) if !config.read_only => Some(Arc::new(GroupCommit::new(wal.clone(), window))),
+            _ => None,
+        };
+        let mut state = Self {
+            catalog,
+            wal,
+            wal_commit,
+            readiness,
+            default_metric: config.default_metric,
+            read_only: config.read_only,
+            snapshot_path,
+            max_dims: config.max_dims,
+            max_collections: config.max_collections,
+            wal_backlog_limit_bytes: config.wal_backlog_limit_bytes,
+            wal_retention_records: config.wal_retention_records,
+            id_scheme: config.id_scheme,
+            wal_replay_counts: BTreeMap::new(),
+            wal_replay_duration: Duration::ZERO,
+            replay_mismatches: Vec::new(),
+            snapshot_running: Arc::default(),
+        };
+        let mut tally = config.verify_on_start.then(ReplayTally::default);
+        let covered = state.load_snapshot(tally.as_mut());
+        let started = Instant::now();
+        state.wal_replay_counts = state.replay_wal(covered, tally.as_mut());
+        state.wal_replay_duration = started.elapsed();
+        if let Some(tally) = tally {
+            let started = Instant::now();
+            state.replay_mismatches = tally.verify(&state.catalog);
+            for mismatch in &state.replay_mismatches {
+                error!(
+                    collection = %mismatch.collection,
+                    expected = ?mismatch.expected,
+                    loaded = ?mismatch.loaded,
+                    "loaded points disagree with the snapshot and WAL",
+                );
+            }
+            info!(
+                mismatches = state.replay_mismatches.len(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "startup integrity check finished",
+            );
+        }
+        state.pin_at_startup(&config.pinned_collections);
+        let evicted = state.catalog.enforce_memory_budget();
+        if !evicted.is_empty() {
+            info!(collections = evicted.len(), "evicted collections to fit the memory budget");
+        }
+        state.readiness.mark_ready();
         state
     }
 
-    fn replay_wal(&self) {
-        let Some(wal) = &self.wal else { return; };
+    /// Pins `names` and warms the ones that exist, before the server reports
+    /// ready.
+    fn pin_at_startup(&self, names: &[String]) {
+        for name in names {
+            self.catalog.pin(name);
+            match self.catalog.get(name).and_then(|handle| handle.warmup()) {
+                Some((points, bytes)) => info!(collection = %name, points, bytes, "pinned collection warmed"),
+                None => info!(collection = %name, "pinned collection does not exist yet; pinned once created"),
+            }
+        }
+    }
+
+    /// Applies the snapshot, if any, and returns the WAL seq it covers.
+    fn load_snapshot(&self, tally: Option<&mut ReplayTally>) -> u64 {
+        let Some(path) = &self.snapshot_path else { return 0; };
+        if !path.exists() {
+            return 0;
+        }
+        match snapshot::read_covering(path) {
+            Ok((records, covered)) => {
+                if let Some(tally) = tally {
+                    records.iter().for_each(|rec| tally.record(rec));
+                }
+                self.apply_records(records);
+                covered
+            }
+            Err(err) => {
+                // Keep the rejected file so the next snapshot cannot overwrite it.
+                let mut rejected = path.as_os_str().to_owned();
+                rejected.push(".rejected");
+                error!(
+                    path = %path.display(),
+                    error = %err,
+                    moved_to = ?rejected,
+                    "snapshot failed validation; relying on WAL replay",
+                );
+                if let Err(err) = std::fs::rename(path, &rejected) {
+                    warn!(?err, "failed to move rejected snapshot aside");
+                }
+                0
+            }
+        }
+    }
+
+    /// Replays the WAL records numbered `covered` and up; the snapshot
+    /// already holds the ones before.
+    fn replay_wal(&self, covered: u64, tally: Option<&mut ReplayTally>) -> BTreeMap<&'static str, u64> {
+        let mut counts = BTreeMap::new();
+        let Some(wal) = &self.wal else { return counts; };
+        let started = Instant::now();
         match wal.replay() {
-            Ok(records) => {
-                for rec in records {
-                    match rec {
-                        WalRecord::CreateCollection { name, dim, metric, .. } => {
-                            let metric = Metric::from_str(&metric);
-                            let _ = self.catalog.create_collection(name, dim as usize, metric);
-                        }
-                        WalRecord::Upsert { collection, id, vector, payload_json, .. } => {
-                            if let Some(handle) = self.catalog.get(&collection) {
-                                let _ = handle.upsert_points(vec![PointWrite {
-                                    id,
-                                    vector,
-                                    payload_json,
-                                }]);
-                            }
-                        }
-                    }
+            Ok(mut records) => {
+                let skip = covered.saturating_sub(wal.base_seq()).min(records.len() as u64) as usize;
+                if skip > 0 {
+                    info!(skipped = skip, "skipping WAL records already in the snapshot");
+                    records.drain(..skip);
+                }
+                for rec in &records {
+                    *counts.entry(rec.kind()).or_default() += 1;
+                }
+                if let Some(tally) = tally {
+                    records.iter().for_each(|rec| tally.record(rec));
                 }
+                self.apply_records(records);
+                info!(
+                    records = counts.values().sum::<u64>(),
+                    counts = ?counts,
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    "WAL replay finished",
+                );
             }
             Err(err) => {
                 warn!(?err, "failed to replay WAL; database will start empty");
             }
         }
+        counts
+    }
+
+    /// Applies records in log order per collection. Collections are
+    /// independent, so the records between two `SwapCollections` (the only
+    /// record touching two collections) are split by collection and each
+    /// collection's share is replayed concurrently on the rayon pool.
+    fn apply_records(&self, records: Vec<WalRecord>) {
+        let mut shards: Vec<Vec<WalRecord>> = Vec::new();
+        let mut shard_of: HashMap<String, usize> = HashMap::new();
+        for rec in records {
+            let collection = match &rec {
+                WalRecord::SwapCollections { .. } => {
+                    self.apply_shards(std::mem::take(&mut shards));
+                    shard_of.clear();
+                    self.apply_record(rec);
+                    continue;
+                }
+                WalRecord::CreateCollection { name, .. } => name,
+                WalRecord::Upsert { collection, .. }
+                | WalRecord::SetCollectionConfig { collection, .. }
+                | WalRecord::SetMetric { collection, .. }
+                | WalRecord::Truncate { collection, .. }
+                | WalRecord::Delete { collection, .. }
+                | WalRecord::DropCollection { collection, .. } => collection,
+            };
+            let shard = *shard_of.entry(collection.clone()).or_insert_with(|| {
+                shards.push(Vec::new());
+                shards.len() - 1
+            });
+            shards[shard].push(rec);
+        }
+        self.apply_shards(shards);
+    }
+
+    fn apply_shards(&self, shards: Vec<Vec<WalRecord>>) {
+        if shards.len() > 1 {
+            shards.into_par_iter().for_each(|shard| self.apply_in_order(shard));
+        } else {
+            shards.into_iter().for_each(|shard| self.apply_in_order(shard));
+        }
+    }
+
+    /// Applies records in log order. Runs of upserts to the same collection
+    /// are buffered and written with one lock acquisition per run.
+    fn apply_in_order(&self, records: Vec<WalRecord>) {
+        let mut pending: Option<(String, Vec<PointWrite>)> = None;
+        for rec in records {
+            if let WalRecord::Upsert { collection, id, vector, payload_json, expires_at_ms, .. } = rec {
+                let point = PointWrite { id, vector, payload_json, expires_at_ms };
+                match &mut pending {
+                    Some((name, points)) if *name == collection => points.push(point),
+                    _ => {
+                        self.flush_upserts(pending.take());
+                        pending = Some((collection, vec![point]));
+                    }
+                }
+                continue;
+            }
+            self.flush_upserts(pending.take());
+            self.apply_record(rec);
+        }
+        self.flush_upserts(pending);
+    }
+
+    /// Appends a buffered run of upserts. One bad point would fail the whole
+    /// batch, so a run that does not pass is restored point by point and only
+    /// the rejected points are skipped, as one-point upserts would have been.
+    fn flush_upserts(&self, pending: Option<(String, Vec<PointWrite>)>) {
+        let Some((collection, points)) = pending else { return; };
+        let Some(handle) = self.catalog.get(&collection) else { return; };
+        let _ = handle.with_mut(|coll| {
+            if let Err(err) = coll.check_restore(&points) {
+                warn!(collection = %coll.name, %err, "upsert batch rejected during replay; restoring its points one by one");
+                for point in points {
+                    let id = point.id.clone();
+                    if let Err(err) = coll.restore_batch(vec![point]) {
+                        warn!(collection = %coll.name, %id, %err, "skipping upsert during replay");
+                    }
+                }
+            } else if let Err(err) = coll.restore_batch(points) {
+                warn!(collection = %coll.name, %err, "skipping upsert batch during replay");
+            }
+        });
+    }
+
+    fn apply_record(&self, rec: WalRecord) {
+        match rec {
+            rec @ WalRecord::CreateCollection { .. } => {
+                if let Some(collection) = configured_collection(&rec) {
+                    let _ = self.catalog.create_collection_with(collection, usize::MAX, || {});
+                }
+            }
+            WalRecord::SetCollectionConfig { collection, parallel_threshold, default_top_k, default_with_payloads, .. } => {
+                if let Some(handle) = self.catalog.get(&collection) {
+                    let _ = handle.with_mut(|coll| {
+                        coll.parallel_threshold = parallel_threshold.map(|t| t as usize);
+                        coll.default_top_k = default_top_k;
+                        coll.default_with_payloads = default_with_payloads;
+                    });
+                }
+            }
+            WalRecord::SetMetric { collection, metric, .. } => {
+                if let Some(handle) = self.catalog.get(&collection) {
+                    let metric = Metric::from_str(&metric);
+                    let _ = handle.with_mut(|coll| coll.set_metric(metric));
+                }
+            }
+            WalRecord::Truncate { collection, .. } => {
+                if let Some(handle) = self.catalog.get(&collection) {
+                    let _ = handle.with_mut(|coll| coll.clear());
+                }
+            }
+            WalRecord::SwapCollections { target, source, .. } => {
+                if let Err(err) = self.catalog.swap_collections_with(&target, &source, || {}) {
+                    warn!(%target, %source, %err, "skipping SwapCollections record during replay");
+                }
+            }
+            WalRecord::DropCollection { collection, .. } => {
+                self.catalog.drop_collection(&collection);
+            }
+            WalRecord::Delete { collection, ids, .. } => {
+                if let Some(handle) = self.catalog.get(&collection) {
+                    let _ = handle.with_mut(|coll| coll.remove_points(&ids));
+                }
+            }
+            WalRecord::Upsert { collection, id, vector, payload_json, expires_at_ms, .. } => {
+                if let Some(handle) = self.catalog.get(&collection) {
+                    let _ = handle.with_mut(|coll| {
+                        coll.restore_batch(vec![PointWrite { id, vector, payload_json, expires_at_ms }])
+                    });
+                }
+            }
+        }
+    }
+
+    /// Removes every point whose TTL has run out, logging a `Delete` record
+    /// per affected collection. Returns the number of points removed.
+    /// Evicted collections are left alone rather than reloaded; their
+    /// expired points stay hidden and are swept once they are back.
+    pub fn sweep_expired(&self) -> usize {
+        if self.read_only {
+            return 0;
+        }
+        let now = now_ms();
+        let mut removed = 0;
+        for name in self.catalog.names() {
+            if self.catalog.is_resident(&name) != Some(true) {
+                continue;
+            }
+            let Some(handle) = self.catalog.get(&name) else { continue; };
+            // Logged under the collection lock, as upserts are, so the
+            // delete reaches the WAL in order with other writes.
+            let swept = handle.with_mut(|coll| {
+                let ids = coll.remove_expired(now);
+                let count = ids.len();
+                if count > 0 {
+                    self.append_wal(WalRecord::Delete { collection: name.clone(), ids, ts_ms: now });
+                }
+                count
+            });
+            removed += swept.unwrap_or(0);
+        }
+        removed
+    }
+
+    /// The WAL backlog in bytes, if it is over `wal_backlog_limit_bytes`.
+    pub fn wal_backlog_exceeded(&self) -> Option<usize> {
+        let wal = self.wal.as_ref()?;
+        let backlog = wal.backlog_bytes();
+        (self.wal_backlog_limit_bytes > 0 && backlog > self.wal_backlog_limit_bytes).then_some(backlog)
+    }
+
+    /// Waits until every WAL record appended so far is fsynced, sharing the
+    /// fsync with other writers in the same commit window. Returns at once
+    /// when group commit is off.
+    ///
+    /// A write whose records take the WAL past `wal_retention_records` also
+    /// waits for the snapshot that brings it back under the cap. A failed
+    /// snapshot is logged, not returned: the write itself is applied.
+    pub async fn commit_wal(&self) -> anyhow::Result<()> {
+        if let Some(commit) = &self.wal_commit {
+            commit.commit().await?;
+        }
+        if self.wal_retention_exceeded() {
+            let state = self.clone();
+            match tokio::task::spawn_blocking(move || state.enforce_wal_retention()).await {
+                Ok(Err(err)) => warn!(?err, "snapshot for WAL retention failed; the WAL stays over its cap"),
+                Err(err) => warn!(?err, "snapshot for WAL retention panicked"),
+                Ok(Ok(_)) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn wal_retention_exceeded(&self) -> bool {
+        match (&self.wal, self.wal_retention_records) {
+            (Some(wal), Some(limit)) => !self.read_only && self.snapshot_path.is_some() && wal.record_count() > limit,
+            _ => false,
+        }
+    }
+
+    /// Caps the WAL at `wal_retention_records`: once it holds more, the
+    /// catalog is snapshotted and the records the snapshot covers (the oldest
+    /// ones) are dropped. Only records written after the snapshot began stay
+    /// in the log, so nothing is dropped that the snapshot does not hold.
+    /// Returns `Ok(None)` when under the cap or another snapshot is running.
+    pub fn enforce_wal_retention(&self) -> anyhow::Result<Option<SnapshotReport>> {
+        if !self.wal_retention_exceeded() {
+            return Ok(None);
+        }
+        self.save_snapshot()
+    }
+
+    /// Writes out buffered WAL records and fsyncs the log. Returns the number
+    /// of bytes that were still buffered.
+    pub fn flush_wal(&self) -> anyhow::Result<usize> {
+        match &self.wal {
+            Some(wal) if !self.read_only => wal.flush(),
+            _ => Ok(0),
+        }
+    }
+
+    /// Writes the whole catalog to `snapshot_path`, then drops the WAL records
+    /// the snapshot covers. Returns `Ok(None)` if another snapshot is running.
+    ///
+    /// The WAL position is taken and the catalog cloned while logged writes
+    /// are held off (see `Catalog::snapshot_with`), and the snapshot records
+    /// that position, so a crash before the WAL is compacted does not replay
+    /// covered records twice. Serialization and file I/O happen on the copy,
+    /// so writers only wait for the clone.
+    pub fn save_snapshot(&self) -> anyhow::Result<Option<SnapshotReport>> {
+        if self.read_only {
+            anyhow::bail!("snapshots are disabled in read-only mode");
+        }
+        let Some(path) = &self.snapshot_path else {
+            anyhow::bail!("no snapshot path configured");
+        };
+        if self.snapshot_running.swap(true, Ordering::AcqRel) {
+            return Ok(None);
+        }
+        let result = self.write_snapshot(path);
+        self.snapshot_running.store(false, Ordering::Release);
+        result.map(Some)
+    }
+
+    fn write_snapshot(&self, path: &Path) -> anyhow::Result<SnapshotReport> {
+        // Logged writes apply and append under the catalog's write gate, so
+        // the clone holds exactly the records before this position.
+        let (wal_mark, collections) = self.catalog.snapshot_with(|| self.wal.as_ref().map(Wal::position));
+        let wal_mark = wal_mark.transpose()?;
+        let records = catalog_records(&collections, now_ms());
+        let snapshot = CatalogSnapshot { records };
+        let mut report = SnapshotReport {
+            collections: collections.len(),
+            points: snapshot.points(),
+            ..SnapshotReport::default()
+        };
+        snapshot::write_covering(path, &snapshot.records, wal_mark.map_or(0, |mark| mark.seq))?;
+
+        if let (Some(wal), Some(mark)) = (&self.wal, wal_mark) {
+            report.wal_bytes_reclaimed = wal.compact_before(mark.offset)?;
+        }
+        Ok(report)
+    }
+
+    /// Captures the catalog without touching disk, e.g. to reset state
+    /// between test cases. Expired points are left out, as in `save_snapshot`.
+    pub fn snapshot_in_memory(&self) -> CatalogSnapshot {
+        CatalogSnapshot { records: catalog_records(&self.catalog.snapshot(), now_ms()) }
+    }
+
+    /// Replaces the catalog with `snapshot`'s contents. Neither the WAL nor
+    /// any snapshot file is written, so a restart still sees the logged
+    /// state. Readers running concurrently may observe a partly restored
+    /// catalog.
+    pub fn restore_in_memory(&self, snapshot: &CatalogSnapshot) {
+        self.catalog.clear();
+        self.apply_records(snapshot.records.clone());
+    }
+
+    /// Where the collection snapshot of `collection` lives: `collections/<name>.snapshot`
+    /// beside the catalog snapshot. `None` without a snapshot path, or when
+    /// the name cannot safely be used as a file name.
+    pub fn collection_snapshot_path(&self, collection: &str) -> Option<PathBuf> {
+        let unsafe_name = collection.is_empty() || collection.starts_with('.') || collection.contains(['/', '\\']);
+        if unsafe_name {
+            return None;
+        }
+        let dir = self.snapshot_path.as_ref()?.with_file_name("collections");
+        Some(dir.join(format!("{collection}.snapshot")))
+    }
+
+    /// Recreates the collection saved by `Collection::save_snapshot` at
+    /// `path` and logs it to the WAL so it survives a restart. The collection
+    /// must not exist, and the `max_dims` and `max_collections` limits apply
+    /// as they do to CreateCollection. Returns its name and point count.
+    pub fn restore_collection(&self, path: &Path) -> Result<(String, usize), RestoreError> {
+        let records = snapshot::read(path)?;
+        let Some(WalRecord::CreateCollection { name, .. }) = records.first() else {
+            return Err(RestoreError::NotSingleCollection);
+        };
+        let name = name.clone();
+        let single = records[1..]
+            .iter()
+            .all(|rec| matches!(rec, WalRecord::Upsert { collection, .. } if *collection == name));
+        if !single {
+            return Err(RestoreError::NotSingleCollection);
+        }
+        let (create, upserts) = records.split_first().ok_or(RestoreError::NotSingleCollection)?;
+        let collection = configured_collection(create).ok_or(RestoreError::NotSingleCollection)?;
+        if collection.dim > self.max_dims {
+            return Err(RestoreError::DimsExceeded { dims: collection.dim, max: self.max_dims });
+        }
+        // The existence and limit checks happen under the same map lock as the
+        // insert, so concurrent restores and creates cannot both succeed.
+        match self.catalog.create_collection_with(collection, self.max_collections, || self.append_wal(create.clone())) {
+            Ok(()) => {}
+            Err(CreateError::AlreadyExists) => return Err(RestoreError::AlreadyExists(name)),
+            Err(CreateError::LimitReached(max)) => return Err(RestoreError::LimitReached(max)),
+        }
+        let points: Vec<PointWrite> = upserts
+            .iter()
+            .filter_map(|rec| match rec {
+                WalRecord::Upsert { id, vector, payload_json, expires_at_ms, .. } => Some(PointWrite {
+                    id: id.clone(),
+                    vector: vector.clone(),
+                    payload_json: payload_json.clone(),
+                    expires_at_ms: *expires_at_ms,
+                }),
+                _ => None,
+            })
+            .collect();
+        let restored = points.len();
+        if let Some(handle) = self.catalog.get(&name) {
+            // Logged under the collection lock, as upserts are.
+            let _ = handle.with_mut(|coll| {
+                for rec in upserts {
+                    self.append_wal(rec.clone());
+                }
+                let kind = coll.index.id_kind();
+                let valid = points.into_iter().filter(|p| coll.validate_dim(&p.vector) && kind.accepts(&p.id)).collect();
+                if let Err(err) = coll.restore_batch(valid) {
+                    warn!(collection = %coll.name, %err, "skipping points that cannot be restored");
+                }
+            });
+        }
+        Ok((name, restored))
+    }
+
+    /// Logs `records` with a single write. Unlike `append_wal` the error is
+    /// returned, and none of the records are logged when it fails.
+    pub fn append_wal_batch(&self, records: &[WalRecord]) -> anyhow::Result<()> {
+        match &self.wal {
+            Some(wal) if !self.read_only => wal.append_all(records),
+            _ => Ok(()),
+        }
     }
 
     pub fn append_wal(&self, record: WalRecord) {
+        if self.read_only {
+            return;
+        }
         if let Some(wal) = &self.wal {
             if let Err(err) = wal.append(&record) {
                 error!(?err, "failed to append WAL record");
@@ -76,10 +690,106 @@ impl DbState {
     }
 }
 
+/// The empty, fully configured collection a `CreateCollection` record
+/// describes; `None` for any other record.
+fn configured_collection(rec: &WalRecord) -> Option<Collection> {
+    let WalRecord::CreateCollection {
+        name,
+        dim,
+        metric,
+        parallel_threshold,
+        layout,
+        assume_normalized,
+        default_top_k,
+        default_with_payloads,
+        payload_store,
+        id_type,
+        ..
+    } = rec
+    else {
+        return None;
+    };
+    let mut collection = Collection::new(name.clone(), *dim as usize, Metric::from_str(metric));
+    collection.parallel_threshold = parallel_threshold.map(|t| t as usize);
+    collection.assume_normalized = *assume_normalized;
+    collection.default_top_k = *default_top_k;
+    collection.default_with_payloads = *default_with_payloads;
+    collection.index.set_layout(layout.as_deref().and_then(VectorLayout::parse).unwrap_or_default());
+    collection.index.set_payload_store(payload_store.as_deref().and_then(PayloadStore::parse).unwrap_or_default());
+    // An empty collection accepts any id type.
+    collection.index.set_id_kind(id_type.as_deref().and_then(IdKind::parse).unwrap_or_default()).ok()?;
+    Some(collection)
+}
+
 #[derive(Clone, Debug)]
 pub struct DbStateConfig {
     pub wal_path: Option<PathBuf>,
     pub enable_wal: bool,
+    pub default_metric: Metric,
+    /// Root for all persistent files when set via `apply_data_dir`.
+    pub data_dir: Option<PathBuf>,
+    pub snapshot_path: Option<PathBuf>,
+    pub read_only: bool,
+    /// Bytes of WAL records held in memory before being written; 0 writes
+    /// every record immediately. See `Wal::with_buffer`.
+    pub wal_buffer_bytes: usize,
+    /// WAL records held in memory and then written and fsynced as one group;
+    /// 0 disables grouping. See `Wal::with_flush_every`.
+    pub wal_flush_every_records: usize,
+    /// Point count at which searches switch to a parallel scan, for
+    /// collections without their own override.
+    pub parallel_threshold: usize,
+    /// Largest vector dimension a new collection may declare. Collections
+    /// already in the WAL or snapshot load regardless.
+    pub max_dims: usize,
+    /// Collections CreateCollection may create before returning
+    /// `RESOURCE_EXHAUSTED`. Collections already in the WAL or snapshot load
+    /// regardless.
+    pub max_collections: usize,
+    /// Backlog of unwritten WAL bytes above which Upsert returns
+    /// `RESOURCE_EXHAUSTED`; 0 disables backpressure.
+    pub wal_backlog_limit_bytes: usize,
+    /// Most WAL records kept before a snapshot is forced and the WAL
+    /// compacted. `None` leaves compaction to the snapshot interval.
+    pub wal_retention_records: Option<usize>,
+    /// Kind of id minted for points upserted without one.
+    pub id_scheme: IdScheme,
+    /// When set, write RPCs return only after their WAL records are fsynced,
+    /// and writes arriving within this window of each other share one fsync.
+    /// `None` acknowledges writes before they reach disk.
+    pub wal_commit_window: Option<Duration>,
+    /// How searches treat an empty query vector, in both the gRPC API and
+    /// `CollectionHandle::search`.
+    pub empty_query: EmptyQuery,
+    /// Accumulator width used when scoring L2, inner-product and cosine.
+    pub score_precision: ScorePrecision,
+    /// How cosine collections treat zero-norm vectors on upsert and search.
+    pub zero_norm: ZeroNorm,
+    /// Collections pinned at startup: warmed once the WAL is replayed and
+    /// never evicted. Names without a collection yet apply once it exists.
+    pub pinned_collections: Vec<String>,
+    /// Approximate bytes resident collections may hold before the least
+    /// recently used are spilled to disk; `None` keeps everything in memory.
+    /// See `Catalog::with_memory_budget`.
+    pub memory_budget_bytes: Option<usize>,
+    /// After replay, recount each collection's points from the snapshot and
+    /// WAL records and compare them with the loaded collections; see
+    /// `DbState::replay_mismatches`. Costs a second pass over the records.
+    pub verify_on_start: bool,
+}
+
+impl DbStateConfig {
+    /// Creates `dir` if needed and derives every persistence path from it:
+    /// `<dir>/wal` (when the WAL is enabled) and `<dir>/snapshot`.
+    pub fn apply_data_dir(&mut self, dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        if self.enable_wal {
+            self.wal_path = Some(dir.join("wal"));
+        }
+        self.snapshot_path = Some(dir.join("snapshot"));
+        self.data_dir = Some(dir.to_path_buf());
+        Ok(())
+    }
 }
 
 impl Default for DbStateConfig {
@@ -97,13 +807,113 @@ impl Default for DbStateConfig {
         } else {
             None
         };
+        let default_metric = match env::var("VECTARAFT_DEFAULT_METRIC") {
+            Ok(name) => Metric::parse(&name).unwrap_or_else(|| {
+                warn!(%name, "unrecognized VECTARAFT_DEFAULT_METRIC; using l2");
+                Metric::L2
+            }),
+            Err(_) => Metric::L2,
+        };
         Self {
             wal_path,
             enable_wal,
+            default_metric,
+            data_dir: None,
+            snapshot_path: None,
+            read_only: env::var("VECTARAFT_READ_ONLY")
+                .ok()
+                .and_then(|v| parse_bool(&v))
+                .unwrap_or(false),
+            wal_buffer_bytes: env::var("VECTARAFT_WAL_BUFFER_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            wal_flush_every_records: env::var("VECTARAFT_WAL_FLUSH_EVERY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            parallel_threshold: env::var("VECTARAFT_PARALLEL_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_PARALLEL_THRESHOLD),
+            max_dims: env::var("VECTARAFT_MAX_DIMS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|dims| *dims > 0)
+                .unwrap_or(DEFAULT_MAX_DIMS),
+            max_collections: env::var("VECTARAFT_MAX_COLLECTIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|count| *count > 0)
+                .unwrap_or(DEFAULT_MAX_COLLECTIONS),
+            wal_backlog_limit_bytes: env::var("VECTARAFT_WAL_BACKLOG_LIMIT_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            wal_retention_records: env::var("VECTARAFT_WAL_RETENTION_RECORDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|records| *records > 0),
+            id_scheme: match env::var("VECTARAFT_ID_SCHEME") {
+                Ok(name) => IdScheme::parse(&name).unwrap_or_else(|| {
+                    warn!(%name, "unrecognized VECTARAFT_ID_SCHEME; using uuid");
+                    IdScheme::Uuid
+                }),
+                Err(_) => IdScheme::Uuid,
+            },
+            wal_commit_window: env::var("VECTARAFT_WAL_COMMIT_WINDOW_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis),
+            empty_query: match env::var("VECTARAFT_EMPTY_QUERY") {
+                Ok(name) => EmptyQuery::parse(&name).unwrap_or_else(|| {
+                    warn!(%name, "unrecognized VECTARAFT_EMPTY_QUERY; rejecting empty queries");
+                    EmptyQuery::Reject
+                }),
+                Err(_) => EmptyQuery::Reject,
+            },
+            score_precision: match env::var("VECTARAFT_SCORE_PRECISION") {
+                Ok(name) => ScorePrecision::parse(&name).unwrap_or_else(|| {
+                    warn!(%name, "unrecognized VECTARAFT_SCORE_PRECISION; using f32");
+                    ScorePrecision::F32
+                }),
+                Err(_) => ScorePrecision::F32,
+            },
+            zero_norm: match env::var("VECTARAFT_ZERO_NORM") {
+                Ok(name) => ZeroNorm::parse(&name).unwrap_or_else(|| {
+                    warn!(%name, "unrecognized VECTARAFT_ZERO_NORM; scoring zero vectors as 0");
+                    ZeroNorm::Zero
+                }),
+                Err(_) => ZeroNorm::Zero,
+            },
+            pinned_collections: env::var("VECTARAFT_PIN_COLLECTIONS")
+                .map(|names| parse_collection_names(&names))
+                .unwrap_or_default(),
+            memory_budget_bytes: env::var("VECTARAFT_MEMORY_BUDGET_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|bytes| *bytes > 0),
+            verify_on_start: env::var("VECTARAFT_VERIFY_ON_START")
+                .ok()
+                .and_then(|v| parse_bool(&v))
+                .unwrap_or(false),
         }
     }
 }
 
+/// The records that recreate `collections`: a CreateCollection per
+/// collection followed by an Upsert per live point.
+fn catalog_records(collections: &[Collection], ts: i64) -> Vec<WalRecord> {
+    collections.iter().flat_map(|coll| coll.snapshot_records(ts)).collect()
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
 fn parse_bool(input: &str) -> Option<bool> {
     match input.to_ascii_lowercase().as_str() {
         "1" | "true" | "yes" | "on" => Some(true),
@@ -111,3 +921,8 @@ fn parse_bool(input: &str) -> Option<bool> {
         _ => None,
     }
 }
+
+/// Splits a comma-separated list of collection names, dropping blanks.
+pub fn parse_collection_names(input: &str) -> Vec<String> {
+    input.split(',').map(str::trim).filter(|name| !name.is_empty()).map(str::to_string).collect()
+}
@@ -0,0 +1,84 @@
+//! Latency-bounded group commit: writers that ask for durability within one
+//! window share a single WAL fsync.
+//!
+//! The first waiter opens a window; everyone who asks before it closes is
+//! released by the same `Wal::flush`. A lone writer therefore waits at most
+//! one window plus one fsync, and a burst of N writers pays for one fsync
+//! instead of N.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::storage::wal::Wal;
+
+type Waiter = oneshot::Sender<Result<(), String>>;
+
+pub struct GroupCommit {
+    wal: Wal,
+    window: Duration,
+    // Started on first use so a state built outside a runtime stays usable.
+    // The task exits once this sender is dropped.
+    tx: OnceLock<mpsc::UnboundedSender<Waiter>>,
+    syncs: Arc<AtomicU64>,
+}
+
+impl GroupCommit {
+    pub fn new(wal: Wal, window: Duration) -> Self {
+        Self { wal, window, tx: OnceLock::new(), syncs: Arc::default() }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Resolves once every record appended to the WAL before the call is
+    /// fsynced. Must be called from within a tokio runtime.
+    pub async fn commit(&self) -> anyhow::Result<()> {
+        let tx = self.tx.get_or_init(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(run(self.wal.clone(), self.window, rx, self.syncs.clone()));
+            tx
+        });
+        let (done, wait) = oneshot::channel();
+        tx.send(done).map_err(|_| anyhow!("WAL commit task stopped"))?;
+        wait.await.map_err(|_| anyhow!("WAL commit task stopped"))?.map_err(|err| anyhow!(err))
+    }
+
+    /// Number of fsyncs issued so far.
+    pub fn syncs(&self) -> u64 {
+        self.syncs.load(Ordering::Relaxed)
+    }
+}
+
+async fn run(wal: Wal, window: Duration, mut rx: mpsc::UnboundedReceiver<Waiter>, syncs: Arc<AtomicU64>) {
+    while let Some(first) = rx.recv().await {
+        let mut waiters = vec![first];
+        let deadline = Instant::now() + window;
+        // Waiters already queued are taken even when the window is zero.
+        while let Ok(Some(waiter)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            waiters.push(waiter);
+        }
+
+        let flushed = {
+            let wal = wal.clone();
+            tokio::task::spawn_blocking(move || wal.flush()).await
+        };
+        syncs.fetch_add(1, Ordering::Relaxed);
+        let result = match flushed {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(err)) => Err(format!("WAL fsync failed: {err:#}")),
+            Err(err) => Err(format!("WAL fsync task failed: {err}")),
+        };
+        if let Err(err) = &result {
+            tracing::error!(waiters = waiters.len(), %err, "group commit failed");
+        }
+        for waiter in waiters {
+            let _ = waiter.send(result.clone());
+        }
+    }
+}
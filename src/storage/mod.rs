@@ -1 +1,3 @@
+pub mod group_commit;
+pub mod snapshot;
 pub mod wal;
@@ -0,0 +1,163 @@
+//! On-disk snapshot format: a fixed header followed by the catalog as
+//! JSON-lines `WalRecord`s.
+//!
+//! | magic (8) | version (u32 LE) | body length (u64 LE) | CRC-32 of seq and body (u32 LE) | WAL seq (u64 LE) | body |
+//!
+//! The WAL seq is the number of the first log record the snapshot does not
+//! contain; replay skips everything before it. Version 1 files have no seq
+//! field (and a 24-byte header) and still load, as covering nothing.
+//!
+//! Bump `VERSION` whenever the body layout changes so older files are
+//! rejected instead of misread.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::storage::wal::WalRecord;
+
+pub const MAGIC: [u8; 8] = *b"VRAFTSNP";
+pub const VERSION: u32 = 2;
+pub const HEADER_LEN: usize = 32;
+
+// Header length of version 1 files, which lack the WAL seq.
+const V1_HEADER_LEN: usize = 24;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("not a snapshot file (bad magic)")]
+    BadMagic,
+    #[error("snapshot format version {found} is not supported (expected {expected})")]
+    UnsupportedVersion { found: u32, expected: u32 },
+    #[error("snapshot truncated: header declares {expected} body bytes, found {found}")]
+    Truncated { expected: u64, found: u64 },
+    #[error("snapshot checksum mismatch: header says {expected:#010x}, body hashes to {actual:#010x}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+    #[error("malformed snapshot record: {0}")]
+    Record(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Serializes records into a complete snapshot file image covering no WAL
+/// records.
+pub fn encode(records: &[WalRecord]) -> serde_json::Result<Vec<u8>> {
+    encode_covering(records, 0)
+}
+
+/// Serializes records into a snapshot image that contains every WAL record
+/// numbered below `wal_seq`.
+pub fn encode_covering(records: &[WalRecord], wal_seq: u64) -> serde_json::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    for rec in records {
+        serde_json::to_writer(&mut body, rec)?;
+        body.push(b'\n');
+    }
+    let seq = wal_seq.to_le_bytes();
+    let mut out = Vec::with_capacity(HEADER_LEN + body.len());
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    out.extend_from_slice(&crc32_parts(&[&seq, &body]).to_le_bytes());
+    out.extend_from_slice(&seq);
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+/// Validates the header and checksum, then parses the body.
+pub fn decode(bytes: &[u8]) -> Result<Vec<WalRecord>, SnapshotError> {
+    decode_covering(bytes).map(|(records, _)| records)
+}
+
+/// Like [`decode`], also returning the WAL seq the snapshot covers.
+pub fn decode_covering(bytes: &[u8]) -> Result<(Vec<WalRecord>, u64), SnapshotError> {
+    if bytes.len() < V1_HEADER_LEN || bytes[..8] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let word = |range: std::ops::Range<usize>| -> [u8; 4] { bytes[range].try_into().expect("4 bytes") };
+    let version = u32::from_le_bytes(word(8..12));
+    let header_len = match version {
+        VERSION if bytes.len() >= HEADER_LEN => HEADER_LEN,
+        VERSION => return Err(SnapshotError::BadMagic),
+        1 => V1_HEADER_LEN,
+        _ => return Err(SnapshotError::UnsupportedVersion { found: version, expected: VERSION }),
+    };
+    let body_len = u64::from_le_bytes(bytes[12..20].try_into().expect("8 bytes"));
+    let expected_crc = u32::from_le_bytes(word(20..24));
+    let seq = &bytes[V1_HEADER_LEN..header_len];
+    let body = &bytes[header_len..];
+    if body.len() as u64 != body_len {
+        return Err(SnapshotError::Truncated { expected: body_len, found: body.len() as u64 });
+    }
+    let actual_crc = crc32_parts(&[seq, body]);
+    if actual_crc != expected_crc {
+        return Err(SnapshotError::ChecksumMismatch { expected: expected_crc, actual: actual_crc });
+    }
+    let wal_seq = if seq.is_empty() { 0 } else { u64::from_le_bytes(seq.try_into().expect("8 bytes")) };
+    let records = body
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).map_err(SnapshotError::from))
+        .collect::<Result<_, _>>()?;
+    Ok((records, wal_seq))
+}
+
+pub fn read(path: &Path) -> Result<Vec<WalRecord>, SnapshotError> {
+    read_covering(path).map(|(records, _)| records)
+}
+
+/// Like [`read`], also returning the WAL seq the snapshot covers.
+pub fn read_covering(path: &Path) -> Result<(Vec<WalRecord>, u64), SnapshotError> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    decode_covering(&bytes)
+}
+
+/// Writes the snapshot to a temp file and renames it over `path`, so a crash
+/// mid-write leaves the previous snapshot intact.
+pub fn write(path: &Path, records: &[WalRecord]) -> anyhow::Result<()> {
+    write_covering(path, records, 0)
+}
+
+/// Like [`write`], recording that the snapshot contains every WAL record
+/// numbered below `wal_seq`.
+pub fn write_covering(path: &Path, records: &[WalRecord], wal_seq: u64) -> anyhow::Result<()> {
+    let image = encode_covering(records, wal_seq)?;
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp = PathBuf::from(tmp_name);
+    let mut f = OpenOptions::new().create(true).write(true).truncate(true).open(&tmp)?;
+    f.write_all(&image)?;
+    f.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// CRC-32 (IEEE 802.3, reflected, as used by zlib and gzip).
+pub fn crc32(bytes: &[u8]) -> u32 {
+    crc32_parts(&[bytes])
+}
+
+// CRC-32 of the concatenation of `parts`.
+fn crc32_parts(parts: &[&[u8]]) -> u32 {
+    !parts.iter().flat_map(|part| part.iter()).fold(!0u32, |crc, &b| CRC_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
@@ -1,12 +1,36 @@
 use std::{
     fs::{OpenOptions, File},
-    io::{BufRead, BufReader, Write},
-    path::PathBuf,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use parking_lot::Mutex;
 use serde::{Serialize, Deserialize};
 use anyhow::Result;
+use tracing::{info, warn};
+
+/// Last line of a fully written compaction temp file. A temp file without it
+/// was interrupted mid-write and is discarded on open.
+const COMPACTION_TRAILER: &str = "#vectaraft-compaction-complete";
+
+/// First line of a log that compaction has cut, followed by the sequence
+/// number of the log's first record. Logs without it start at 0.
+const BASE_PREFIX: &str = "#vectaraft-wal-base ";
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A point in the log: `seq` numbers records from the first ever appended,
+/// across compactions, and `offset` is the byte position in the current file
+/// (buffered records included) where record `seq` starts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WalPosition {
+    pub seq: u64,
+    pub offset: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum WalRecord {
     Upsert {
@@ -14,35 +38,363 @@ pub enum WalRecord {
         id: String,
         vector: Vec<f32>,
         payload_json: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at_ms: Option<i64>,
         ts_ms: i64,
     },
     CreateCollection {
         name: String,
         dim: u32,
         metric: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parallel_threshold: Option<u64>,
+        /// `VectorLayout` name; absent means row-major.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        layout: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        assume_normalized: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default_top_k: Option<u32>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        default_with_payloads: bool,
+        /// `PayloadStore` name; absent means inline.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload_store: Option<String>,
+        /// `IdKind` name; absent means string.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id_type: Option<String>,
+        ts_ms: i64,
+    },
+    /// The collection's settings after the change, not just the fields the
+    /// request touched.
+    SetCollectionConfig {
+        collection: String,
+        parallel_threshold: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default_top_k: Option<u32>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        default_with_payloads: bool,
+        ts_ms: i64,
+    },
+    SetMetric {
+        collection: String,
+        metric: String,
+        ts_ms: i64,
+    },
+    Truncate {
+        collection: String,
+        ts_ms: i64,
+    },
+    Delete {
+        collection: String,
+        ids: Vec<String>,
+        ts_ms: i64,
+    },
+    SwapCollections {
+        target: String,
+        source: String,
+        ts_ms: i64,
+    },
+    DropCollection {
+        collection: String,
         ts_ms: i64,
+    },
+}
+
+impl WalRecord {
+    /// Variant name, matching the serialized `type` tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Upsert { .. } => "Upsert",
+            Self::CreateCollection { .. } => "CreateCollection",
+            Self::SetCollectionConfig { .. } => "SetCollectionConfig",
+            Self::SetMetric { .. } => "SetMetric",
+            Self::Truncate { .. } => "Truncate",
+            Self::Delete { .. } => "Delete",
+            Self::SwapCollections { .. } => "SwapCollections",
+            Self::DropCollection { .. } => "DropCollection",
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct Wal {
     path: PathBuf,
+    // Encoded records not yet written to the file. Its lock also serializes
+    // appends with rewrites so no record lands in a file that is about to be
+    // replaced.
+    pending: Arc<Mutex<Vec<u8>>>,
+    // Bytes appended but not yet written: the pending buffer plus records
+    // still waiting for its lock. Readable without taking the lock, so it
+    // stays cheap to check while a slow write holds it.
+    backlog: Arc<AtomicUsize>,
+    // Unix milliseconds at which the oldest record in `pending` was buffered;
+    // 0 while it is empty. Updated under the `pending` lock.
+    oldest_pending_ms: Arc<AtomicU64>,
+    // Records in the log, buffered ones included. Starts at 0 and is set by
+    // the startup `replay`; otherwise changed under the `pending` lock.
+    records: Arc<AtomicUsize>,
+    // Records in `pending`. Changed under the `pending` lock.
+    pending_records: Arc<AtomicUsize>,
+    // Sequence number of the file's first record, from its base line. Set
+    // by `replay` and changed under the `pending` lock.
+    base_seq: Arc<AtomicU64>,
+    buffer_bytes: usize,
+    flush_every: usize,
 }
 
 impl Wal {
     pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
         let path = path.into();
         if let Some(parent) = path.parent() { std::fs::create_dir_all(parent)?; }
+        recover_compaction(&path)?;
         OpenOptions::new().create(true).append(true).open(&path)?; // ensure exists
-        Ok(Self { path })
+        Ok(Self {
+            path,
+            pending: Arc::default(),
+            backlog: Arc::default(),
+            oldest_pending_ms: Arc::default(),
+            records: Arc::default(),
+            pending_records: Arc::default(),
+            base_seq: Arc::default(),
+            buffer_bytes: 0,
+            flush_every: 0,
+        })
+    }
+
+    /// Opens an existing log for replay without creating, repairing, or
+    /// otherwise modifying anything on disk.
+    pub fn open_read_only(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        File::open(&path)?;
+        Ok(Self {
+            path,
+            pending: Arc::default(),
+            backlog: Arc::default(),
+            oldest_pending_ms: Arc::default(),
+            records: Arc::default(),
+            pending_records: Arc::default(),
+            base_seq: Arc::default(),
+            buffer_bytes: 0,
+            flush_every: 0,
+        })
+    }
+
+    /// Holds appended records in memory until `bytes` have accumulated or
+    /// `flush` is called. Buffered records are lost if the process dies
+    /// before they are written; `0` (the default) writes every append.
+    pub fn with_buffer(mut self, bytes: usize) -> Self {
+        self.buffer_bytes = bytes;
+        self
+    }
+
+    /// Holds appended records in memory until `records` of them have
+    /// accumulated, then writes and fsyncs them as one group, so a crash
+    /// loses at most the unwritten partial group. `flush` writes a partial
+    /// group early. A `with_buffer` size, if also set, still writes a group
+    /// early once it is exceeded. `0` (the default) turns this off.
+    pub fn with_flush_every(mut self, records: usize) -> Self {
+        self.flush_every = records;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Atomically replaces the log with `records`. The new log is written to
+    /// `<path>.tmp` with a trailer, synced, then renamed over the live file, so
+    /// a crash at any point leaves either the old or the new log intact.
+    pub fn rewrite(&self, records: &[WalRecord]) -> Result<()> {
+        let mut pending = self.pending.lock();
+        self.write_pending(&mut pending)?;
+        let base = self.base_seq.load(Ordering::Acquire);
+        self.replace_with(|f| {
+            write_base_line(f, base)?;
+            for rec in records {
+                f.write_all(serde_json::to_string(rec)?.as_bytes())?;
+                f.write_all(b"\n")?;
+            }
+            Ok(())
+        })?;
+        self.records.store(records.len(), Ordering::Release);
+        Ok(())
+    }
+
+    /// Drops the first `offset` bytes of the log (records already captured by
+    /// a snapshot) and keeps everything appended after them. The kept log
+    /// records its base sequence number, so record numbering survives the
+    /// cut. Returns the number of bytes reclaimed.
+    pub fn compact_before(&self, offset: u64) -> Result<u64> {
+        let mut pending = self.pending.lock();
+        self.write_pending(&mut pending)?;
+        let mut src = File::open(&self.path)?;
+        let offset = offset.min(src.metadata()?.len());
+        src.seek(SeekFrom::Start(offset))?;
+        let mut tail = Vec::new();
+        src.read_to_end(&mut tail)?;
+        let kept = tail.iter().filter(|b| **b == b'\n').count();
+        let dropped = self.records.load(Ordering::Acquire).saturating_sub(kept);
+        let base = self.base_seq.load(Ordering::Acquire) + dropped as u64;
+        self.replace_with(|f| {
+            write_base_line(f, base)?;
+            Ok(f.write_all(&tail)?)
+        })?;
+        self.records.store(kept, Ordering::Release);
+        self.base_seq.store(base, Ordering::Release);
+        Ok(offset)
+    }
+
+    /// Where the next appended record will go. Taken under the buffer lock,
+    /// so no append is half counted.
+    pub fn position(&self) -> Result<WalPosition> {
+        let pending = self.pending.lock();
+        let seq = self.base_seq.load(Ordering::Acquire) + self.records.load(Ordering::Acquire) as u64;
+        let offset = std::fs::metadata(&self.path)?.len() + pending.len() as u64;
+        Ok(WalPosition { seq, offset })
+    }
+
+    /// Sequence number of the first record in the file. Only known once
+    /// `replay` has run.
+    pub fn base_seq(&self) -> u64 {
+        self.base_seq.load(Ordering::Acquire)
+    }
+
+    /// Records in the log, including buffered ones. Only counts records
+    /// found on disk once `replay` has run.
+    pub fn record_count(&self) -> usize {
+        self.records.load(Ordering::Acquire)
+    }
+
+    /// Current size of the log file in bytes, excluding buffered records.
+    pub fn size_bytes(&self) -> Result<u64> {
+        Ok(std::fs::metadata(&self.path)?.len())
+    }
+
+    fn replace_with(&self, write_body: impl FnOnce(&mut File) -> Result<()>) -> Result<()> {
+        let tmp = temp_path(&self.path);
+        let mut f = File::create(&tmp)?;
+        write_body(&mut f)?;
+        let body_len = f.metadata()?.len();
+        f.write_all(COMPACTION_TRAILER.as_bytes())?;
+        f.write_all(b"\n")?;
+        f.sync_all()?;
+        finalize_temp(&tmp, &self.path, body_len)
     }
 
     pub fn append(&self, rec: &WalRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(rec)?;
+        line.push(b'\n');
+        self.backlog.fetch_add(line.len(), Ordering::AcqRel);
+        let mut pending = self.pending.lock();
+        if pending.is_empty() {
+            self.oldest_pending_ms.store(now_ms().max(1), Ordering::Release);
+        }
+        pending.extend_from_slice(&line);
+        self.records.fetch_add(1, Ordering::AcqRel);
+        self.pending_records.fetch_add(1, Ordering::AcqRel);
+        if self.write_due(&pending) {
+            self.write_pending(&mut pending)?;
+        }
+        Ok(())
+    }
+
+    /// Appends `records` as one write: either all of them reach the log (or
+    /// the buffer) or, on error, none do.
+    pub fn append_all(&self, records: &[WalRecord]) -> Result<()> {
+        let mut lines = Vec::new();
+        for rec in records {
+            serde_json::to_writer(&mut lines, rec)?;
+            lines.push(b'\n');
+        }
+        self.backlog.fetch_add(lines.len(), Ordering::AcqRel);
+        let mut pending = self.pending.lock();
+        let before = pending.len();
+        if before == 0 {
+            self.oldest_pending_ms.store(now_ms().max(1), Ordering::Release);
+        }
+        pending.extend_from_slice(&lines);
+        self.records.fetch_add(records.len(), Ordering::AcqRel);
+        self.pending_records.fetch_add(records.len(), Ordering::AcqRel);
+        if self.write_due(&pending) {
+            if let Err(err) = self.write_pending(&mut pending) {
+                // Take this batch back out; earlier buffered records stay.
+                pending.truncate(before);
+                if before == 0 {
+                    self.oldest_pending_ms.store(0, Ordering::Release);
+                }
+                self.backlog.fetch_sub(lines.len(), Ordering::AcqRel);
+                self.records.fetch_sub(records.len(), Ordering::AcqRel);
+                self.pending_records.fetch_sub(records.len(), Ordering::AcqRel);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes any buffered records and fsyncs the log. Everything appended
+    /// before this returns is durable. Returns the number of bytes that were
+    /// still buffered.
+    pub fn flush(&self) -> Result<usize> {
+        let mut pending = self.pending.lock();
+        let buffered = pending.len();
+        self.write_pending(&mut pending)?;
+        OpenOptions::new().append(true).open(&self.path)?.sync_all()?;
+        Ok(buffered)
+    }
+
+    /// Number of bytes appended but not yet written to the file.
+    pub fn buffered_bytes(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// How long the oldest buffered record has waited to be written; `None`
+    /// when the buffer is empty. Never waits for the buffer lock.
+    pub fn oldest_unflushed_age(&self) -> Option<Duration> {
+        match self.oldest_pending_ms.load(Ordering::Acquire) {
+            0 => None,
+            since => Some(Duration::from_millis(now_ms().saturating_sub(since))),
+        }
+    }
+
+    /// Like `buffered_bytes`, but also counts appends blocked behind an
+    /// in-progress write, and never waits for the buffer lock. Grows without
+    /// bound while writes keep failing.
+    pub fn backlog_bytes(&self) -> usize {
+        self.backlog.load(Ordering::Acquire)
+    }
+
+    /// Whether the buffered records should be written now.
+    fn write_due(&self, pending: &[u8]) -> bool {
+        match self.flush_every {
+            0 => pending.len() > self.buffer_bytes,
+            group => {
+                self.pending_records.load(Ordering::Acquire) >= group
+                    || (self.buffer_bytes > 0 && pending.len() > self.buffer_bytes)
+            }
+        }
+    }
+
+    fn write_pending(&self, pending: &mut Vec<u8>) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
         let mut f = OpenOptions::new().append(true).open(&self.path)?;
-        let line = serde_json::to_string(rec)?;
-        f.write_all(line.as_bytes())?;
-        f.write_all(b"\n")?;
-        f.flush()?;
+        let len = f.metadata()?.len();
+        // Groups are fsynced as they are written, so none is half durable.
+        let written = f
+            .write_all(pending)
+            .and_then(|_| f.flush())
+            .and_then(|_| if self.flush_every > 0 { f.sync_data() } else { Ok(()) });
+        if let Err(err) = written {
+            // Cut off a partly written tail so the log never ends mid-record.
+            let _ = f.set_len(len);
+            return Err(err.into());
+        }
+        self.backlog.fetch_sub(pending.len(), Ordering::AcqRel);
+        pending.clear();
+        self.pending_records.store(0, Ordering::Release);
+        self.oldest_pending_ms.store(0, Ordering::Release);
         Ok(())
     }
 
@@ -50,12 +402,65 @@ impl Wal {
         let f = File::open(&self.path)?;
         let reader = BufReader::new(f);
         let mut out = Vec::new();
+        let mut base = 0;
         for line in reader.lines() {
             let line = line?;
-            if line.trim().is_empty() { continue; }
+            if let Some(seq) = line.strip_prefix(BASE_PREFIX) {
+                base = seq.trim().parse()?;
+                continue;
+            }
+            if line.trim().is_empty() || line == COMPACTION_TRAILER { continue; }
             let rec: WalRecord = serde_json::from_str(&line)?;
             out.push(rec);
         }
+        self.records.store(out.len(), Ordering::Release);
+        self.base_seq.store(base, Ordering::Release);
         Ok(out)
     }
 }
+
+/// Writes the base line of a cut log; nothing for a log starting at 0.
+fn write_base_line(f: &mut File, base: u64) -> Result<()> {
+    if base > 0 {
+        f.write_all(format!("{BASE_PREFIX}{base}\n").as_bytes())?;
+    }
+    Ok(())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+/// Strips the trailer from a complete temp file and renames it over `path`.
+fn finalize_temp(tmp: &Path, path: &Path, body_len: u64) -> Result<()> {
+    let f = OpenOptions::new().write(true).open(tmp)?;
+    f.set_len(body_len)?;
+    f.sync_all()?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// Resolves a temp file left behind by an interrupted `rewrite`: finalize it
+/// if its trailer made it to disk, otherwise discard it and keep the old log.
+fn recover_compaction(path: &Path) -> Result<()> {
+    let tmp = temp_path(path);
+    if !tmp.exists() {
+        return Ok(());
+    }
+    let contents = std::fs::read_to_string(&tmp).unwrap_or_default();
+    let trailer = format!("{COMPACTION_TRAILER}\n");
+    if let Some(body) = contents.strip_suffix(&trailer) {
+        finalize_temp(&tmp, path, body.len() as u64)?;
+        info!(path = %path.display(), "finalized WAL compaction interrupted before rename");
+    } else {
+        std::fs::remove_file(&tmp)?;
+        warn!(path = %tmp.display(), "discarded incomplete WAL compaction temp file");
+    }
+    Ok(())
+}
@@ -1,25 +1,126 @@
-use std::{net::SocketAddr, sync::Arc};
+pub mod openmetrics;
+pub mod otel;
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Router};
-use prometheus::{Encoder, Opts, Registry, TextEncoder, CounterVec, Gauge};
+use std::{collections::BTreeMap, net::SocketAddr, sync::{Arc, OnceLock}, time::Duration};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use prometheus::{exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec, Opts, Registry, TextEncoder, Counter, CounterVec, Gauge, GaugeVec};
 use tokio::task::JoinHandle;
 
+use crate::catalog::Catalog;
+use crate::server::state::Readiness;
+use crate::storage::wal::Wal;
+
+/// Latency buckets (seconds) used when no valid custom buckets are configured.
+pub const DEFAULT_LATENCY_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// Route that serves the Prometheus exposition unless configured otherwise.
+pub const DEFAULT_METRICS_PATH: &str = "/metrics";
+
+/// Upper bounds for `query_hits`, covering the usual top_k values so a
+/// result shortfall lands below the bucket of the requested k.
+pub const QUERY_HITS_BUCKETS: &[f64] = &[0.0, 1.0, 5.0, 10.0, 20.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
 #[derive(Clone)]
 pub struct Metrics {
     registry: Registry,
     grpc_requests_total: CounterVec,
+    grpc_errors_total: CounterVec,
+    grpc_request_duration_seconds: HistogramVec,
+    search_candidates_evaluated_total: CounterVec,
+    search_candidates_rejected_total: CounterVec,
+    collection_queries_total: CounterVec,
+    collection_upserts_total: CounterVec,
+    points_upserted_total: Counter,
+    points_deleted_total: Counter,
+    point_payload_bytes: Histogram,
     collections_total: Gauge,
     points_total: Gauge,
+    index_len: GaugeVec,
+    index_capacity: GaugeVec,
+    query_hits: Histogram,
+    wal_replayed_records_total: CounterVec,
+    wal_oldest_unflushed_seconds: Gauge,
+    // Sampled for `wal_oldest_unflushed_seconds` on every scrape.
+    wal: OnceLock<Wal>,
 }
 
 impl Metrics {
-    pub fn new() -> anyhow::Result<Arc<Self>> {
-        let registry = Registry::new();
+    /// Builds the registry. `latency_buckets` must be positive and strictly
+    /// increasing; otherwise `DEFAULT_LATENCY_BUCKETS` is used.
+    pub fn new(latency_buckets: &[f64]) -> anyhow::Result<Arc<Self>> {
+        Self::with_namespace(latency_buckets, "")
+    }
+
+    /// Like `new`, but every metric name is prefixed with `namespace_` (e.g.
+    /// `vectaraft_grpc_requests_total`) so it cannot collide with other
+    /// exporters scraped by the same Prometheus. An empty namespace adds no
+    /// prefix; one that is not a valid metric name is an error.
+    pub fn with_namespace(latency_buckets: &[f64], namespace: &str) -> anyhow::Result<Arc<Self>> {
+        let registry = if namespace.is_empty() {
+            Registry::new()
+        } else {
+            let valid = namespace.chars().enumerate().all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+            anyhow::ensure!(valid, "invalid metrics namespace {namespace:?}: use letters, digits and underscores");
+            Registry::new_custom(Some(namespace.to_string()), None)?
+        };
 
         let grpc_requests_total = CounterVec::new(
             Opts::new("grpc_requests_total", "Total gRPC requests handled"),
             &["method", "status"],
         )?;
+        let grpc_errors_total = CounterVec::new(
+            Opts::new("grpc_errors_total", "gRPC requests that returned a non-OK status, by canonical code"),
+            &["method", "code"],
+        )?;
+        let grpc_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "grpc_request_duration_seconds",
+                "gRPC request latency in seconds",
+            )
+            .buckets(validated_buckets(latency_buckets)),
+            &["method"],
+        )?;
+        let search_candidates_evaluated_total = CounterVec::new(
+            Opts::new("search_candidates_evaluated_total", "Points considered by search scans"),
+            &["collection"],
+        )?;
+        let search_candidates_rejected_total = CounterVec::new(
+            Opts::new(
+                "search_candidates_rejected_total",
+                "Points dropped by payload filters or excluded ids before scoring",
+            ),
+            &["collection"],
+        )?;
+        let collection_queries_total = CounterVec::new(
+            Opts::new("collection_queries_total", "Searches served by Query and QueryStream, per collection"),
+            &["collection"],
+        )?;
+        let collection_upserts_total = CounterVec::new(
+            Opts::new("collection_upserts_total", "Upsert batches applied, per collection"),
+            &["collection"],
+        )?;
+        let points_upserted_total = Counter::with_opts(Opts::new(
+            "points_upserted_total",
+            "Points written by Upsert and UpsertStream",
+        ))?;
+        let points_deleted_total = Counter::with_opts(Opts::new(
+            "points_deleted_total",
+            "Points removed from collections by truncation or deletion",
+        ))?;
+        // 16 B .. 4 MiB in powers of four.
+        let point_payload_bytes = Histogram::with_opts(
+            HistogramOpts::new("point_payload_bytes", "Size of payload_json per upserted point in bytes")
+                .buckets(exponential_buckets(16.0, 4.0, 10)?),
+        )?;
         let collections_total = Gauge::with_opts(Opts::new(
             "collections_total",
             "Number of collections currently registered",
@@ -28,16 +129,66 @@ impl Metrics {
             "points_total",
             "Number of points stored across all collections",
         ))?;
+        let index_len = GaugeVec::new(
+            Opts::new("index_len", "Points stored in a collection's index"),
+            &["collection"],
+        )?;
+        let index_capacity = GaugeVec::new(
+            Opts::new("index_capacity", "Points a collection's index can hold without reallocating"),
+            &["collection"],
+        )?;
+        let query_hits = Histogram::with_opts(
+            HistogramOpts::new("query_hits", "Hits returned per Query; compare with requested top_k")
+                .buckets(QUERY_HITS_BUCKETS.to_vec()),
+        )?;
+        let wal_replayed_records_total = CounterVec::new(
+            Opts::new("wal_replayed_records_total", "WAL records applied at startup, by record type"),
+            &["type"],
+        )?;
+
+        let wal_oldest_unflushed_seconds = Gauge::with_opts(Opts::new(
+            "wal_oldest_unflushed_seconds",
+            "Age of the oldest WAL record still buffered in memory; 0 when the buffer is empty",
+        ))?;
 
         registry.register(Box::new(grpc_requests_total.clone()))?;
+        registry.register(Box::new(grpc_errors_total.clone()))?;
+        registry.register(Box::new(grpc_request_duration_seconds.clone()))?;
+        registry.register(Box::new(search_candidates_evaluated_total.clone()))?;
+        registry.register(Box::new(search_candidates_rejected_total.clone()))?;
+        registry.register(Box::new(collection_queries_total.clone()))?;
+        registry.register(Box::new(collection_upserts_total.clone()))?;
+        registry.register(Box::new(points_upserted_total.clone()))?;
+        registry.register(Box::new(points_deleted_total.clone()))?;
+        registry.register(Box::new(point_payload_bytes.clone()))?;
         registry.register(Box::new(collections_total.clone()))?;
         registry.register(Box::new(points_total.clone()))?;
+        registry.register(Box::new(index_len.clone()))?;
+        registry.register(Box::new(index_capacity.clone()))?;
+        registry.register(Box::new(query_hits.clone()))?;
+        registry.register(Box::new(wal_replayed_records_total.clone()))?;
+        registry.register(Box::new(wal_oldest_unflushed_seconds.clone()))?;
 
         Ok(Arc::new(Self {
             registry,
             grpc_requests_total,
+            grpc_errors_total,
+            grpc_request_duration_seconds,
+            search_candidates_evaluated_total,
+            search_candidates_rejected_total,
+            collection_queries_total,
+            collection_upserts_total,
+            points_upserted_total,
+            points_deleted_total,
+            point_payload_bytes,
             collections_total,
             points_total,
+            index_len,
+            index_capacity,
+            query_hits,
+            wal_replayed_records_total,
+            wal_oldest_unflushed_seconds,
+            wal: OnceLock::new(),
         }))
     }
 
@@ -47,6 +198,48 @@ impl Metrics {
             .inc();
     }
 
+    /// `code` is the canonical name, e.g. `NOT_FOUND`.
+    pub fn record_grpc_error(&self, method: &str, code: &str) {
+        self.grpc_errors_total
+            .with_label_values(&[method, code])
+            .inc();
+    }
+
+    pub fn observe_latency(&self, method: &str, elapsed: Duration) {
+        self.grpc_request_duration_seconds
+            .with_label_values(&[method])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_search_candidates(&self, collection: &str, evaluated: usize, rejected: usize) {
+        self.search_candidates_evaluated_total
+            .with_label_values(&[collection])
+            .inc_by(evaluated as f64);
+        self.search_candidates_rejected_total
+            .with_label_values(&[collection])
+            .inc_by(rejected as f64);
+    }
+
+    pub fn record_collection_query(&self, collection: &str) {
+        self.collection_queries_total.with_label_values(&[collection]).inc();
+    }
+
+    pub fn record_collection_upsert(&self, collection: &str) {
+        self.collection_upserts_total.with_label_values(&[collection]).inc();
+    }
+
+    pub fn record_points_upserted(&self, count: usize) {
+        self.points_upserted_total.inc_by(count as f64);
+    }
+
+    pub fn observe_payload_bytes(&self, bytes: usize) {
+        self.point_payload_bytes.observe(bytes as f64);
+    }
+
+    pub fn record_points_deleted(&self, count: usize) {
+        self.points_deleted_total.inc_by(count as f64);
+    }
+
     pub fn set_collection_count(&self, value: usize) {
         self.collections_total.set(value as f64);
     }
@@ -55,41 +248,159 @@ impl Metrics {
         self.points_total.set(value as f64);
     }
 
-    fn router(self: Arc<Self>) -> Router {
-        Router::new()
-            .route("/metrics", get(metrics_handler))
-            .with_state(self)
+    pub fn observe_query_hits(&self, hits: usize) {
+        self.query_hits.observe(hits as f64);
     }
+
+    /// Records the per-type tallies of a finished WAL replay.
+    pub fn record_wal_replay(&self, counts: &BTreeMap<&'static str, u64>) {
+        for (kind, count) in counts {
+            self.wal_replayed_records_total.with_label_values(&[kind]).inc_by(*count as f64);
+        }
+    }
+
+    /// Samples `wal`'s buffer age on every `encode`. Only the first call
+    /// has an effect.
+    pub fn track_wal(&self, wal: Wal) {
+        let _ = self.wal.set(wal);
+    }
+
+    pub fn set_wal_oldest_unflushed(&self, age: Option<Duration>) {
+        self.wal_oldest_unflushed_seconds.set(age.map_or(0.0, |age| age.as_secs_f64()));
+    }
+
+    pub fn set_index_size(&self, collection: &str, len: usize, capacity: usize) {
+        self.index_len.with_label_values(&[collection]).set(len as f64);
+        self.index_capacity.with_label_values(&[collection]).set(capacity as f64);
+    }
+
+    /// Sets the collection, point and index-size gauges from one pass over
+    /// `catalog`.
+    pub fn refresh_inventory(&self, catalog: &Catalog) {
+        let sizes = catalog.index_sizes();
+        self.set_collection_count(sizes.len());
+        self.set_point_count(sizes.iter().map(|(_, len, _)| len).sum());
+        for (name, len, capacity) in &sizes {
+            self.set_index_size(name, *len, *capacity);
+        }
+    }
+
+    /// Drops the per-collection series of a collection that no longer exists.
+    pub fn forget_collection(&self, collection: &str) {
+        let _ = self.index_len.remove_label_values(&[collection]);
+        let _ = self.index_capacity.remove_label_values(&[collection]);
+        let _ = self.collection_queries_total.remove_label_values(&[collection]);
+        let _ = self.collection_upserts_total.remove_label_values(&[collection]);
+    }
+
+    /// Renders all registered metrics in the Prometheus text format.
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let encoder = TextEncoder::new();
+        let metric_families = self.gather();
+        let mut buffer = Vec::new();
+        encoder.encode(&metric_families, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    /// Renders all registered metrics in the OpenMetrics text format, ending
+    /// with `# EOF`.
+    pub fn encode_openmetrics(&self) -> String {
+        openmetrics::encode(&self.gather())
+    }
+
+    fn gather(&self) -> Vec<prometheus::proto::MetricFamily> {
+        if let Some(wal) = self.wal.get() {
+            self.set_wal_oldest_unflushed(wal.oldest_unflushed_age());
+        }
+        self.registry.gather()
+    }
+
+}
+
+#[derive(Clone)]
+struct TelemetryState {
+    metrics: Arc<Metrics>,
+    readiness: Readiness,
+}
+
+fn router(state: TelemetryState, metrics_path: &str) -> Router {
+    Router::new()
+        .route(&validated_path(metrics_path), get(metrics_handler))
+        .route("/livez", get(livez_handler))
+        .route("/readyz", get(readyz_handler))
+        .with_state(state)
+}
+
+/// `path` if it is a plain absolute route that does not shadow the probe
+/// endpoints; otherwise `DEFAULT_METRICS_PATH`.
+fn validated_path(path: &str) -> String {
+    let plain = path.starts_with('/') && path.len() > 1 && !path.contains([':', '*', '{', '}', ' ']);
+    if plain && path != "/livez" && path != "/readyz" {
+        return path.to_string();
+    }
+    tracing::warn!(path, "metrics path must be an absolute route other than /livez and /readyz; using {DEFAULT_METRICS_PATH}");
+    DEFAULT_METRICS_PATH.to_string()
 }
 
-async fn metrics_handler(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
-    let encoder = TextEncoder::new();
-    let metric_families = metrics.registry.gather();
-    let mut buffer = Vec::new();
-    if let Err(err) = encoder.encode(&metric_families, &mut buffer) {
-        tracing::error!(?err, "failed to encode metrics");
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+fn validated_buckets(buckets: &[f64]) -> Vec<f64> {
+    let positive = buckets.iter().all(|b| b.is_finite() && *b > 0.0);
+    let sorted = buckets.windows(2).all(|w| w[0] < w[1]);
+    if !buckets.is_empty() && positive && sorted {
+        return buckets.to_vec();
     }
-    match String::from_utf8(buffer) {
+    if !buckets.is_empty() {
+        tracing::warn!(?buckets, "latency buckets must be positive and strictly increasing; using defaults");
+    }
+    DEFAULT_LATENCY_BUCKETS.to_vec()
+}
+
+/// Serves OpenMetrics to clients whose `Accept` header asks for it and the
+/// Prometheus text format to everyone else.
+async fn metrics_handler(State(state): State<TelemetryState>, headers: HeaderMap) -> impl IntoResponse {
+    let wants_openmetrics = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.contains("application/openmetrics-text"));
+    if wants_openmetrics {
+        let body = state.metrics.encode_openmetrics();
+        return (StatusCode::OK, [(header::CONTENT_TYPE, openmetrics::CONTENT_TYPE)], body).into_response();
+    }
+    match state.metrics.encode() {
         Ok(body) => (StatusCode::OK, body).into_response(),
         Err(err) => {
-            tracing::error!(?err, "failed to convert metrics to UTF-8");
+            tracing::error!(?err, "failed to encode metrics");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
-pub async fn serve(metrics: Arc<Metrics>, addr: SocketAddr) -> anyhow::Result<()> {
-    let router = metrics.clone().router();
+async fn livez_handler() -> StatusCode {
+    StatusCode::OK
+}
+
+async fn readyz_handler(State(state): State<TelemetryState>) -> StatusCode {
+    if state.readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Serves the metrics exposition at `metrics_path` plus the `/livez` and
+/// `/readyz` probes.
+pub async fn serve(metrics: Arc<Metrics>, readiness: Readiness, addr: SocketAddr, metrics_path: &str) -> anyhow::Result<()> {
+    let router = router(TelemetryState { metrics, readiness }, metrics_path);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    tracing::info!("metrics server listening on {}", addr);
+    tracing::info!(path = metrics_path, "metrics server listening on {}", addr);
     axum::serve(listener, router.into_make_service()).await?;
     Ok(())
 }
 
-pub fn spawn(metrics: Arc<Metrics>, addr: SocketAddr) -> JoinHandle<()> {
+pub fn spawn(metrics: Arc<Metrics>, readiness: Readiness, addr: SocketAddr, metrics_path: &str) -> JoinHandle<()> {
+    let metrics_path = metrics_path.to_string();
     tokio::spawn(async move {
-        if let Err(err) = serve(metrics, addr).await {
+        if let Err(err) = serve(metrics, readiness, addr, &metrics_path).await {
             tracing::error!(?err, "metrics server stopped");
         }
     })
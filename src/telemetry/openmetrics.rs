@@ -0,0 +1,110 @@
+//! OpenMetrics 1.0 text exposition for the families gathered from a
+//! `prometheus::Registry`. The prometheus crate only ships the classic text
+//! encoder, whose output differs in counter naming, escaping and the
+//! mandatory `# EOF` terminator.
+
+use std::fmt::Write;
+
+use prometheus::proto::{LabelPair, Metric, MetricFamily, MetricType};
+
+/// `Content-Type` of an OpenMetrics exposition.
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Renders `families` in the OpenMetrics text format. Counter families are
+/// named without their `_total` suffix, which every counter sample carries.
+pub fn encode(families: &[MetricFamily]) -> String {
+    let mut out = String::new();
+    for family in families {
+        let name = family.get_name();
+        let kind = family.get_field_type();
+        let base = match kind {
+            MetricType::COUNTER => name.strip_suffix("_total").unwrap_or(name),
+            _ => name,
+        };
+        let type_name = match kind {
+            MetricType::COUNTER => "counter",
+            MetricType::GAUGE => "gauge",
+            MetricType::HISTOGRAM => "histogram",
+            MetricType::SUMMARY => "summary",
+            MetricType::UNTYPED => "unknown",
+        };
+        let _ = writeln!(out, "# TYPE {base} {type_name}");
+        if !family.get_help().is_empty() {
+            let _ = writeln!(out, "# HELP {base} {}", escape(family.get_help()));
+        }
+        for metric in family.get_metric() {
+            match kind {
+                MetricType::COUNTER => {
+                    sample(&mut out, &format!("{base}_total"), metric, None, metric.get_counter().get_value())
+                }
+                MetricType::GAUGE => sample(&mut out, base, metric, None, metric.get_gauge().get_value()),
+                MetricType::HISTOGRAM => {
+                    let h = metric.get_histogram();
+                    let bucket = format!("{base}_bucket");
+                    let mut inf_seen = false;
+                    for b in h.get_bucket() {
+                        let bound = b.get_upper_bound();
+                        inf_seen |= bound == f64::INFINITY;
+                        let le = float(bound);
+                        sample(&mut out, &bucket, metric, Some(("le", &le)), b.get_cumulative_count() as f64);
+                    }
+                    if !inf_seen {
+                        sample(&mut out, &bucket, metric, Some(("le", "+Inf")), h.get_sample_count() as f64);
+                    }
+                    sample(&mut out, &format!("{base}_count"), metric, None, h.get_sample_count() as f64);
+                    sample(&mut out, &format!("{base}_sum"), metric, None, h.get_sample_sum());
+                }
+                MetricType::SUMMARY => {
+                    let s = metric.get_summary();
+                    for q in s.get_quantile() {
+                        let quantile = float(q.get_quantile());
+                        sample(&mut out, base, metric, Some(("quantile", &quantile)), q.get_value());
+                    }
+                    sample(&mut out, &format!("{base}_count"), metric, None, s.get_sample_count() as f64);
+                    sample(&mut out, &format!("{base}_sum"), metric, None, s.get_sample_sum());
+                }
+                MetricType::UNTYPED => sample(&mut out, base, metric, None, metric.get_untyped().get_value()),
+            }
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+fn sample(out: &mut String, name: &str, metric: &Metric, extra: Option<(&str, &str)>, value: f64) {
+    out.push_str(name);
+    labels(out, metric.get_label(), extra);
+    let _ = write!(out, " {}", float(value));
+    // OpenMetrics timestamps are in seconds.
+    if metric.get_timestamp_ms() != 0 {
+        let _ = write!(out, " {}", float(metric.get_timestamp_ms() as f64 / 1000.0));
+    }
+    out.push('\n');
+}
+
+fn labels(out: &mut String, pairs: &[LabelPair], extra: Option<(&str, &str)>) {
+    let mut separator = '{';
+    for (name, value) in pairs.iter().map(|p| (p.get_name(), p.get_value())).chain(extra) {
+        out.push(separator);
+        let _ = write!(out, "{name}=\"{}\"", escape(value));
+        separator = ',';
+    }
+    if separator == ',' {
+        out.push('}');
+    }
+}
+
+/// OpenMetrics numbers: integral values keep a `.0` so `le` and `quantile`
+/// labels are canonical, and infinities are spelled `+Inf`/`-Inf`.
+fn float(v: f64) -> String {
+    match v {
+        f64::INFINITY => "+Inf".into(),
+        f64::NEG_INFINITY => "-Inf".into(),
+        v if v.is_nan() => "NaN".into(),
+        v => format!("{v:?}"),
+    }
+}
+
+fn escape(v: &str) -> String {
+    v.replace('\\', r"\\").replace('\n', r"\n").replace('"', "\\\"")
+}
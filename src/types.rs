@@ -5,14 +5,286 @@ pub enum Metric {
     L2,
     Cosine,
     IP,
+    /// L-infinity: negated maximum absolute per-dimension difference.
+    Chebyshev,
+    /// Intersection over union of the nonzero components; 0 when both are all-zero.
+    Jaccard,
 }
 
 impl Metric {
+    /// Lenient parse used for WAL replay: unrecognized names fall back to L2.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
+        Self::parse(s).unwrap_or(Self::L2)
+    }
+
+    /// Strict parse of a metric name or alias; `None` if unrecognized.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "l2" | "euclidean" => Some(Self::L2),
+            "cosine" => Some(Self::Cosine),
+            "ip" | "inner_product" => Some(Self::IP),
+            "chebyshev" | "linf" => Some(Self::Chebyshev),
+            "jaccard" => Some(Self::Jaccard),
+            _ => None,
+        }
+    }
+
+    /// Whether scores are negated distances (lower raw value = closer)
+    /// rather than similarities.
+    pub fn is_distance(&self) -> bool {
+        matches!(self, Self::L2 | Self::Chebyshev)
+    }
+
+    /// Maps a higher-is-better search score onto [0, 1], preserving order:
+    /// - cosine: `(s + 1) / 2`
+    /// - l2, chebyshev: `1 / (1 + d)` where `d = -s` is the squared
+    ///   (respectively max-absolute) distance
+    /// - ip: logistic `1 / (1 + e^-s)`, since dot products are unbounded;
+    ///   it saturates, so hits with large dot products may report equal scores
+    /// - jaccard: unchanged
+    ///
+    /// Scores pushed outside the metric's natural range (e.g. by query
+    /// boosts) are clamped to [0, 1].
+    pub fn normalize_score(&self, score: f32) -> f32 {
+        let normalized = match self {
+            Self::Cosine => (score + 1.0) / 2.0,
+            Self::L2 | Self::Chebyshev => 1.0 / (1.0 - score.min(0.0)),
+            Self::IP => 1.0 / (1.0 + (-score).exp()),
+            Self::Jaccard => score,
+        };
+        normalized.clamp(0.0, 1.0)
+    }
+
+    /// Maps a higher-is-better search score back onto the metric's distance,
+    /// 0 for identical vectors, reversing the order:
+    /// - cosine, jaccard: `1 - s`, so orthogonal (or disjoint) vectors are at 1
+    /// - l2: `sqrt(-s)`, the true rather than squared distance
+    /// - chebyshev: `-s`
+    ///
+    /// `None` for ip, whose dot products have no distance counterpart.
+    /// Scores pushed past the identical-vector score (e.g. by query boosts)
+    /// report a distance of 0 for l2 and chebyshev.
+    pub fn distance(&self, score: f32) -> Option<f32> {
+        match self {
+            Self::Cosine | Self::Jaccard => Some(1.0 - score),
+            Self::L2 => Some((-score).max(0.0).sqrt()),
+            Self::Chebyshev => Some((-score).max(0.0)),
+            Self::IP => None,
+        }
+    }
+
+    /// Per-dimension terms of the raw value scoring `v` against `q`:
+    /// `(q[i] - v[i])^2` for l2, summing to the squared distance, and
+    /// `q[i] * v[i]` for ip, summing to the dot product. `None` for metrics
+    /// that do not decompose into a sum over dimensions.
+    pub fn contributions(&self, q: &[f32], v: &[f32]) -> Option<Vec<f32>> {
+        match self {
+            Self::L2 => Some(q.iter().zip(v).map(|(a, b)| (a - b) * (a - b)).collect()),
+            Self::IP => Some(q.iter().zip(v).map(|(a, b)| a * b).collect()),
+            Self::Cosine | Self::Chebyshev | Self::Jaccard => None,
+        }
+    }
+
+    /// Canonical name, accepted by `parse`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::L2 => "l2",
+            Self::Cosine => "cosine",
+            Self::IP => "ip",
+            Self::Chebyshev => "chebyshev",
+            Self::Jaccard => "jaccard",
+        }
+    }
+}
+
+/// How a flat index arranges vectors in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VectorLayout {
+    /// `[v0[0..dim], v1[0..dim], ...]`; rows are contiguous slices.
+    #[default]
+    RowMajor,
+    /// Groups of `BLOCK_ROWS` vectors stored dimension-major, so scoring a
+    /// block streams each dimension for all of its rows in one contiguous load.
+    Blocked,
+}
+
+impl VectorLayout {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "row_major" | "rows" => Some(Self::RowMajor),
+            "blocked" => Some(Self::Blocked),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RowMajor => "row_major",
+            Self::Blocked => "blocked",
+        }
+    }
+}
+
+/// What a search does with an empty query vector.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyQuery {
+    /// Treat it as a dimension mismatch: `CollectionHandle::search` returns
+    /// `None` and the Query RPCs fail with `INVALID_ARGUMENT`.
+    #[default]
+    Reject,
+    /// Answer with no hits, as if nothing matched.
+    NoHits,
+}
+
+impl EmptyQuery {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "reject" => Some(Self::Reject),
+            "empty" | "no_hits" => Some(Self::NoHits),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reject => "reject",
+            Self::NoHits => "empty",
+        }
+    }
+}
+
+/// Width of the accumulators that sum per-dimension terms when scoring.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScorePrecision {
+    /// Sum in `f32`.
+    #[default]
+    F32,
+    /// Sum L2, inner-product and cosine terms in `f64` and round to `f32`
+    /// once at the end. Slower, but high-dimensional vectors with large
+    /// components no longer lose small terms to rounding, which can misorder
+    /// close results. Chebyshev and Jaccard are exact either way.
+    F64,
+}
+
+impl ScorePrecision {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "f32" => Some(Self::F32),
+            "f64" => Some(Self::F64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        }
+    }
+}
+
+/// What cosine does with zero-norm vectors, whose angle is undefined.
+/// Other metrics score them like any vector.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ZeroNorm {
+    /// Score them as 0, as if orthogonal to everything.
+    #[default]
+    Zero,
+    /// Refuse zero vectors upserted into a cosine collection. Points already
+    /// stored (e.g. replayed from the WAL) are kept and score as 0.
+    Reject,
+    /// Score them as NaN, i.e. leave them out of cosine results; a zero
+    /// query vector matches nothing.
+    Exclude,
+}
+
+impl ZeroNorm {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "zero" => Some(Self::Zero),
+            "reject" => Some(Self::Reject),
+            "exclude" | "nan" => Some(Self::Exclude),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Zero => "zero",
+            Self::Reject => "reject",
+            Self::Exclude => "exclude",
+        }
+    }
+}
+
+/// Whether `vector` has no direction: its squared norm is 0, computed as
+/// cosine scoring computes it.
+pub fn is_zero_norm(vector: &[f32]) -> bool {
+    vector.iter().map(|x| x * x).sum::<f32>() == 0.0
+}
+
+/// How a flat index stores point ids.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdKind {
+    /// Arbitrary strings.
+    #[default]
+    String,
+    /// Decimal `u64`s, stored as integers. Ids are canonicalized, so "007"
+    /// and "7" name the same point and read back as "7".
+    U64,
+}
+
+impl IdKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "string" => Some(Self::String),
+            "u64" => Some(Self::U64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::U64 => "u64",
+        }
+    }
+
+    /// Whether `id` can be stored under this kind.
+    pub fn accepts(&self, id: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::U64 => id.parse::<u64>().is_ok(),
+        }
+    }
+}
+
+/// Where a flat index keeps point payloads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PayloadStore {
+    /// One payload per row, stored alongside the vectors and moved with them.
+    #[default]
+    Inline,
+    /// One payload per id in a map of its own; rows look theirs up by id, so
+    /// deletes and compaction only move vectors. Rows sharing an id share the
+    /// payload written last.
+    ById,
+}
+
+impl PayloadStore {
+    pub fn parse(s: &str) -> Option<Self> {
         match s.to_ascii_lowercase().as_str() {
-            "cosine" => Self::Cosine,
-            "ip" | "inner_product" => Self::IP,
-            _ => Self::L2,
+            "inline" => Some(Self::Inline),
+            "by_id" => Some(Self::ById),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Inline => "inline",
+            Self::ById => "by_id",
         }
     }
 }
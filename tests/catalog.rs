@@ -0,0 +1,377 @@
+use std::borrow::Cow;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use tempfile::tempdir;
+
+use vectaraft::catalog::{Catalog, IdFilter, PointWrite, UpsertError};
+use vectaraft::index::flat::{BatchError, FlatIndex};
+use vectaraft::types::{IdKind, Metric, ScorePrecision, VectorLayout, ZeroNorm};
+
+#[test]
+fn handle_exposes_shape_and_iterates_points() {
+    let catalog = Catalog::default();
+    assert!(catalog.create_collection("embedded".into(), 2, Metric::IP));
+    let handle = catalog.get("embedded").expect("handle");
+
+    handle
+        .upsert_points(vec![
+            PointWrite { id: "x".into(), vector: vec![1.0, 2.0], payload_json: "{}".into(), expires_at_ms: None },
+            PointWrite { id: "y".into(), vector: vec![3.0, 4.0], payload_json: r#"{"k":1}"#.into(), expires_at_ms: None },
+        ])
+        .expect("upsert");
+
+    assert_eq!(handle.len(), Some(2));
+    assert_eq!(handle.dim(), Some(2));
+    assert_eq!(handle.metric(), Some(Metric::IP));
+
+    let view = handle.iter_points().expect("view");
+    let points: Vec<_> = view.iter().collect();
+    assert_eq!(
+        points,
+        vec![
+            (Cow::Borrowed("x"), Cow::Borrowed(&[1.0, 2.0][..]), "{}"),
+            (Cow::Borrowed("y"), Cow::Borrowed(&[3.0, 4.0][..]), r#"{"k":1}"#),
+        ]
+    );
+}
+
+#[test]
+fn collection_parallel_threshold_overrides_catalog_default() {
+    let catalog = Catalog::with_parallel_threshold(usize::MAX);
+    catalog.create_collection("small".into(), 1, Metric::L2);
+    let handle = catalog.get("small").expect("handle");
+    handle
+        .upsert_points((0..10).map(|i| PointWrite { id: format!("p{i}"), vector: vec![i as f32], payload_json: String::new(), expires_at_ms: None }).collect())
+        .expect("upsert");
+    assert_eq!(handle.with_ref(|c| c.runs_parallel()), Some(false));
+
+    handle.with_mut(|c| c.parallel_threshold = Some(0));
+    assert_eq!(handle.with_ref(|c| c.runs_parallel()), Some(true));
+
+    let parallel = Catalog::with_parallel_threshold(0);
+    parallel.create_collection("tiny".into(), 1, Metric::L2);
+    let tiny = parallel.get("tiny").expect("handle");
+    tiny.with_mut(|c| c.parallel_threshold = Some(1_000_000));
+    assert_eq!(tiny.with_ref(|c| c.runs_parallel()), Some(false));
+    tiny.with_mut(|c| c.parallel_threshold = None);
+    assert_eq!(tiny.with_ref(|c| c.runs_parallel()), Some(true));
+
+    // Both scan paths rank identically.
+    let serial_hits = handle.with_mut(|c| {
+        c.parallel_threshold = Some(usize::MAX);
+        c.search(&[3.2], 3, None, None, &IdFilter::default(), &[], None, None).0
+    });
+    let parallel_hits = handle.with_mut(|c| {
+        c.parallel_threshold = Some(0);
+        c.search(&[3.2], 3, None, None, &IdFilter::default(), &[], None, None).0
+    });
+    assert_eq!(serial_hits, parallel_hits);
+}
+
+#[test]
+fn blocked_layout_matches_row_major_results() {
+    // 37 points of dim 5: neither is a multiple of the block size.
+    let points: Vec<PointWrite> = (0..37)
+        .map(|i| PointWrite {
+            id: format!("p{i}"),
+            vector: (0..5).map(|d| (((i * 7 + d * 3) % 11) as f32 - 5.0) * if d == 2 { 0.0 } else { 1.0 }).collect(),
+            payload_json: format!(r#"{{"even":"{}","slot":{i}}}"#, i % 2 == 0),
+            expires_at_ms: None,
+        })
+        .collect();
+    let catalog = Catalog::default();
+    for name in ["rows", "blocked"] {
+        catalog.create_collection(name.into(), 5, Metric::L2);
+        catalog.get(name).expect("handle").upsert_points(points.clone()).expect("upsert");
+    }
+    let rows = catalog.get("rows").expect("handle");
+    let blocked = catalog.get("blocked").expect("handle");
+    blocked.with_mut(|c| c.index.set_layout(VectorLayout::Blocked));
+
+    let compare = |query: &[f32], filters: Vec<(String, String)>| {
+        for metric in [Metric::L2, Metric::IP, Metric::Cosine, Metric::Chebyshev, Metric::Jaccard] {
+            for parallel in [Some(0), Some(usize::MAX)] {
+                rows.with_mut(|c| c.parallel_threshold = parallel);
+                blocked.with_mut(|c| c.parallel_threshold = parallel);
+                let search = |h: &vectaraft::catalog::CollectionHandle| {
+                    h.search(query.to_vec(), 10, Some(metric), filters.clone(), IdFilter::default(), None, vec![], None, None)
+                        .expect("search")
+                        .0
+                };
+                assert_eq!(search(&rows), search(&blocked), "{metric:?}");
+            }
+        }
+    };
+    compare(&[1.0, -2.0, 0.0, 3.0, 0.5], vec![]);
+    compare(&[0.0, 0.0, 0.0, 0.0, 0.0], vec![("even".into(), "true".into())]);
+    // A single admitted candidate takes the row-by-row path.
+    compare(&[1.0, 1.0, 1.0, 1.0, 1.0], vec![("slot".into(), "5".into())]);
+
+    // Removal compacts blocked storage without disturbing the survivors.
+    let doomed: Vec<String> = (0..37).filter(|i| i % 3 == 0).map(|i| format!("p{i}")).collect();
+    rows.with_mut(|c| c.remove_points(&doomed));
+    blocked.with_mut(|c| c.remove_points(&doomed));
+    compare(&[-1.0, 2.0, 1.0, 0.0, 4.0], vec![]);
+    let rows_view = rows.iter_points().expect("view");
+    let blocked_view = blocked.iter_points().expect("view");
+    assert!(rows_view.iter().eq(blocked_view.iter()));
+}
+
+#[test]
+fn writes_to_different_collections_do_not_serialize() {
+    let catalog = Catalog::default();
+    for name in ["a", "b"] {
+        catalog.create_collection(name.into(), 2, Metric::L2);
+    }
+    let point = |id: &str| PointWrite { id: id.into(), vector: vec![1.0, 2.0], payload_json: String::new(), expires_at_ms: None };
+
+    // Park a writer inside collection a's write lock.
+    let (entered_tx, entered_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let a = catalog.get("a").expect("a");
+    let a_point = point("a1");
+    let writer = thread::spawn(move || {
+        a.with_mut(|coll| {
+            coll.upsert_batch(vec![a_point]).expect("upsert");
+            entered_tx.send(()).expect("signal");
+            release_rx.recv_timeout(Duration::from_secs(10)).is_ok()
+        })
+    });
+    entered_rx.recv().expect("writer holds a");
+
+    // With one catalog-wide lock this upsert would wait for the writer.
+    let b = catalog.get("b").expect("b");
+    let (done_tx, done_rx) = mpsc::channel();
+    let b_point = point("b1");
+    thread::spawn(move || done_tx.send(b.upsert_points(vec![b_point])).expect("send"));
+    let upserted = done_rx.recv_timeout(Duration::from_secs(5)).expect("upsert into b blocked behind a's writer");
+    assert_eq!(upserted, Ok(1));
+
+    release_tx.send(()).expect("release");
+    assert_eq!(writer.join().expect("writer"), Some(true));
+    assert_eq!(catalog.total_points(), 2);
+}
+
+#[test]
+fn u64_ids_use_less_memory_than_string_ids() {
+    let catalog = Catalog::default();
+    let points = || -> Vec<PointWrite> {
+        (0..10_000u64)
+            .map(|i| PointWrite {
+                id: (1_000_000_000 + i).to_string(),
+                vector: vec![i as f32],
+                payload_json: String::new(),
+                expires_at_ms: None,
+            })
+            .collect()
+    };
+    let mut bytes = Vec::new();
+    for (name, kind) in [("strings", IdKind::String), ("ints", IdKind::U64)] {
+        catalog.create_collection(name.into(), 1, Metric::L2);
+        let handle = catalog.get(name).expect("handle");
+        handle.with_mut(|c| c.index.set_id_kind(kind)).expect("collection").expect("empty index");
+        handle.upsert_points(points()).expect("upsert");
+        bytes.push(handle.with_ref(|c| c.index.id_heap_bytes()).expect("collection"));
+    }
+    let (strings, ints) = (bytes[0], bytes[1]);
+    println!("id storage for 10k points: string ids {strings} bytes, u64 ids {ints} bytes");
+    assert!(ints * 3 < strings * 2, "u64 ids took {ints} bytes vs {strings} for strings");
+
+    // Both read back the same ids and answer lookups the same way.
+    let ids = |name: &str| -> Vec<String> {
+        catalog.get(name).expect("handle").iter_points().expect("view").iter().map(|(id, _, _)| id.into_owned()).collect()
+    };
+    assert_eq!(ids("strings"), ids("ints"));
+    let ints = catalog.get("ints").expect("handle");
+    assert_eq!(ints.with_ref(|c| c.index.positions("1000000007").to_vec()), Some(vec![7]));
+    assert_eq!(
+        ints.upsert_points(vec![PointWrite { id: "p1".into(), vector: vec![1.0], payload_json: String::new(), expires_at_ms: None }]),
+        Err(UpsertError::Batch(BatchError::InvalidId("p1".into())))
+    );
+}
+
+#[test]
+fn malformed_batches_are_rejected_without_writing() {
+    let mut index = FlatIndex::new(2, Metric::L2);
+    let err = index
+        .add_batch(vec!["a".into(), "b".into()], vec![vec![1.0, 2.0]], vec![String::new(); 2], vec![None; 2])
+        .expect_err("short vector column");
+    assert_eq!(err, BatchError::LengthMismatch { ids: 2, vectors: 1, payloads: 2, expiries: 2 });
+    let err = index
+        .add_batch(vec!["a".into(), "b".into()], vec![vec![1.0, 2.0], vec![3.0]], vec![String::new(); 2], vec![None; 2])
+        .expect_err("wrong dimension");
+    assert_eq!(err, BatchError::Dimension { position: 1, expected: 2, actual: 1 });
+    assert_eq!(index.len(), 0);
+
+    let catalog = Catalog::default();
+    catalog.create_collection("c".into(), 2, Metric::L2);
+    let handle = catalog.get("c").expect("handle");
+    let point = |id: &str, vector: Vec<f32>| PointWrite { id: id.into(), vector, payload_json: String::new(), expires_at_ms: None };
+    let result = handle.upsert_points(vec![point("ok", vec![1.0, 2.0]), point("bad", vec![1.0, 2.0, 3.0])]);
+    assert!(matches!(result, Err(UpsertError::Batch(BatchError::Dimension { position: 1, .. }))));
+    assert_eq!(handle.len(), Some(0));
+}
+
+#[test]
+fn f64_score_precision_keeps_small_terms_of_large_vectors() {
+    // In f32, 1e8 + 1 rounds back to 1e8, so "cancels" scores 0 instead of 1
+    // and ranks below "half".
+    let points = || {
+        vec![
+            PointWrite { id: "cancels".into(), vector: vec![1e8, 1.0, -1e8], payload_json: String::new(), expires_at_ms: None },
+            PointWrite { id: "half".into(), vector: vec![0.5, 0.0, 0.0], payload_json: String::new(), expires_at_ms: None },
+        ]
+    };
+    let ranked = |precision: ScorePrecision, layout: VectorLayout| {
+        let catalog = Catalog::default().with_score_precision(precision);
+        assert!(catalog.create_collection("big".into(), 3, Metric::IP));
+        let handle = catalog.get("big").expect("handle");
+        handle.with_mut(|c| c.index.set_layout(layout)).expect("layout");
+        handle.upsert_points(points()).expect("upsert");
+        let (hits, _) = handle
+            .search(vec![1.0, 1.0, 1.0], 2, None, vec![], IdFilter::default(), None, vec![], None, None)
+            .expect("search");
+        hits.into_iter().map(|(id, score, _)| (id, score)).collect::<Vec<_>>()
+    };
+
+    for layout in [VectorLayout::RowMajor, VectorLayout::Blocked] {
+        assert_eq!(
+            ranked(ScorePrecision::F32, layout),
+            vec![("half".to_string(), 0.5), ("cancels".to_string(), 0.0)]
+        );
+        assert_eq!(
+            ranked(ScorePrecision::F64, layout),
+            vec![("cancels".to_string(), 1.0), ("half".to_string(), 0.5)]
+        );
+    }
+}
+
+#[test]
+fn least_recently_used_collections_are_evicted_and_reloaded() {
+    let points = || {
+        (0..64)
+            .map(|i| PointWrite {
+                id: format!("p{i}"),
+                vector: vec![i as f32, 1.0, 0.0, 0.0],
+                payload_json: format!(r#"{{"i":{i}}}"#),
+                expires_at_ms: None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let fill = |catalog: &Catalog, name: &str| {
+        assert!(catalog.create_collection(name.into(), 4, Metric::L2));
+        catalog.get(name).expect("handle").upsert_points(points()).expect("upsert");
+    };
+    let probe = Catalog::default();
+    fill(&probe, "probe");
+    let one = probe.resident_bytes();
+
+    // Room for two of the three collections.
+    let spill = tempdir().expect("tempdir");
+    let catalog = Catalog::default().with_memory_budget(2 * one + one / 2, spill.path()).expect("budget");
+    for name in ["a", "b", "c"] {
+        fill(&catalog, name);
+    }
+    let top = |name: &str| {
+        let (hits, _) = catalog
+            .get(name)
+            .expect("handle")
+            .search(vec![10.0, 1.0, 0.0, 0.0], 2, None, vec![], IdFilter::default(), None, vec![], None, None)
+            .expect("search");
+        hits.into_iter().map(|(id, _, payload)| (id, payload)).collect::<Vec<_>>()
+    };
+    let expected = vec![("p10".to_string(), r#"{"i":10}"#.to_string()), ("p9".to_string(), r#"{"i":9}"#.to_string())];
+    assert_eq!(top("a"), expected);
+    assert_eq!(top("b"), expected);
+    assert_eq!(top("c"), expected);
+    assert_eq!(catalog.is_resident("a"), Some(false));
+    assert_eq!(catalog.is_resident("b"), Some(true));
+    assert!(catalog.resident_bytes() <= catalog.memory_budget().expect("budget"));
+    assert_eq!(catalog.total_points(), 3 * 64);
+
+    // A query loads "a" back transparently, pushing out "b", now the least
+    // recently used.
+    assert_eq!(top("a"), expected);
+    assert_eq!(catalog.is_resident("a"), Some(true));
+    assert_eq!(catalog.is_resident("b"), Some(false));
+    assert_eq!(catalog.is_resident("c"), Some(true));
+
+    // Writes to an evicted collection land, and survive its next eviction.
+    let b = catalog.get("b").expect("handle");
+    b.upsert_points(vec![PointWrite { id: "new".into(), vector: vec![10.0, 1.0, 0.0, 0.0], payload_json: "{}".into(), expires_at_ms: None }])
+        .expect("upsert");
+    assert_eq!(catalog.is_resident("c"), Some(false));
+    top("c");
+    top("a");
+    assert_eq!(catalog.is_resident("b"), Some(false));
+    assert_eq!(b.len(), Some(65));
+    assert!(b.iter_points().expect("view").iter().any(|(id, _, _)| id == "new"));
+
+    assert!(catalog.drop_collection("b"));
+    catalog.clear();
+    assert_eq!(std::fs::read_dir(spill.path()).expect("spill dir").count(), 0);
+}
+
+#[test]
+fn pinned_collections_are_never_evicted() {
+    let spill = tempdir().expect("tempdir");
+    let catalog = Catalog::default().with_memory_budget(1, spill.path()).expect("budget");
+    catalog.pin("hot");
+    for name in ["hot", "cold", "warm"] {
+        assert!(catalog.create_collection(name.into(), 2, Metric::L2));
+        let handle = catalog.get(name).expect("handle");
+        handle
+            .upsert_points(vec![PointWrite { id: "x".into(), vector: vec![1.0, 2.0], payload_json: "{}".into(), expires_at_ms: None }])
+            .expect("upsert");
+    }
+    // Only the most recently used unpinned collection may stay over budget.
+    assert_eq!(catalog.is_resident("hot"), Some(true));
+    assert_eq!(catalog.is_resident("cold"), Some(false));
+    assert_eq!(catalog.is_resident("warm"), Some(true));
+    assert_eq!(catalog.get("cold").expect("handle").len(), Some(1));
+    assert_eq!(catalog.is_resident("hot"), Some(true));
+    assert_eq!(catalog.is_resident("warm"), Some(false));
+}
+
+#[test]
+fn zero_norm_policy_governs_zero_vectors_under_cosine() {
+    let point = |id: &str, vector: Vec<f32>| PointWrite { id: id.into(), vector, payload_json: String::new(), expires_at_ms: None };
+    let setup = |policy: ZeroNorm, metric: Metric| {
+        let catalog = Catalog::default().with_zero_norm(policy);
+        assert!(catalog.create_collection("c".into(), 2, metric));
+        let handle = catalog.get("c").expect("handle");
+        let upserted = handle.upsert_points(vec![point("unit", vec![1.0, 0.0]), point("zero", vec![0.0, 0.0])]);
+        (handle, upserted)
+    };
+    let hits = |handle: &vectaraft::catalog::CollectionHandle, query: Vec<f32>| {
+        let (hits, _) = handle.search(query, 10, None, vec![], IdFilter::default(), None, vec![], None, None).expect("search");
+        hits.into_iter().map(|(id, score, _)| (id, score)).collect::<Vec<_>>()
+    };
+
+    // Default: zero vectors are stored and score 0.
+    let (handle, upserted) = setup(ZeroNorm::Zero, Metric::Cosine);
+    assert_eq!(upserted, Ok(2));
+    assert_eq!(hits(&handle, vec![1.0, 0.0]), vec![("unit".to_string(), 1.0), ("zero".to_string(), 0.0)]);
+    let zero_query = hits(&handle, vec![0.0, 0.0]);
+    assert_eq!(zero_query.len(), 2);
+    assert!(zero_query.iter().all(|(_, score)| *score == 0.0));
+
+    // Reject: the batch is refused whole, and only under cosine.
+    let (handle, upserted) = setup(ZeroNorm::Reject, Metric::Cosine);
+    assert!(matches!(upserted, Err(UpsertError::Batch(BatchError::ZeroNorm { position: 1 }))));
+    assert_eq!(handle.len(), Some(0));
+    assert_eq!(setup(ZeroNorm::Reject, Metric::L2).1, Ok(2));
+
+    // Exclude: zero vectors never match under cosine, but other metrics
+    // still score them.
+    let (handle, upserted) = setup(ZeroNorm::Exclude, Metric::Cosine);
+    assert_eq!(upserted, Ok(2));
+    assert_eq!(hits(&handle, vec![1.0, 0.0]), vec![("unit".to_string(), 1.0)]);
+    assert_eq!(hits(&handle, vec![0.0, 0.0]), vec![]);
+    let (l2_hits, _) = handle
+        .search(vec![0.0, 0.0], 10, Some(Metric::L2), vec![], IdFilter::default(), None, vec![], None, None)
+        .expect("search");
+    assert_eq!(l2_hits.len(), 2);
+}
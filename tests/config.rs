@@ -0,0 +1,38 @@
+use tempfile::tempdir;
+
+use vectaraft::server::state::{DbState, DbStateConfig};
+
+#[test]
+fn data_dir_derives_persistence_paths() {
+    let tmp = tempdir().expect("tempdir");
+    let root = tmp.path().join("nested").join("data");
+
+    let mut config = DbStateConfig {
+        enable_wal: true,
+        ..DbStateConfig::default()
+    };
+    config.apply_data_dir(&root).expect("apply data dir");
+
+    assert!(root.is_dir());
+    assert_eq!(config.data_dir.as_deref(), Some(root.as_path()));
+    assert_eq!(config.wal_path, Some(root.join("wal")));
+    assert_eq!(config.snapshot_path, Some(root.join("snapshot")));
+
+    let state = DbState::with_config(config);
+    assert!(state.wal.is_some());
+    assert!(root.join("wal").is_file());
+}
+
+#[test]
+fn data_dir_leaves_wal_disabled() {
+    let tmp = tempdir().expect("tempdir");
+    let mut config = DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    };
+    config.apply_data_dir(tmp.path()).expect("apply data dir");
+
+    assert_eq!(config.wal_path, None);
+    assert_eq!(config.snapshot_path, Some(tmp.path().join("snapshot")));
+}
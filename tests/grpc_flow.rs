@@ -6,14 +6,37 @@ use tonic::Request;
 
 use vectaraft::pb::vectordb::v1::{
     vector_db_server::VectorDb,
+    CollectionChecksumRequest,
     CreateCollectionRequest,
+    DeleteCollectionsByPrefixRequest,
+    DescribeCollectionRequest,
+    DescribeCollectionResponse,
+    ExportAllRequest,
+    FetchByFilterRequest,
+    FindDuplicatesRequest,
     Filter,
+    FlushRequest,
+    HybridScore,
+    MovePointsRequest,
+    PinCollectionRequest,
     Point,
     QueryRequest,
+    RebuildIndexRequest, ShrinkToFitRequest,
+    ScoreBoost,
+    ScrollRequest,
+    SetCollectionConfigRequest,
+    SetMetricRequest,
+    SwapCollectionsRequest,
+    TruncateCollectionRequest,
     UpsertRequest,
+    WarmupRequest,
 };
+use vectaraft::catalog::IdFilter;
+use vectaraft::ids::IdScheme;
+use vectaraft::server::embedded::{point, EmbeddedClient};
 use vectaraft::server::grpc::VectorDbService;
 use vectaraft::server::state::{DbState, DbStateConfig};
+use vectaraft::types::{EmptyQuery, IdKind, Metric};
 
 fn state_with_temp_wal() -> (Arc<DbState>, std::path::PathBuf, tempfile::TempDir) {
     let tmp = tempdir().expect("tempdir");
@@ -21,6 +44,7 @@ fn state_with_temp_wal() -> (Arc<DbState>, std::path::PathBuf, tempfile::TempDir
     let config = DbStateConfig {
         wal_path: Some(wal_path.clone()),
         enable_wal: true,
+        ..DbStateConfig::default()
     };
     (Arc::new(DbState::with_config(config)), wal_path, tmp)
 }
@@ -29,64 +53,34 @@ fn state_with_temp_wal() -> (Arc<DbState>, std::path::PathBuf, tempfile::TempDir
 #[serial]
 async fn create_upsert_query_roundtrip() {
     let (state, _wal_path, _guard) = state_with_temp_wal();
-    let svc = VectorDbService { state: state.clone(), metrics: None };
-
-    svc.create_collection(Request::new(CreateCollectionRequest {
-        name: "demo".into(),
-        dims: 4,
-        metric: "cosine".into(),
-    }))
-    .await
-    .expect("create collection");
+    let client = EmbeddedClient::new(state);
 
-    let points = vec![
-        Point { id: String::new(), vector: vec![1.0, 0.0, 0.0, 0.0], payload_json: "{\"k\":0}".into() },
-        Point { id: "manual".into(), vector: vec![0.0, 1.0, 0.0, 0.0], payload_json: "{\"k\":1}".into() },
-    ];
+    client.create_collection("demo", 4, "cosine").await.expect("create collection");
 
-    let upserted = svc
-        .upsert(Request::new(UpsertRequest {
-            collection: "demo".into(),
-            points,
-        }))
+    let upserted = client
+        .upsert(
+            "demo",
+            vec![
+                point("", vec![1.0, 0.0, 0.0, 0.0], "{\"k\":0}"),
+                point("manual", vec![0.0, 1.0, 0.0, 0.0], "{\"k\":1}"),
+            ],
+        )
         .await
         .expect("upsert")
-        .into_inner()
         .upserted;
     assert_eq!(upserted, 2);
 
-    let hits = svc
-        .query(Request::new(QueryRequest {
-            collection: "demo".into(),
-            vector: vec![0.9, 0.1, 0.0, 0.0],
-            top_k: 2,
-            metric_override: String::new(),
-            with_payloads: true,
-            filters: vec![],
-        }))
-        .await
-        .expect("query")
-        .into_inner()
-        .hits;
+    let hits = client.query("demo", vec![0.9, 0.1, 0.0, 0.0], 2).await.expect("query");
 
     assert_eq!(hits.len(), 2);
     assert!(hits.iter().any(|h| h.id == "manual"));
     assert!(hits.iter().all(|h| !h.payload_json.is_empty()));
     assert!(hits.iter().all(|h| !h.id.is_empty()));
 
-    let filtered = svc
-        .query(Request::new(QueryRequest {
-            collection: "demo".into(),
-            vector: vec![0.9, 0.1, 0.0, 0.0],
-            top_k: 5,
-            metric_override: String::new(),
-            with_payloads: true,
-            filters: vec![Filter { key: "k".into(), equals: "1".into() }],
-        }))
+    let filtered = client
+        .query_filtered("demo", vec![0.9, 0.1, 0.0, 0.0], 5, &[("k", "1")])
         .await
-        .expect("filtered query")
-        .into_inner()
-        .hits;
+        .expect("filtered query");
 
     assert_eq!(filtered.len(), 1);
     assert_eq!(filtered[0].payload_json, "{\"k\":1}");
@@ -102,6 +96,15 @@ async fn wal_replay_restores_points() {
         name: "demo".into(),
         dims: 3,
         metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
     }))
     .await
     .expect("create collection");
@@ -112,7 +115,9 @@ async fn wal_replay_restores_points() {
             id: "persist".into(),
             vector: vec![1.0, 1.0, 1.0],
             payload_json: "{\"hello\":true}".into(),
+            ttl_ms: 0,
         }],
+        dry_run: false,
     }))
     .await
     .expect("upsert");
@@ -123,6 +128,7 @@ async fn wal_replay_restores_points() {
     let config = DbStateConfig {
         wal_path: Some(wal_path.clone()),
         enable_wal: true,
+        ..DbStateConfig::default()
     };
     let state = Arc::new(DbState::with_config(config));
     // Keep guard alive until end of test.
@@ -133,10 +139,23 @@ async fn wal_replay_restores_points() {
         .query(Request::new(QueryRequest {
             collection: "demo".into(),
             vector: vec![1.0, 1.0, 1.0],
-            top_k: 1,
+            top_k: Some(1),
             metric_override: String::new(),
-            with_payloads: true,
+            with_payloads: Some(true),
             filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
         }))
         .await
         .expect("query after replay")
@@ -155,6 +174,7 @@ async fn operations_work_without_wal() {
     let config = DbStateConfig {
         wal_path: None,
         enable_wal: false,
+        ..DbStateConfig::default()
     };
     let state = Arc::new(DbState::with_config(config));
     assert!(state.wal.is_none());
@@ -165,6 +185,15 @@ async fn operations_work_without_wal() {
         name: "no-wal".into(),
         dims: 2,
         metric: "ip".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
     }))
     .await
     .expect("create collection");
@@ -176,7 +205,9 @@ async fn operations_work_without_wal() {
                 id: String::new(),
                 vector: vec![0.5, 0.5],
                 payload_json: String::new(),
+                ttl_ms: 0,
             }],
+            dry_run: false,
         }))
         .await
         .expect("upsert")
@@ -188,10 +219,23 @@ async fn operations_work_without_wal() {
         .query(Request::new(QueryRequest {
             collection: "no-wal".into(),
             vector: vec![0.5, 0.5],
-            top_k: 1,
+            top_k: Some(1),
             metric_override: String::new(),
-            with_payloads: false,
+            with_payloads: Some(false),
             filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
         }))
         .await
         .expect("query")
@@ -201,3 +245,3289 @@ async fn operations_work_without_wal() {
     assert_eq!(hits.len(), 1);
     assert!(!hits[0].id.is_empty());
 }
+
+#[tokio::test]
+#[serial]
+async fn rebuild_index_preserves_query_results() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "rebuild".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "rebuild".into(),
+        points: (0..10)
+            .map(|i| Point {
+                id: format!("p{i}"),
+                vector: vec![i as f32, 0.0],
+                payload_json: format!("{{\"i\":{i}}}"),
+                ttl_ms: 0,
+            })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let query = || QueryRequest {
+        collection: "rebuild".into(),
+        vector: vec![3.2, 0.0],
+        top_k: Some(4),
+        metric_override: String::new(),
+        with_payloads: Some(true),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+
+    let before = svc.query(Request::new(query())).await.expect("query").into_inner().hits;
+
+    let rebuilt = svc
+        .rebuild_index(Request::new(RebuildIndexRequest { collection: "rebuild".into() }))
+        .await
+        .expect("rebuild")
+        .into_inner();
+    assert_eq!(rebuilt.points, 10);
+
+    let after = svc.query(Request::new(query())).await.expect("query").into_inner().hits;
+    assert_eq!(before, after);
+    assert_eq!(after[0].id, "p3");
+
+    let missing = svc
+        .rebuild_index(Request::new(RebuildIndexRequest { collection: "nope".into() }))
+        .await
+        .expect_err("missing collection");
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+#[serial]
+async fn set_metric_switches_ranking_and_replays() {
+    let (state, wal_path, guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "metric".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "metric".into(),
+        points: vec![
+            // Far away but perfectly aligned with the query direction.
+            Point { id: "aligned".into(), vector: vec![10.0, 0.0], payload_json: String::new(), ttl_ms: 0 },
+            // Close in Euclidean terms but at an angle.
+            Point { id: "near".into(), vector: vec![0.9, 0.3], payload_json: String::new(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let query = || QueryRequest {
+        collection: "metric".into(),
+        vector: vec![1.0, 0.0],
+        top_k: Some(1),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+
+    let hits = svc.query(Request::new(query())).await.expect("l2 query").into_inner().hits;
+    assert_eq!(hits[0].id, "near");
+
+    svc.set_metric(Request::new(SetMetricRequest {
+        collection: "metric".into(),
+        metric: "cosine".into(),
+    }))
+    .await
+    .expect("set metric");
+
+    let hits = svc.query(Request::new(query())).await.expect("cosine query").into_inner().hits;
+    assert_eq!(hits[0].id, "aligned");
+    assert!((hits[0].score - 1.0).abs() < 1e-6);
+
+    drop(svc);
+    drop(state);
+    let _guard = guard;
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: None };
+    let hits = svc.query(Request::new(query())).await.expect("replayed query").into_inner().hits;
+    assert_eq!(hits[0].id, "aligned");
+}
+
+#[tokio::test]
+#[serial]
+async fn dry_run_upsert_validates_without_writing() {
+    let (state, wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "dry".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let wal_len = std::fs::metadata(&wal_path).expect("wal").len();
+
+    let upserted = svc
+        .upsert(Request::new(UpsertRequest {
+            collection: "dry".into(),
+            points: vec![
+                Point { id: "a".into(), vector: vec![1.0, 0.0], payload_json: "{\"ok\":true}".into(), ttl_ms: 0 },
+                Point { id: "b".into(), vector: vec![0.0, 1.0], payload_json: String::new(), ttl_ms: 0 },
+            ],
+            dry_run: true,
+        }))
+        .await
+        .expect("dry run")
+        .into_inner()
+        .upserted;
+    assert_eq!(upserted, 2);
+    assert_eq!(state.catalog.total_points(), 0);
+    assert_eq!(std::fs::metadata(&wal_path).expect("wal").len(), wal_len);
+
+    for (vector, payload) in [
+        (vec![1.0, 0.0, 0.0], String::new()),
+        (vec![f32::NAN, 0.0], String::new()),
+        (vec![1.0, 0.0], "{not json".to_string()),
+    ] {
+        let err = svc
+            .upsert(Request::new(UpsertRequest {
+                collection: "dry".into(),
+                points: vec![Point { id: "bad".into(), vector, payload_json: payload, ttl_ms: 0 }],
+                dry_run: true,
+            }))
+            .await
+            .expect_err("invalid point");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn truncate_keeps_collection_and_replays() {
+    let (state, wal_path, guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "trunc".into(),
+        dims: 2,
+        metric: "ip".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    let upsert = |id: &str| UpsertRequest {
+        collection: "trunc".into(),
+        points: vec![Point { id: id.into(), vector: vec![1.0, 2.0], payload_json: String::new(), ttl_ms: 0 }],
+        dry_run: false,
+    };
+    svc.upsert(Request::new(upsert("old-1"))).await.expect("upsert");
+    svc.upsert(Request::new(upsert("old-2"))).await.expect("upsert");
+
+    let removed = svc
+        .truncate_collection(Request::new(TruncateCollectionRequest { collection: "trunc".into() }))
+        .await
+        .expect("truncate")
+        .into_inner()
+        .removed;
+    assert_eq!(removed, 2);
+    assert_eq!(state.catalog.total_points(), 0);
+    assert!(state.catalog.get("trunc").is_some());
+
+    svc.upsert(Request::new(upsert("new"))).await.expect("upsert after truncate");
+
+    drop(svc);
+    drop(state);
+    let _guard = guard;
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    let hits = svc
+        .query(Request::new(QueryRequest {
+            collection: "trunc".into(),
+            vector: vec![1.0, 2.0],
+            top_k: Some(10),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query after replay")
+        .into_inner()
+        .hits;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].id, "new");
+}
+
+#[tokio::test]
+#[serial]
+async fn move_points_between_collections() {
+    let (state, wal_path, guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    for (name, dims) in [("draft", 2), ("published", 2), ("wide", 3)] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims,
+            metric: "cosine".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+    }
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "draft".into(),
+        points: vec![
+            Point { id: "doc-1".into(), vector: vec![1.0, 0.0], payload_json: "{\"stage\":1}".into(), ttl_ms: 0 },
+            Point { id: "doc-2".into(), vector: vec![0.0, 1.0], payload_json: String::new(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let resp = svc
+        .move_points(Request::new(MovePointsRequest {
+            source: "draft".into(),
+            target: "published".into(),
+            ids: vec!["doc-1".into(), "ghost".into()],
+        }))
+        .await
+        .expect("move")
+        .into_inner();
+    assert_eq!(resp.moved, 1);
+    assert_eq!(resp.missing, 1);
+
+    let err = svc
+        .move_points(Request::new(MovePointsRequest {
+            source: "draft".into(),
+            target: "wide".into(),
+            ids: vec!["doc-2".into()],
+        }))
+        .await
+        .expect_err("dims differ");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let ids_in = |svc: VectorDbService, collection: &'static str| async move {
+        let mut ids: Vec<String> = svc
+            .query(Request::new(QueryRequest {
+                collection: collection.into(),
+                vector: vec![1.0, 1.0],
+                top_k: Some(10),
+                metric_override: String::new(),
+                with_payloads: Some(true),
+                filters: vec![],
+                exclude_ids: vec![],
+                hybrid: None,
+                include_ids: vec![],
+                explain: false,
+                boosts: vec![],
+                normalize_scores: false,
+                centroid_ids: vec![],
+                max_scan: 0,
+                group_by: String::new(),
+                per_group_limit: 0,
+                dedup_by: String::new(),
+                report_distance: false,
+                explain_dimensions: false,
+            }))
+            .await
+            .expect("query")
+            .into_inner()
+            .hits
+            .into_iter()
+            .map(|h| h.id)
+            .collect();
+        ids.sort();
+        ids
+    };
+    assert_eq!(ids_in(svc.clone(), "draft").await, vec!["doc-2"]);
+    assert_eq!(ids_in(svc.clone(), "published").await, vec!["doc-1"]);
+
+    drop(svc);
+    drop(state);
+    let _guard = guard;
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: None };
+    assert_eq!(ids_in(svc.clone(), "draft").await, vec!["doc-2"]);
+    assert_eq!(ids_in(svc, "published").await, vec!["doc-1"]);
+}
+
+#[tokio::test]
+#[serial]
+async fn dimension_mismatch_errors_name_expected_and_actual() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "dims".into(),
+        dims: 4,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    let err = svc
+        .query(Request::new(QueryRequest {
+            collection: "dims".into(),
+            vector: vec![1.0, 2.0, 3.0],
+            top_k: Some(1),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect_err("query dim mismatch");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(err.message().contains("expected 4, got 3"), "{}", err.message());
+
+    let err = svc
+        .upsert(Request::new(UpsertRequest {
+            collection: "dims".into(),
+            points: vec![
+                Point { id: "fine".into(), vector: vec![0.0; 4], payload_json: String::new(), ttl_ms: 0 },
+                Point { id: "too-long".into(), vector: vec![0.0; 5], payload_json: String::new(), ttl_ms: 0 },
+            ],
+            dry_run: false,
+        }))
+        .await
+        .expect_err("upsert dim mismatch");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(err.message().contains("'too-long'"), "{}", err.message());
+    assert!(err.message().contains("expected 4, got 5"), "{}", err.message());
+}
+
+#[tokio::test]
+#[serial]
+async fn scroll_pages_with_and_without_vectors() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "scroll".into(),
+        dims: 3,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let inserted: Vec<Point> = (0..5)
+        .map(|i| Point {
+            id: format!("p{i}"),
+            vector: vec![i as f32, i as f32 * 0.5, -(i as f32)],
+            payload_json: format!("{{\"i\":{i}}}"),
+            ttl_ms: 0,
+        })
+        .collect();
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "scroll".into(),
+        points: inserted.clone(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let scroll = |offset: u64, with_vectors: bool| ScrollRequest {
+        collection: "scroll".into(),
+        offset,
+        limit: 3,
+        with_payloads: true,
+        with_vectors,
+    };
+
+    let first = svc.scroll(Request::new(scroll(0, true))).await.expect("scroll").into_inner();
+    assert!(first.has_more);
+    assert_eq!(first.next_offset, 3);
+    assert_eq!(first.points, inserted[..3].to_vec());
+
+    let second = svc
+        .scroll(Request::new(scroll(first.next_offset, true)))
+        .await
+        .expect("scroll")
+        .into_inner();
+    assert!(!second.has_more);
+    assert_eq!(second.points, inserted[3..].to_vec());
+
+    let bare = svc.scroll(Request::new(scroll(0, false))).await.expect("scroll").into_inner();
+    assert_eq!(bare.points.len(), 3);
+    assert!(bare.points.iter().all(|p| p.vector.is_empty()));
+    assert_eq!(bare.points[1].payload_json, "{\"i\":1}");
+}
+
+#[tokio::test]
+#[serial]
+async fn query_skips_excluded_ids() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "exclude".into(),
+        dims: 1,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "exclude".into(),
+        points: (0..6)
+            .map(|i| Point { id: format!("p{i}"), vector: vec![i as f32], payload_json: String::new(), ttl_ms: 0 })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let hits = svc
+        .query(Request::new(QueryRequest {
+            collection: "exclude".into(),
+            vector: vec![0.0],
+            top_k: Some(3),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec!["p0".into(), "p1".into()],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+
+    let ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(ids, vec!["p2", "p3", "p4"]);
+}
+
+#[tokio::test]
+#[serial]
+async fn chebyshev_ranks_by_largest_coordinate_gap() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "linf".into(),
+        dims: 2,
+        metric: "chebyshev".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "linf".into(),
+        points: vec![
+            // L2^2 = 18, L-inf = 3
+            Point { id: "diagonal".into(), vector: vec![3.0, 3.0], payload_json: String::new(), ttl_ms: 0 },
+            // L2^2 = 16, L-inf = 4
+            Point { id: "axis".into(), vector: vec![0.0, 4.0], payload_json: String::new(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let query = |metric_override: &str| QueryRequest {
+        collection: "linf".into(),
+        vector: vec![0.0, 0.0],
+        top_k: Some(2),
+        metric_override: metric_override.into(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+
+    let chebyshev = svc.query(Request::new(query(""))).await.expect("query").into_inner().hits;
+    assert_eq!(chebyshev[0].id, "diagonal");
+    assert_eq!(chebyshev[0].score, -3.0);
+    assert_eq!(chebyshev[1].score, -4.0);
+
+    let l2 = svc.query(Request::new(query("l2"))).await.expect("query").into_inner().hits;
+    assert_eq!(l2[0].id, "axis");
+
+    let linf = svc.query(Request::new(query("linf"))).await.expect("query").into_inner().hits;
+    assert_eq!(linf[0].id, "diagonal");
+}
+
+#[tokio::test]
+#[serial]
+async fn jaccard_matches_set_oracle() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "sets".into(),
+        dims: 5,
+        metric: "jaccard".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    let sets: Vec<(&str, Vec<f32>)> = vec![
+        ("same", vec![1.0, 1.0, 0.0, 0.0, 1.0]),
+        ("half", vec![1.0, 0.0, 1.0, 0.0, 1.0]),
+        ("disjoint", vec![0.0, 0.0, 1.0, 1.0, 0.0]),
+        ("empty", vec![0.0; 5]),
+    ];
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "sets".into(),
+        points: sets
+            .iter()
+            .map(|(id, v)| Point { id: (*id).into(), vector: v.clone(), payload_json: String::new(), ttl_ms: 0 })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let query = |vector: Vec<f32>| QueryRequest {
+        collection: "sets".into(),
+        vector,
+        top_k: Some(10),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+    let score_of = |hits: &[vectaraft::pb::vectordb::v1::ScoredPoint], id: &str| {
+        hits.iter().find(|h| h.id == id).map(|h| h.score).expect("hit")
+    };
+
+    // query = {0, 1, 4}
+    let hits = svc
+        .query(Request::new(query(vec![1.0, 1.0, 0.0, 0.0, 1.0])))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+    assert_eq!(hits[0].id, "same");
+    assert_eq!(score_of(&hits, "same"), 1.0);
+    assert_eq!(score_of(&hits, "half"), 2.0 / 4.0); // {0,4} / {0,1,2,4}
+    assert_eq!(score_of(&hits, "disjoint"), 0.0);
+    assert_eq!(score_of(&hits, "empty"), 0.0);
+
+    // Two all-zero vectors are defined as similarity 0.
+    let hits = svc
+        .query(Request::new(query(vec![0.0; 5])))
+        .await
+        .expect("zero query")
+        .into_inner()
+        .hits;
+    assert_eq!(score_of(&hits, "empty"), 0.0);
+}
+
+#[tokio::test]
+#[serial]
+async fn hybrid_weight_shifts_ranking_between_metrics() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "hybrid".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "hybrid".into(),
+        points: vec![
+            // Exact L2 match, modest inner product.
+            Point { id: "close".into(), vector: vec![1.0, 0.0], payload_json: String::new(), ttl_ms: 0 },
+            // Far in L2, largest inner product.
+            Point { id: "long".into(), vector: vec![5.0, 0.0], payload_json: String::new(), ttl_ms: 0 },
+            Point { id: "off".into(), vector: vec![0.0, 1.0], payload_json: String::new(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let query = |weight: f32| QueryRequest {
+        collection: "hybrid".into(),
+        vector: vec![1.0, 0.0],
+        top_k: Some(3),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: Some(HybridScore { metric_a: "l2".into(), metric_b: "ip".into(), weight }),
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+
+    let l2_heavy = svc.query(Request::new(query(0.9))).await.expect("query").into_inner().hits;
+    assert_eq!(l2_heavy[0].id, "close");
+    assert!(l2_heavy.iter().all(|h| (0.0..=1.0).contains(&h.score)));
+
+    let ip_heavy = svc.query(Request::new(query(0.1))).await.expect("query").into_inner().hits;
+    assert_eq!(ip_heavy[0].id, "long");
+
+    let err = svc.query(Request::new(query(1.5))).await.expect_err("weight out of range");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+#[serial]
+async fn create_collection_rejects_unknown_metric() {
+    let tmp = tempdir().expect("tempdir");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(tmp.path().join("wal.log")),
+        enable_wal: true,
+        default_metric: Metric::Cosine,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    let create = |name: &str, metric: &str| CreateCollectionRequest {
+        name: name.into(),
+        dims: 2,
+        metric: metric.into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    };
+
+    let err = svc
+        .create_collection(Request::new(create("typo", "cosien")))
+        .await
+        .expect_err("typo metric");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(err.message().contains("cosien"));
+    assert!(state.catalog.get("typo").is_none());
+
+    svc.create_collection(Request::new(create("ok", "cosine"))).await.expect("cosine");
+    svc.create_collection(Request::new(create("alias", "inner_product"))).await.expect("alias");
+    svc.create_collection(Request::new(create("defaulted", ""))).await.expect("default metric");
+
+    let metric_of = |name: &str| state.catalog.get(name).and_then(|h| h.with_ref(|c| c.metric));
+    assert_eq!(metric_of("ok"), Some(Metric::Cosine));
+    assert_eq!(metric_of("alias"), Some(Metric::IP));
+    assert_eq!(metric_of("defaulted"), Some(Metric::Cosine));
+}
+
+#[tokio::test]
+#[serial]
+async fn read_only_mode_rejects_writes_and_serves_reads() {
+    let (state, wal_path, guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "frozen".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "frozen".into(),
+        points: vec![Point { id: "kept".into(), vector: vec![1.0, 1.0], payload_json: String::new(), ttl_ms: 0 }],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+    drop(svc);
+    drop(state);
+
+    let _guard = guard;
+    let wal_before = std::fs::read(&wal_path).expect("wal");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path.clone()),
+        enable_wal: true,
+        read_only: true,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: None };
+
+    let create = svc
+        .create_collection(Request::new(CreateCollectionRequest {
+            name: "new".into(),
+            dims: 2,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect_err("create rejected");
+    assert_eq!(create.code(), tonic::Code::FailedPrecondition);
+
+    let upsert = svc
+        .upsert(Request::new(UpsertRequest {
+            collection: "frozen".into(),
+            points: vec![Point { id: "nope".into(), vector: vec![0.0, 0.0], payload_json: String::new(), ttl_ms: 0 }],
+            dry_run: false,
+        }))
+        .await
+        .expect_err("upsert rejected");
+    assert_eq!(upsert.code(), tonic::Code::FailedPrecondition);
+
+    let truncate = svc
+        .truncate_collection(Request::new(TruncateCollectionRequest { collection: "frozen".into() }))
+        .await
+        .expect_err("truncate rejected");
+    assert_eq!(truncate.code(), tonic::Code::FailedPrecondition);
+
+    let hits = svc
+        .query(Request::new(QueryRequest {
+            collection: "frozen".into(),
+            vector: vec![1.0, 1.0],
+            top_k: Some(5),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].id, "kept");
+    assert_eq!(std::fs::read(&wal_path).expect("wal"), wal_before);
+}
+
+#[tokio::test]
+#[serial]
+async fn create_collection_if_not_exists_is_idempotent() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    let create = |dims: u32, metric: &str| {
+        Request::new(CreateCollectionRequest {
+            name: "boot".into(),
+            dims,
+            metric: metric.into(),
+            if_not_exists: true,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        })
+    };
+
+    svc.create_collection(create(3, "cosine")).await.expect("fresh create");
+    assert_eq!(state.catalog.len(), 1);
+
+    svc.create_collection(create(3, "cosine")).await.expect("matching create is a no-op");
+    assert_eq!(state.catalog.len(), 1);
+
+    for (dims, metric) in [(4, "cosine"), (3, "l2")] {
+        let err = svc
+            .create_collection(create(dims, metric))
+            .await
+            .expect_err("mismatched create");
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    }
+
+    let err = svc
+        .create_collection(Request::new(CreateCollectionRequest {
+            name: "boot".into(),
+            dims: 3,
+            metric: "cosine".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect_err("plain create of existing collection");
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+}
+
+#[tokio::test]
+#[serial]
+async fn buffered_wal_is_durable_only_after_flush() {
+    let tmp = tempdir().expect("tempdir");
+    let wal_path = tmp.path().join("wal.log");
+    let config = || DbStateConfig {
+        wal_path: Some(wal_path.clone()),
+        enable_wal: true,
+        wal_buffer_bytes: 1 << 20,
+        ..DbStateConfig::default()
+    };
+    let state = Arc::new(DbState::with_config(config()));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "buffered".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "buffered".into(),
+        points: vec![Point { id: "p".into(), vector: vec![1.0, 2.0], payload_json: String::new(), ttl_ms: 0 }],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    // Simulates a crash: a fresh process sees only what reached the file.
+    assert_eq!(std::fs::metadata(&wal_path).expect("wal").len(), 0);
+    assert!(DbState::with_config(config()).catalog.get("buffered").is_none());
+
+    let flushed = svc
+        .flush(Request::new(FlushRequest {}))
+        .await
+        .expect("flush")
+        .into_inner()
+        .flushed_bytes;
+    assert!(flushed > 0);
+    let again = svc.flush(Request::new(FlushRequest {})).await.expect("flush").into_inner();
+    assert_eq!(again.flushed_bytes, 0);
+
+    let reopened = DbState::with_config(config());
+    let handle = reopened.catalog.get("buffered").expect("collection after flush");
+    assert_eq!(handle.len(), Some(1));
+}
+
+#[tokio::test]
+#[serial]
+async fn collection_config_is_replayed_from_wal() {
+    let (state, wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    for (name, threshold) in [("meta", Some(u64::MAX)), ("embeddings", None)] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims: 2,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: threshold,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+    }
+    svc.set_collection_config(Request::new(SetCollectionConfigRequest {
+        collection: "embeddings".into(),
+        parallel_threshold: Some(0),
+        reset_parallel_threshold: false,
+        default_top_k: None,
+        default_with_payloads: None,
+    }))
+    .await
+    .expect("set config");
+    let conflicting = svc
+        .set_collection_config(Request::new(SetCollectionConfigRequest {
+            collection: "embeddings".into(),
+            parallel_threshold: Some(5),
+            reset_parallel_threshold: true,
+            default_top_k: None,
+            default_with_payloads: None,
+        }))
+        .await
+        .expect_err("conflicting fields");
+    assert_eq!(conflicting.code(), tonic::Code::InvalidArgument);
+
+    let reopened = DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    });
+    let threshold = |name: &str| reopened.catalog.get(name).and_then(|h| h.with_ref(|c| c.parallel_threshold));
+    assert_eq!(threshold("meta"), Some(Some(usize::MAX)));
+    assert_eq!(threshold("embeddings"), Some(Some(0)));
+}
+
+#[tokio::test]
+#[serial]
+async fn include_ids_restricts_scoring_to_listed_points() {
+    let (state, _wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "candidates".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "candidates".into(),
+        points: (0..1000)
+            .map(|i| Point {
+                id: format!("p{i}"),
+                vector: vec![i as f32, 0.0],
+                payload_json: format!("{{\"even\":{}}}", i % 2 == 0),
+                ttl_ms: 0,
+            })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let query = |include: &[&str], exclude: &[&str], filters: Vec<Filter>| QueryRequest {
+        collection: "candidates".into(),
+        vector: vec![0.0, 0.0],
+        top_k: Some(10),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters,
+        exclude_ids: exclude.iter().map(|s| s.to_string()).collect(),
+        hybrid: None,
+        include_ids: include.iter().map(|s| s.to_string()).collect(),
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+    let ids = |hits: Vec<vectaraft::pb::vectordb::v1::ScoredPoint>| hits.into_iter().map(|h| h.id).collect::<Vec<_>>();
+
+    let hits = svc
+        .query(Request::new(query(&["p700", "p3", "p512", "unknown"], &[], vec![])))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+    assert_eq!(ids(hits), vec!["p3", "p512", "p700"]);
+
+    let hits = svc
+        .query(Request::new(query(
+            &["p700", "p3", "p512"],
+            &["p512"],
+            vec![Filter { key: "even".into(), equals: "true".into() }],
+        )))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+    assert_eq!(ids(hits), vec!["p700"]);
+}
+
+#[tokio::test]
+#[serial]
+async fn expired_points_vanish_from_queries_and_are_swept() {
+    let (state, wal_path, _guard) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "cache".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "cache".into(),
+        points: vec![
+            Point { id: "ephemeral".into(), vector: vec![0.0, 0.0], payload_json: String::new(), ttl_ms: 50 },
+            Point { id: "durable".into(), vector: vec![1.0, 1.0], payload_json: String::new(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let hit_ids = || async {
+        svc.query(Request::new(QueryRequest {
+            collection: "cache".into(),
+            vector: vec![0.0, 0.0],
+            top_k: Some(10),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits
+        .into_iter()
+        .map(|h| h.id)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(hit_ids().await, vec!["ephemeral", "durable"]);
+
+    tokio::time::sleep(std::time::Duration::from_millis(80)).await;
+    // Hidden before any sweep runs.
+    assert_eq!(hit_ids().await, vec!["durable"]);
+    assert_eq!(state.catalog.total_points(), 2);
+    let page = svc
+        .scroll(Request::new(ScrollRequest {
+            collection: "cache".into(),
+            offset: 0,
+            limit: 1,
+            with_payloads: false,
+            with_vectors: false,
+        }))
+        .await
+        .expect("scroll")
+        .into_inner();
+    assert_eq!(page.points.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), vec!["durable"]);
+    assert_eq!(page.next_offset, 2);
+    assert!(!page.has_more);
+
+    let sweeper = vectaraft::server::expiry::spawn(state.clone(), std::time::Duration::from_millis(20));
+    for _ in 0..50 {
+        if state.catalog.total_points() == 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    sweeper.abort();
+    assert_eq!(state.catalog.total_points(), 1);
+
+    let reopened = DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    });
+    assert_eq!(reopened.catalog.total_points(), 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn sparse_collections_are_compacted_automatically() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state.clone());
+    client.create_collection("sparse", 2, "l2").await.expect("create");
+    client.create_collection("dense", 2, "l2").await.expect("create");
+    let points = |n: usize| (0..n).map(|i| point(&format!("p{i}"), vec![i as f32, 0.0], "")).collect::<Vec<_>>();
+    client.upsert("sparse", points(100)).await.expect("upsert");
+    client.upsert("dense", points(100)).await.expect("upsert");
+
+    let drop_first = |name: &str, n: usize| {
+        let ids: Vec<String> = (0..n).map(|i| format!("p{i}")).collect();
+        state.catalog.get(name).expect("collection").with_mut(|coll| coll.remove_points(&ids)).expect("remove");
+    };
+    drop_first("sparse", 60);
+    drop_first("dense", 10);
+    let freed = |name: &str| state.catalog.get(name).expect("collection").with_ref(|coll| coll.freed_ratio()).unwrap();
+    assert!((freed("sparse") - 0.6).abs() < 1e-9);
+    assert!((freed("dense") - 0.1).abs() < 1e-9);
+    let capacity = |name: &str| {
+        state.catalog.index_sizes().into_iter().find(|(n, _, _)| n == name).map(|(_, _, cap)| cap).unwrap()
+    };
+    assert!(capacity("sparse") >= 100);
+
+    let compactor = vectaraft::server::compaction::spawn(state.clone(), std::time::Duration::from_millis(20), 0.3);
+    for _ in 0..50 {
+        if freed("sparse") == 0.0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    compactor.abort();
+    assert_eq!(freed("sparse"), 0.0);
+    assert!(capacity("sparse") < 100, "rebuild returns the freed rows' memory");
+    // Below the threshold, so left alone.
+    assert!((freed("dense") - 0.1).abs() < 1e-9);
+    let hits = client.query("sparse", vec![99.0, 0.0], 1).await.expect("query");
+    assert_eq!(hits[0].id, "p99");
+    assert_eq!(state.catalog.total_points(), 130);
+}
+
+#[tokio::test]
+#[serial]
+async fn expected_count_preallocates_index() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "bulk".into(),
+        dims: 4,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 5_000,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    let handle = state.catalog.get("bulk").expect("collection");
+    let capacity = handle.with_mut(|coll| coll.index.capacity()).expect("capacity");
+    assert!(capacity >= 5_000, "capacity {capacity} below hint");
+    assert_eq!(handle.len(), Some(0));
+}
+
+#[tokio::test]
+#[serial]
+async fn fetch_by_filter_returns_projected_payloads_in_pages() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "docs".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let docs = [("n1", "news"), ("s1", "sports"), ("n2", "news"), ("n3", "news")];
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "docs".into(),
+        points: docs
+            .iter()
+            .map(|(id, category)| Point {
+                id: id.to_string(),
+                vector: vec![0.0, 0.0],
+                payload_json: format!(r#"{{"category":"{category}","title":"{id} title","body":"long text"}}"#),
+                ttl_ms: 0,
+            })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let fetch = |offset: u64, limit: u32| {
+        svc.fetch_by_filter(Request::new(FetchByFilterRequest {
+            collection: "docs".into(),
+            filters: vec![Filter { key: "category".into(), equals: "news".into() }],
+            payload_fields: vec!["title".into()],
+            offset,
+            limit,
+        }))
+    };
+
+    let first = fetch(0, 2).await.expect("first page").into_inner();
+    let ids: Vec<_> = first.points.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, vec!["n1", "n2"]);
+    assert_eq!(first.points[0].payload_json, r#"{"title":"n1 title"}"#);
+    assert!(first.points.iter().all(|p| p.vector.is_empty()));
+    assert!(first.has_more);
+
+    let second = fetch(first.next_offset, 2).await.expect("second page").into_inner();
+    let payloads: Vec<_> = second.points.iter().map(|p| p.payload_json.as_str()).collect();
+    assert_eq!(payloads, vec![r#"{"title":"n3 title"}"#]);
+    assert!(!second.has_more);
+}
+
+#[tokio::test]
+#[serial]
+async fn upsert_returns_final_ids_in_input_order() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "auto".into(),
+        dims: 1,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let point = |id: &str, x: f32| Point { id: id.into(), vector: vec![x], payload_json: String::new(), ttl_ms: 0 };
+    let resp = svc
+        .upsert(Request::new(UpsertRequest {
+            collection: "auto".into(),
+            points: vec![point("", 0.0), point("given", 1.0), point("", 2.0)],
+            dry_run: false,
+        }))
+        .await
+        .expect("upsert")
+        .into_inner();
+
+    assert_eq!(resp.upserted, 3);
+    assert_eq!(resp.ids.len(), 3);
+    assert_eq!(resp.ids[1], "given");
+    for generated in [&resp.ids[0], &resp.ids[2]] {
+        assert!(uuid::Uuid::parse_str(generated).is_ok(), "{generated} is not a UUID");
+    }
+    assert_ne!(resp.ids[0], resp.ids[2]);
+
+    // The returned ids are the ones stored, in the same order.
+    let handle = state.catalog.get("auto").expect("collection");
+    let view = handle.iter_points().expect("view");
+    let stored: Vec<String> = view.iter().map(|(id, _, _)| id.to_string()).collect();
+    assert_eq!(stored, resp.ids);
+}
+
+#[tokio::test]
+#[serial]
+async fn ulid_scheme_generates_increasing_ids() {
+    let tmp = tempdir().expect("tempdir");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(tmp.path().join("wal.log")),
+        enable_wal: true,
+        id_scheme: IdScheme::Ulid,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "events".into(),
+        dims: 1,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    let mut ids = Vec::new();
+    for batch in 0..4 {
+        let points = (0..3)
+            .map(|i| Point { id: String::new(), vector: vec![(batch * 3 + i) as f32], payload_json: String::new(), ttl_ms: 0 })
+            .collect();
+        let resp = svc
+            .upsert(Request::new(UpsertRequest { collection: "events".into(), points, dry_run: false }))
+            .await
+            .expect("upsert")
+            .into_inner();
+        ids.extend(resp.ids);
+        tokio::time::sleep(std::time::Duration::from_millis(3)).await;
+    }
+
+    assert_eq!(ids.len(), 12);
+    for id in &ids {
+        assert_eq!(id.len(), 26, "{id} is not a ULID");
+        assert!(id.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase()), "{id}");
+    }
+    // Ids within a batch share a millisecond and must still increase.
+    assert!(ids.windows(2).all(|w| w[0] < w[1]), "{ids:?}");
+}
+
+#[tokio::test]
+#[serial]
+async fn assume_normalized_cosine_matches_full_cosine() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    for (name, assume_normalized) in [("full", false), ("fast", true)] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims: 16,
+            metric: "cosine".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+    }
+    let unit = |seed: usize| -> Vec<f32> {
+        let v: Vec<f32> = (0..16).map(|d| ((seed * 31 + d * 7) as f32).sin()).collect();
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        v.into_iter().map(|x| x / norm).collect()
+    };
+    for name in ["full", "fast"] {
+        let points = (0..200)
+            .map(|i| Point { id: format!("p{i}"), vector: unit(i), payload_json: String::new(), ttl_ms: 0 })
+            .collect();
+        svc.upsert(Request::new(UpsertRequest { collection: name.into(), points, dry_run: false }))
+            .await
+            .expect("upsert");
+    }
+
+    let query = |name: &'static str| {
+        let svc = svc.clone();
+        async move {
+            svc.query(Request::new(QueryRequest {
+                collection: name.into(),
+                vector: unit(1000),
+                top_k: Some(10),
+                metric_override: String::new(),
+                with_payloads: Some(false),
+                filters: vec![],
+                exclude_ids: vec![],
+                hybrid: None,
+                include_ids: vec![],
+                explain: false,
+                boosts: vec![],
+                normalize_scores: false,
+                centroid_ids: vec![],
+                max_scan: 0,
+                group_by: String::new(),
+                per_group_limit: 0,
+                dedup_by: String::new(),
+                report_distance: false,
+                explain_dimensions: false,
+            }))
+            .await
+            .expect("query")
+            .into_inner()
+            .hits
+        }
+    };
+    let (full, fast) = (query("full").await, query("fast").await);
+    assert_eq!(full.len(), 10);
+    let ids = |hits: &[vectaraft::pb::vectordb::v1::ScoredPoint]| hits.iter().map(|h| h.id.clone()).collect::<Vec<_>>();
+    assert_eq!(ids(&full), ids(&fast));
+    for (a, b) in full.iter().zip(&fast) {
+        assert!((a.score - b.score).abs() < 1e-5, "{} vs {}", a.score, b.score);
+    }
+
+    // The flag is part of the collection definition and survives replay.
+    drop(svc);
+    drop(state);
+    let replayed = DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        read_only: true,
+        ..DbStateConfig::default()
+    });
+    let flag = |name: &str| replayed.catalog.get(name).and_then(|h| h.with_ref(|c| c.assume_normalized));
+    assert_eq!(flag("fast"), Some(true));
+    assert_eq!(flag("full"), Some(false));
+}
+
+#[tokio::test]
+#[serial]
+async fn pinned_collections_are_warmed_and_reported() {
+    let tmp = tempdir().expect("tempdir");
+    let config = DbStateConfig {
+        wal_path: Some(tmp.path().join("wal.log")),
+        enable_wal: true,
+        pinned_collections: vec!["hot".into(), "later".into()],
+        ..DbStateConfig::default()
+    };
+    let client = EmbeddedClient::new(Arc::new(DbState::with_config(config.clone())));
+    for name in ["hot", "cold", "later"] {
+        client.create_collection(name, 2, "l2").await.expect("create");
+    }
+    client.upsert("cold", vec![point("a", vec![1.0, 2.0], "{}"), point("b", vec![3.0, 4.0], "")]).await.expect("upsert");
+    let svc = client.service();
+    let pinned = |name: &'static str| async move {
+        svc.describe_collection(Request::new(DescribeCollectionRequest { collection: name.into() }))
+            .await
+            .expect("describe")
+            .into_inner()
+            .pinned
+    };
+    // Configured names apply to collections created after startup too.
+    assert!(pinned("hot").await);
+    assert!(pinned("later").await);
+    assert!(!pinned("cold").await);
+
+    let resp = svc
+        .pin_collection(Request::new(PinCollectionRequest { collection: "cold".into(), unpin: false }))
+        .await
+        .expect("pin")
+        .into_inner();
+    assert_eq!(resp.points, 2);
+    assert!(resp.bytes >= 4 * std::mem::size_of::<f32>() as u64);
+    assert!(pinned("cold").await);
+
+    let resp = svc
+        .pin_collection(Request::new(PinCollectionRequest { collection: "hot".into(), unpin: true }))
+        .await
+        .expect("unpin")
+        .into_inner();
+    assert_eq!(resp.points, 0);
+    assert!(!pinned("hot").await);
+    assert_eq!(client.state().catalog.pinned(), vec!["cold".to_string(), "later".to_string()]);
+
+    let missing = svc
+        .pin_collection(Request::new(PinCollectionRequest { collection: "ghost".into(), unpin: false }))
+        .await
+        .expect_err("missing collection");
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+    assert!(!client.state().catalog.is_pinned("ghost"));
+
+    // Runtime pins are not persisted; a restart pins the configured names.
+    drop(client);
+    let reopened = DbState::with_config(config);
+    assert_eq!(reopened.catalog.pinned(), vec!["hot".to_string(), "later".to_string()]);
+}
+
+#[tokio::test]
+#[serial]
+async fn describe_collection_reports_shape_or_not_found() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "docs".into(),
+        dims: 3,
+        metric: "cosine".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: "blocked".into(),
+        assume_normalized: true,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let points = (0..3)
+        .map(|i| Point { id: format!("p{i}"), vector: vec![1.0, 0.0, 0.0], payload_json: String::new(), ttl_ms: 0 })
+        .collect();
+    svc.upsert(Request::new(UpsertRequest { collection: "docs".into(), points, dry_run: false }))
+        .await
+        .expect("upsert");
+
+    let described = svc
+        .describe_collection(Request::new(DescribeCollectionRequest { collection: "docs".into() }))
+        .await
+        .expect("describe")
+        .into_inner();
+    assert_eq!(
+        described,
+        DescribeCollectionResponse {
+            name: "docs".into(),
+            dims: 3,
+            metric: "cosine".into(),
+            index_type: "flat".into(),
+            layout: "blocked".into(),
+            points: 3,
+            assume_normalized: true,
+            parallel_threshold: None,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: "inline".into(),
+            id_type: "string".into(),
+            queries: 0,
+            upserts: 1,
+            pinned: false,
+        }
+    );
+
+    let missing = svc
+        .describe_collection(Request::new(DescribeCollectionRequest { collection: "ghost".into() }))
+        .await
+        .expect_err("missing collection");
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+    assert_eq!(
+        vectaraft::server::grpc::error_info(&missing).expect("error info").reason,
+        vectaraft::server::grpc::REASON_COLLECTION_NOT_FOUND
+    );
+    assert!(state.catalog.get("ghost").is_none(), "describe must not create");
+}
+
+#[tokio::test]
+#[serial]
+async fn normalized_scores_keep_ranking_within_unit_range() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state);
+    client.create_collection("mixed", 3, "l2").await.expect("create collection");
+    let points = (0..12)
+        .map(|i| {
+            let i = i as f32;
+            point(&format!("p{i}"), vec![i - 6.0, (i * 1.7) % 5.0, if i > 5.0 { 0.0 } else { 3.0 * i }], "{}")
+        })
+        .collect();
+    client.upsert("mixed", points).await.expect("upsert");
+
+    for metric in ["l2", "cosine", "ip", "chebyshev", "jaccard"] {
+        let query = |normalize_scores: bool| QueryRequest {
+            collection: "mixed".into(),
+            vector: vec![2.0, -1.0, 4.0],
+            top_k: Some(12),
+            metric_override: metric.into(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        };
+        let service = client.service();
+        let raw = service.query(Request::new(query(false))).await.expect("query").into_inner().hits;
+        let normalized = service.query(Request::new(query(true))).await.expect("query").into_inner().hits;
+
+        let ids = |hits: &[vectaraft::pb::vectordb::v1::ScoredPoint]| hits.iter().map(|h| h.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&raw), ids(&normalized), "{metric}");
+        assert!(normalized.iter().all(|h| (0.0..=1.0).contains(&h.score)), "{metric}: {normalized:?}");
+        assert!(normalized.windows(2).all(|w| w[0].score >= w[1].score), "{metric}");
+        let metric = Metric::parse(metric).expect("metric");
+        for (r, n) in raw.iter().zip(&normalized) {
+            assert_eq!(metric.normalize_score(r.score), n.score);
+        }
+    }
+    assert_eq!(Metric::Cosine.normalize_score(-1.0), 0.0);
+    assert_eq!(Metric::Cosine.normalize_score(1.0), 1.0);
+    assert_eq!(Metric::L2.normalize_score(-3.0), 0.25);
+    assert_eq!(Metric::IP.normalize_score(0.0), 0.5);
+}
+
+#[tokio::test]
+#[serial]
+async fn cosine_distance_reports_one_minus_similarity() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state);
+    client.create_collection("angles", 2, "cosine").await.expect("create collection");
+    let points = vec![
+        point("same", vec![2.0, 0.0], "{}"),
+        point("diagonal", vec![1.0, 1.0], "{}"),
+        point("orthogonal", vec![0.0, 3.0], "{}"),
+        point("opposite", vec![-1.0, 0.0], "{}"),
+    ];
+    client.upsert("angles", points).await.expect("upsert");
+    let query = |metric: &str, report_distance: bool, normalize_scores: bool| QueryRequest {
+        collection: "angles".into(),
+        vector: vec![1.0, 0.0],
+        top_k: Some(4),
+        metric_override: metric.into(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance,
+        explain_dimensions: false,
+    };
+    let service = client.service();
+    let similarity = service.query(Request::new(query("", false, false))).await.expect("query").into_inner().hits;
+    let distance = service.query(Request::new(query("", true, false))).await.expect("query").into_inner().hits;
+
+    let ids = |hits: &[vectaraft::pb::vectordb::v1::ScoredPoint]| hits.iter().map(|h| h.id.clone()).collect::<Vec<_>>();
+    assert_eq!(ids(&distance), vec!["same", "diagonal", "orthogonal", "opposite"]);
+    assert_eq!(ids(&similarity), ids(&distance));
+    let expected = [0.0, 1.0 - std::f32::consts::FRAC_1_SQRT_2, 1.0, 2.0];
+    for (hit, want) in distance.iter().zip(expected) {
+        assert!((hit.score - want).abs() < 1e-6, "{}: {} != {want}", hit.id, hit.score);
+    }
+    for (s, d) in similarity.iter().zip(&distance) {
+        assert_eq!(d.score, 1.0 - s.score);
+    }
+
+    // L2 reports the true rather than squared distance.
+    let l2 = service.query(Request::new(query("l2", true, false))).await.expect("query").into_inner().hits;
+    assert_eq!(l2[0].id, "same");
+    assert!((l2[0].score - 1.0).abs() < 1e-6);
+    for (metric, normalize) in [("ip", false), ("", true)] {
+        let err = service.query(Request::new(query(metric, true, normalize))).await.expect_err("rejected");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn dimension_contributions_sum_to_the_top_hit_score() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state);
+    client.create_collection("dims", 3, "l2").await.expect("create collection");
+    let points = vec![
+        point("near", vec![1.0, 2.5, -1.0], "{}"),
+        point("far", vec![-4.0, 0.0, 3.0], "{}"),
+    ];
+    client.upsert("dims", points).await.expect("upsert");
+    let query = |metric: &str| QueryRequest {
+        collection: "dims".into(),
+        vector: vec![1.5, 2.0, 0.5],
+        top_k: Some(2),
+        metric_override: metric.into(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: true,
+    };
+    let service = client.service();
+
+    let l2 = service.query(Request::new(query(""))).await.expect("query").into_inner().hits;
+    assert_eq!(l2[0].id, "near");
+    assert_eq!(l2[0].dimension_contributions, vec![0.25, 0.25, 2.25]);
+    let sum: f32 = l2[0].dimension_contributions.iter().sum();
+    assert!((sum + l2[0].score).abs() < 1e-6, "{sum} vs {}", l2[0].score);
+    assert!(l2[1].dimension_contributions.is_empty());
+
+    let ip = service.query(Request::new(query("ip"))).await.expect("query").into_inner().hits;
+    assert_eq!(ip[0].id, "near");
+    assert_eq!(ip[0].dimension_contributions, vec![1.5, 5.0, -0.5]);
+    let sum: f32 = ip[0].dimension_contributions.iter().sum();
+    assert!((sum - ip[0].score).abs() < 1e-6, "{sum} vs {}", ip[0].score);
+
+    let err = service.query(Request::new(query("cosine"))).await.expect_err("rejected");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn stalled_export_does_not_block_writers() {
+    use tokio_stream::StreamExt;
+    use vectaraft::pb::vectordb::v1::export_record::Record;
+
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state);
+    client.create_collection("big", 2, "l2").await.expect("create collection");
+    let points = (0..2_000).map(|i| point(&format!("p{i}"), vec![i as f32, 0.0], "{}")).collect();
+    client.upsert("big", points).await.expect("upsert");
+
+    let mut stream = client.service().export_all(Request::new(ExportAllRequest {})).await.expect("export").into_inner();
+    // Nothing is read yet, so the exporter stalls once the channel fills.
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let writer = client.clone();
+    let write = tokio::spawn(async move { writer.upsert("big", vec![point("late", vec![0.0, 1.0], "{}")]).await });
+    tokio::time::timeout(std::time::Duration::from_secs(5), write)
+        .await
+        .expect("upsert blocked behind the export")
+        .expect("join")
+        .expect("upsert");
+
+    let mut exported = 0;
+    while let Some(frame) = stream.next().await {
+        if let Some(Record::Point(_)) = frame.expect("frame").record {
+            exported += 1;
+        }
+    }
+    assert_eq!(exported, 2_000);
+}
+
+#[tokio::test]
+#[serial]
+async fn collection_query_defaults_apply_when_request_leaves_them_unset() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "defaults".into(),
+        dims: 1,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: Some(3),
+        default_with_payloads: true,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let points = (0..10)
+        .map(|i| Point { id: format!("p{i}"), vector: vec![i as f32], payload_json: format!(r#"{{"i":{i}}}"#), ttl_ms: 0 })
+        .collect();
+    svc.upsert(Request::new(UpsertRequest { collection: "defaults".into(), points, dry_run: false }))
+        .await
+        .expect("upsert");
+
+    let query = |svc: VectorDbService, top_k: Option<u32>, with_payloads: Option<bool>| async move {
+        svc.query(Request::new(QueryRequest {
+            collection: "defaults".into(),
+            vector: vec![0.0],
+            top_k,
+            metric_override: String::new(),
+            with_payloads,
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits
+    };
+
+    let hits = query(svc.clone(), None, None).await;
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[0].payload_json, r#"{"i":0}"#);
+
+    // Explicit values, including zero and false, override the defaults.
+    let hits = query(svc.clone(), Some(5), Some(false)).await;
+    assert_eq!(hits.len(), 5);
+    assert!(hits.iter().all(|h| h.payload_json.is_empty()));
+    assert!(query(svc.clone(), Some(0), None).await.is_empty());
+
+    svc.set_collection_config(Request::new(SetCollectionConfigRequest {
+        collection: "defaults".into(),
+        parallel_threshold: None,
+        reset_parallel_threshold: false,
+        default_top_k: Some(0),
+        default_with_payloads: None,
+    }))
+    .await
+    .expect("clear default top_k");
+    assert!(query(svc.clone(), None, None).await.is_empty(), "no default top_k left");
+    svc.set_collection_config(Request::new(SetCollectionConfigRequest {
+        collection: "defaults".into(),
+        parallel_threshold: None,
+        reset_parallel_threshold: false,
+        default_top_k: Some(2),
+        default_with_payloads: None,
+    }))
+    .await
+    .expect("set default top_k");
+    svc.state.flush_wal().expect("flush wal");
+
+    let replayed = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    }));
+    let hits = query(VectorDbService { state: replayed, metrics: None }, None, None).await;
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(|h| !h.payload_json.is_empty()));
+}
+
+#[tokio::test]
+#[serial]
+async fn collection_config_survives_restart() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "tuned".into(),
+        dims: 2,
+        metric: "cosine".into(),
+        if_not_exists: false,
+        parallel_threshold: Some(64),
+        expected_count: 0,
+        layout: "blocked".into(),
+        assume_normalized: true,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.set_collection_config(Request::new(SetCollectionConfigRequest {
+        collection: "tuned".into(),
+        parallel_threshold: Some(128),
+        reset_parallel_threshold: false,
+        default_top_k: None,
+        default_with_payloads: None,
+    }))
+    .await
+    .expect("set config");
+    let describe = |svc: VectorDbService| async move {
+        svc.describe_collection(Request::new(DescribeCollectionRequest { collection: "tuned".into() }))
+            .await
+            .expect("describe")
+            .into_inner()
+    };
+    let before = describe(svc.clone()).await;
+    assert_eq!((before.layout.as_str(), before.parallel_threshold), ("blocked", Some(128)));
+    svc.state.flush_wal().expect("flush wal");
+
+    let restart = || {
+        Arc::new(DbState::with_config(DbStateConfig {
+            wal_path: Some(wal_path.clone()),
+            enable_wal: true,
+            snapshot_path: Some(wal_path.with_extension("snapshot")),
+            ..DbStateConfig::default()
+        }))
+    };
+    let replayed = restart();
+    assert_eq!(describe(VectorDbService { state: replayed.clone(), metrics: None }).await, before);
+
+    // A snapshot folds the config into its CreateCollection record.
+    replayed.save_snapshot().expect("snapshot").expect("not already running");
+    assert_eq!(describe(VectorDbService { state: restart(), metrics: None }).await, before);
+}
+
+#[tokio::test]
+#[serial]
+async fn group_commit_shares_fsyncs_and_acks_only_durable_writes() {
+    let tmp = tempdir().expect("tempdir");
+    let wal_path = tmp.path().join("wal.log");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path.clone()),
+        enable_wal: true,
+        // Large enough that nothing reaches the file until a commit flushes it.
+        wal_buffer_bytes: 1 << 20,
+        wal_commit_window: Some(std::time::Duration::from_millis(20)),
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "durable".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let syncs_before = state.wal_commit.as_ref().expect("group commit").syncs();
+
+    let writers: Vec<_> = (0..32)
+        .map(|i| {
+            let svc = svc.clone();
+            let wal_path = wal_path.clone();
+            tokio::spawn(async move {
+                let id = format!("w{i}");
+                svc.upsert(Request::new(UpsertRequest {
+                    collection: "durable".into(),
+                    points: vec![Point { id: id.clone(), vector: vec![i as f32, 0.0], payload_json: String::new(), ttl_ms: 0 }],
+                    dry_run: false,
+                }))
+                .await
+                .expect("upsert");
+                // Acknowledged, so the record must already be in the file.
+                let on_disk = std::fs::read_to_string(&wal_path).expect("read wal");
+                assert!(on_disk.contains(&format!("\"id\":\"{id}\"")), "{id} acknowledged before it was written");
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.await.expect("writer");
+    }
+
+    let syncs = state.wal_commit.as_ref().expect("group commit").syncs() - syncs_before;
+    assert!((1..=4).contains(&syncs), "32 concurrent upserts took {syncs} fsyncs");
+    assert_eq!(state.wal.as_ref().expect("wal").buffered_bytes(), 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn find_duplicates_reports_only_the_near_identical_pair() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    let point = |id: &str, vector: Vec<f32>| Point { id: id.into(), vector, payload_json: String::new(), ttl_ms: 0 };
+    for metric in ["cosine", "l2"] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: metric.into(),
+            dims: 3,
+            metric: metric.into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+        svc.upsert(Request::new(UpsertRequest {
+            collection: metric.into(),
+            points: vec![
+                point("orig", vec![1.0, 0.2, 0.0]),
+                point("other", vec![0.0, 0.3, 1.0]),
+                point("copy", vec![1.0, 0.21, 0.0]),
+            ],
+            dry_run: false,
+        }))
+        .await
+        .expect("upsert");
+    }
+
+    let find = |collection: &str, threshold: f32| {
+        svc.find_duplicates(Request::new(FindDuplicatesRequest { collection: collection.into(), threshold, limit: 0 }))
+    };
+    // Cosine: similarity floor.
+    let cosine = find("cosine", 0.99).await.expect("find").into_inner();
+    assert_eq!(cosine.total_pairs, 1);
+    assert_eq!((cosine.pairs[0].id_a.as_str(), cosine.pairs[0].id_b.as_str()), ("orig", "copy"));
+    assert!(cosine.pairs[0].raw > 0.99);
+    // L2: squared-distance ceiling.
+    let l2 = find("l2", 0.01).await.expect("find").into_inner();
+    assert_eq!(l2.total_pairs, 1);
+    assert_eq!((l2.pairs[0].id_a.as_str(), l2.pairs[0].id_b.as_str()), ("orig", "copy"));
+    assert!((l2.pairs[0].raw - 0.0001).abs() < 1e-5, "{}", l2.pairs[0].raw);
+    // A loose threshold pairs everything, closest first; limit trims the list.
+    let loose = svc
+        .find_duplicates(Request::new(FindDuplicatesRequest { collection: "l2".into(), threshold: 10.0, limit: 2 }))
+        .await
+        .expect("find")
+        .into_inner();
+    assert_eq!(loose.total_pairs, 3);
+    assert_eq!(loose.pairs.len(), 2);
+    assert_eq!(loose.pairs[0].id_b, "copy");
+
+    let missing = find("ghost", 0.5).await.expect_err("missing collection");
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+#[serial]
+async fn swap_collections_replaces_target_atomically() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+
+    for (name, dims, ids) in [("live", 2, vec!["old1", "old2"]), ("shadow", 3, vec!["new1", "new2", "new3"])] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+        svc.upsert(Request::new(UpsertRequest {
+            collection: name.into(),
+            points: ids
+                .iter()
+                .enumerate()
+                .map(|(i, id)| Point {
+                    id: id.to_string(),
+                    vector: vec![i as f32; dims as usize],
+                    payload_json: String::new(),
+                    ttl_ms: 0,
+                })
+                .collect(),
+            dry_run: false,
+        }))
+        .await
+        .expect("upsert");
+    }
+
+    // A reader polling the live collection must never find it missing or empty.
+    let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let reader = {
+        let (state, stop) = (state.clone(), stop.clone());
+        std::thread::spawn(move || {
+            let mut observed = Vec::new();
+            while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                observed.push(state.catalog.get("live").and_then(|h| h.len()));
+            }
+            observed
+        })
+    };
+    let resp = svc
+        .swap_collections(Request::new(SwapCollectionsRequest { target: "live".into(), source: "shadow".into() }))
+        .await
+        .expect("swap")
+        .into_inner();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    let observed = reader.join().expect("reader");
+    assert!(observed.iter().all(|len| matches!(len, Some(2) | Some(3))), "{observed:?}");
+    assert_eq!(resp.points, 3);
+
+    let hits = svc
+        .query(Request::new(QueryRequest {
+            collection: "live".into(),
+            vector: vec![2.0, 2.0, 2.0],
+            top_k: Some(1),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query uses the swapped-in dims")
+        .into_inner()
+        .hits;
+    assert_eq!(hits[0].id, "new3");
+    assert!(state.catalog.get("shadow").is_none());
+
+    let err = svc
+        .swap_collections(Request::new(SwapCollectionsRequest { target: "live".into(), source: "shadow".into() }))
+        .await
+        .expect_err("source is gone");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    let reopened = DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    });
+    let live = reopened.catalog.get("live").expect("live after replay");
+    assert_eq!((live.len(), live.dim()), (Some(3), Some(3)));
+    assert!(reopened.catalog.get("shadow").is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn create_collection_enforces_max_dims() {
+    let tmp = tempdir().expect("tempdir");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(tmp.path().join("wal.log")),
+        enable_wal: true,
+        max_dims: 8,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    let create = |name: &str, dims: u32| {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+    };
+
+    let err = create("huge", 9).await.expect_err("over the limit");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(state.catalog.get("huge").is_none());
+
+    create("fits", 8).await.expect("at the limit");
+    assert_eq!(state.catalog.get("fits").and_then(|h| h.dim()), Some(8));
+    assert_eq!(DbStateConfig::default().max_dims, vectaraft::server::state::DEFAULT_MAX_DIMS);
+}
+
+#[tokio::test]
+#[serial]
+async fn collection_checksum_ignores_insert_order_but_not_content() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    let points = |ids: &[&str]| -> Vec<Point> {
+        ids.iter()
+            .map(|id| Point {
+                id: id.to_string(),
+                vector: vec![id.len() as f32, 0.5],
+                payload_json: format!(r#"{{"name":"{id}"}}"#),
+                ttl_ms: 0,
+            })
+            .collect()
+    };
+    for (name, order) in [("forward", ["a", "bb", "ccc"]), ("reverse", ["ccc", "bb", "a"])] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims: 2,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+        // One point per batch so insertion order really differs.
+        for point in points(&order) {
+            svc.upsert(Request::new(UpsertRequest { collection: name.into(), points: vec![point], dry_run: false }))
+                .await
+                .expect("upsert");
+        }
+    }
+    let checksum = |name: &str| {
+        let svc = &svc;
+        let name = name.to_string();
+        async move {
+            svc.collection_checksum(Request::new(CollectionChecksumRequest { collection: name }))
+                .await
+                .expect("checksum")
+                .into_inner()
+        }
+    };
+
+    let forward = checksum("forward").await;
+    assert_eq!(forward.points, 3);
+    assert_eq!(forward.checksum.len(), 16);
+    assert_eq!(forward.checksum, checksum("reverse").await.checksum);
+
+    let mut changed = points(&["bb"]);
+    changed[0].payload_json = r#"{"name":"BB"}"#.into();
+    svc.upsert(Request::new(UpsertRequest { collection: "reverse".into(), points: changed, dry_run: false }))
+        .await
+        .expect("upsert");
+    svc.upsert(Request::new(UpsertRequest { collection: "forward".into(), points: points(&["bb"]), dry_run: false }))
+        .await
+        .expect("upsert");
+    assert_ne!(checksum("forward").await.checksum, checksum("reverse").await.checksum);
+}
+
+#[tokio::test]
+#[serial]
+async fn warmup_reads_every_collection() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    for (name, count) in [("first", 3usize), ("second", 5)] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims: 4,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+        svc.upsert(Request::new(UpsertRequest {
+            collection: name.into(),
+            points: (0..count)
+                .map(|i| Point { id: format!("p{i}"), vector: vec![i as f32; 4], payload_json: "{}".into(), ttl_ms: 0 })
+                .collect(),
+            dry_run: false,
+        }))
+        .await
+        .expect("upsert");
+    }
+
+    let all = svc.warmup(Request::new(WarmupRequest { collection: String::new() })).await.expect("warmup").into_inner();
+    assert_eq!(all.points, 8);
+    // 4 floats, a two-byte id and a two-byte payload per point.
+    assert_eq!(all.bytes, 8 * (4 * 4 + 2 + 2));
+    assert!(all.elapsed_ms >= 0.0);
+
+    let one = svc.warmup(Request::new(WarmupRequest { collection: "second".into() })).await.expect("warmup").into_inner();
+    assert_eq!(one.points, 5);
+
+    let err = svc.warmup(Request::new(WarmupRequest { collection: "missing".into() })).await.expect_err("missing");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+#[serial]
+async fn upserts_are_refused_while_wal_is_backlogged() {
+    let tmp = tempdir().expect("tempdir");
+    let wal_path = tmp.path().join("wal.log");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        wal_backlog_limit_bytes: 300,
+        wal_buffer_bytes: 4096,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "ingest".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let upsert = |i: usize| {
+        svc.upsert(Request::new(UpsertRequest {
+            collection: "ingest".into(),
+            points: vec![Point { id: format!("p{i}"), vector: vec![i as f32, 0.0], payload_json: String::new(), ttl_ms: 0 }],
+            dry_run: false,
+        }))
+    };
+
+    // Nothing reaches the file until 4 KiB are buffered, so the backlog grows
+    // as it would behind a slow disk.
+    let mut accepted = 0;
+    let err = loop {
+        match upsert(accepted).await {
+            Ok(_) => accepted += 1,
+            Err(status) => break status,
+        }
+        assert!(accepted < 100, "backpressure never kicked in");
+    };
+    assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+    assert!(accepted >= 1);
+    assert!(state.wal.as_ref().expect("wal").backlog_bytes() > 300);
+    // Dry runs write nothing and stay available.
+    svc.upsert(Request::new(UpsertRequest { collection: "ingest".into(), points: vec![], dry_run: true }))
+        .await
+        .expect("dry run");
+
+    // Once the backlog is flushed, upserts resume.
+    svc.flush(Request::new(FlushRequest {})).await.expect("flush");
+    assert_eq!(state.wal.as_ref().expect("wal").backlog_bytes(), 0);
+    upsert(accepted).await.expect("upsert after drain");
+    assert_eq!(state.catalog.total_points(), accepted + 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn explain_attaches_metric_raw_score_and_filter_matches() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "tuned".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "tuned".into(),
+        points: vec![
+            Point { id: "near".into(), vector: vec![1.0, 1.0], payload_json: r#"{"lang":"en","n":3}"#.into(), ttl_ms: 0 },
+            Point { id: "far".into(), vector: vec![4.0, 5.0], payload_json: r#"{"lang":"en","n":3}"#.into(), ttl_ms: 0 },
+            Point { id: "other".into(), vector: vec![1.0, 1.0], payload_json: r#"{"lang":"de"}"#.into(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+    let query = |explain: bool, metric_override: &str| QueryRequest {
+        collection: "tuned".into(),
+        vector: vec![1.0, 2.0],
+        top_k: Some(5),
+        metric_override: metric_override.into(),
+        with_payloads: Some(false),
+        filters: vec![Filter { key: "lang".into(), equals: "en".into() }, Filter { key: "n".into(), equals: "3".into() }],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+
+    let hits = svc.query(Request::new(query(true, ""))).await.expect("query").into_inner().hits;
+    assert_eq!(hits.len(), 2);
+    let explanation = hits[0].explanation.as_ref().expect("explanation");
+    assert_eq!(explanation.metric, "l2");
+    // Squared distance from (1,2) to (1,1).
+    assert_eq!(explanation.raw, 1.0);
+    assert_eq!(explanation.score, hits[0].score);
+    let matched: Vec<(&str, &str)> =
+        explanation.matched_filters.iter().map(|f| (f.key.as_str(), f.equals.as_str())).collect();
+    assert_eq!(matched, vec![("lang", "en"), ("n", "3")]);
+
+    let ip = svc.query(Request::new(query(true, "ip"))).await.expect("query").into_inner().hits;
+    let explanation = ip[0].explanation.as_ref().expect("explanation");
+    assert_eq!((explanation.metric.as_str(), explanation.raw), ("ip", ip[0].score));
+
+    let plain = svc.query(Request::new(query(false, ""))).await.expect("query").into_inner().hits;
+    assert!(plain.iter().all(|h| h.explanation.is_none()));
+}
+
+#[tokio::test]
+#[serial]
+async fn boosts_lift_matching_points_without_excluding_others() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "boosted".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    // "plain" and "promoted" are equally far from the query; "nearest" is closer.
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "boosted".into(),
+        points: vec![
+            Point { id: "plain".into(), vector: vec![1.0, 0.0], payload_json: r#"{"tier":"basic"}"#.into(), ttl_ms: 0 },
+            Point { id: "promoted".into(), vector: vec![-1.0, 0.0], payload_json: r#"{"tier":"gold"}"#.into(), ttl_ms: 0 },
+            Point { id: "nearest".into(), vector: vec![0.0, 0.5], payload_json: "{}".into(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+    let query = |boosts: Vec<ScoreBoost>| QueryRequest {
+        collection: "boosted".into(),
+        vector: vec![0.0, 0.0],
+        top_k: Some(3),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: true,
+        boosts,
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+    let gold = |boost: f32| ScoreBoost { filters: vec![Filter { key: "tier".into(), equals: "gold".into() }], boost };
+
+    let hits = svc.query(Request::new(query(vec![gold(0.1)]))).await.expect("query").into_inner().hits;
+    let ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(ids, vec!["nearest", "promoted", "plain"]);
+    assert_eq!(hits[1].score, -1.0 + 0.1);
+    assert_eq!(hits[2].score, -1.0);
+    let explanation = hits[1].explanation.as_ref().expect("explanation");
+    assert_eq!((explanation.raw, explanation.boost), (1.0, 0.1));
+    assert_eq!(hits[2].explanation.as_ref().expect("explanation").boost, 0.0);
+
+    // A large enough boost outranks a genuinely closer point; boosts add up.
+    let hits = svc.query(Request::new(query(vec![gold(0.5), gold(0.5)]))).await.expect("query").into_inner().hits;
+    assert_eq!(hits[0].id, "promoted");
+    assert_eq!(hits.len(), 3);
+
+    let err = svc
+        .query(Request::new(query(vec![ScoreBoost { filters: vec![], boost: 1.0 }])))
+        .await
+        .expect_err("boost without filters");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+#[serial]
+async fn by_id_payload_store_joins_payloads_back_into_hits() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    let create = |name: &str, payload_store: &str| CreateCollectionRequest {
+        name: name.into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: "blocked".into(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: payload_store.into(),
+        id_type: String::new(),
+    };
+    svc.create_collection(Request::new(create("docs", "by_id"))).await.expect("create docs");
+    svc.create_collection(Request::new(create("archive", ""))).await.expect("create archive");
+    let err = svc
+        .create_collection(Request::new(create("bad", "columnar")))
+        .await
+        .expect_err("unknown payload store");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let points = (0..10)
+        .map(|i| Point {
+            id: format!("doc-{i}"),
+            vector: vec![i as f32, 0.0],
+            payload_json: format!(r#"{{"n":{i}}}"#),
+            ttl_ms: 0,
+        })
+        .collect();
+    svc.upsert(Request::new(UpsertRequest { collection: "docs".into(), points, dry_run: false }))
+        .await
+        .expect("upsert");
+    // Moving points out compacts the vector rows; the payload map is only pruned.
+    svc.move_points(Request::new(MovePointsRequest {
+        source: "docs".into(),
+        target: "archive".into(),
+        ids: vec!["doc-0".into(), "doc-2".into(), "doc-5".into()],
+    }))
+    .await
+    .expect("move");
+
+    let hits_of = |svc: VectorDbService, collection: &'static str| async move {
+        let hits = svc
+            .query(Request::new(QueryRequest {
+                collection: collection.into(),
+                vector: vec![0.0, 0.0],
+                top_k: Some(10),
+                metric_override: String::new(),
+                with_payloads: Some(true),
+                filters: vec![],
+                exclude_ids: vec![],
+                hybrid: None,
+                include_ids: vec![],
+                explain: false,
+                boosts: vec![],
+                normalize_scores: false,
+                centroid_ids: vec![],
+                max_scan: 0,
+                group_by: String::new(),
+                per_group_limit: 0,
+                dedup_by: String::new(),
+                report_distance: false,
+                explain_dimensions: false,
+            }))
+            .await
+            .expect("query")
+            .into_inner()
+            .hits;
+        hits.into_iter().map(|h| (h.id, h.payload_json)).collect::<Vec<_>>()
+    };
+    let expected = |ns: &[usize]| -> Vec<(String, String)> {
+        ns.iter().map(|n| (format!("doc-{n}"), format!(r#"{{"n":{n}}}"#))).collect()
+    };
+    assert_eq!(hits_of(svc.clone(), "docs").await, expected(&[1, 3, 4, 6, 7, 8, 9]));
+    assert_eq!(hits_of(svc.clone(), "archive").await, expected(&[0, 2, 5]));
+
+    let described = svc
+        .describe_collection(Request::new(DescribeCollectionRequest { collection: "docs".into() }))
+        .await
+        .expect("describe")
+        .into_inner();
+    assert_eq!(described.payload_store, "by_id");
+
+    svc.state.flush_wal().expect("flush wal");
+    let replayed = VectorDbService {
+        state: Arc::new(DbState::with_config(DbStateConfig {
+            wal_path: Some(wal_path),
+            enable_wal: true,
+            ..DbStateConfig::default()
+        })),
+        metrics: None,
+    };
+    assert_eq!(hits_of(replayed, "docs").await, expected(&[1, 3, 4, 6, 7, 8, 9]));
+}
+
+#[tokio::test]
+#[serial]
+async fn empty_query_vector_is_handled_alike_by_library_and_grpc() {
+    for policy in [EmptyQuery::Reject, EmptyQuery::NoHits] {
+        let state = Arc::new(DbState::with_config(DbStateConfig {
+            enable_wal: false,
+            wal_path: None,
+            empty_query: policy,
+            ..DbStateConfig::default()
+        }));
+        let client = EmbeddedClient::new(state.clone());
+        client.create_collection("docs", 2, "l2").await.expect("create");
+        client.upsert("docs", vec![point("a", vec![1.0, 0.0], "{}")]).await.expect("upsert");
+
+        let library = state
+            .catalog
+            .get("docs")
+            .expect("handle")
+            .search(vec![], 5, None, vec![], IdFilter::default(), None, vec![], None, None)
+            .map(|(hits, _)| hits.len());
+        let grpc = client.query("docs", vec![], 5).await.map(|hits| hits.len());
+        match policy {
+            EmptyQuery::Reject => {
+                assert_eq!(library, None);
+                assert_eq!(grpc.expect_err("rejected").code(), tonic::Code::InvalidArgument);
+            }
+            EmptyQuery::NoHits => {
+                assert_eq!(library, Some(0));
+                assert_eq!(grpc.expect("no hits"), 0);
+            }
+        }
+
+        let missing = client.query("ghost", vec![], 5).await.expect_err("missing collection");
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn centroid_query_matches_query_by_averaged_vector() {
+    let state = Arc::new(DbState::with_config(DbStateConfig { enable_wal: false, wal_path: None, ..DbStateConfig::default() }));
+    let client = EmbeddedClient::new(state.clone());
+    let svc = VectorDbService { state, metrics: None };
+    client.create_collection("docs", 2, "l2").await.expect("create");
+    client
+        .upsert(
+            "docs",
+            vec![
+                point("a", vec![0.0, 0.0], "{}"),
+                point("b", vec![4.0, 2.0], "{}"),
+                point("c", vec![2.0, 1.5], "{}"),
+                point("d", vec![-3.0, 5.0], "{}"),
+            ],
+        )
+        .await
+        .expect("upsert");
+    let query = |vector: Vec<f32>, centroid_ids: Vec<String>| QueryRequest {
+        collection: "docs".into(),
+        vector,
+        top_k: Some(4),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids,
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+    let scored = |hits: Vec<vectaraft::pb::vectordb::v1::ScoredPoint>| -> Vec<(String, f32)> {
+        hits.into_iter().map(|h| (h.id, h.score)).collect()
+    };
+
+    let by_centroid = svc
+        .query(Request::new(query(vec![], vec!["a".into(), "b".into()])))
+        .await
+        .expect("centroid query")
+        .into_inner()
+        .hits;
+    let by_vector = svc.query(Request::new(query(vec![2.0, 1.0], vec![]))).await.expect("query").into_inner().hits;
+    assert_eq!(by_centroid[0].id, "c");
+    assert_eq!(scored(by_centroid), scored(by_vector));
+
+    let missing = svc
+        .query(Request::new(query(vec![], vec!["a".into(), "ghost".into()])))
+        .await
+        .expect_err("unknown id");
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+    assert!(missing.message().contains("ghost"));
+    let both = svc
+        .query(Request::new(query(vec![1.0, 1.0], vec!["a".into()])))
+        .await
+        .expect_err("vector and centroid ids");
+    assert_eq!(both.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+#[serial]
+async fn delete_collections_by_prefix_drops_only_matching_names() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state.clone());
+    let svc = VectorDbService { state, metrics: None };
+    for name in ["t1_a", "t1_b", "t2_a"] {
+        client.create_collection(name, 2, "l2").await.expect("create");
+        client.upsert(name, vec![point("p", vec![1.0, 0.0], "{}")]).await.expect("upsert");
+    }
+
+    let err = svc
+        .delete_collections_by_prefix(Request::new(DeleteCollectionsByPrefixRequest { prefix: String::new() }))
+        .await
+        .expect_err("empty prefix");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert_eq!(svc.state.catalog.len(), 3);
+
+    let resp = svc
+        .delete_collections_by_prefix(Request::new(DeleteCollectionsByPrefixRequest { prefix: "t1_".into() }))
+        .await
+        .expect("delete")
+        .into_inner();
+    assert_eq!(resp.deleted, 2);
+    assert_eq!(resp.collections, vec!["t1_a", "t1_b"]);
+    assert_eq!(svc.state.catalog.names(), vec!["t2_a"]);
+
+    svc.state.flush_wal().expect("flush wal");
+    let replayed = DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path),
+        enable_wal: true,
+        ..DbStateConfig::default()
+    });
+    assert_eq!(replayed.catalog.names(), vec!["t2_a"]);
+    assert_eq!(replayed.catalog.get("t2_a").and_then(|h| h.len()), Some(1));
+}
+
+#[tokio::test]
+#[serial]
+async fn create_collection_enforces_max_collections() {
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        enable_wal: false,
+        wal_path: None,
+        max_collections: 2,
+        ..DbStateConfig::default()
+    }));
+    let client = EmbeddedClient::new(state.clone());
+    let svc = VectorDbService { state, metrics: None };
+    client.create_collection("a", 2, "l2").await.expect("first");
+    client.create_collection("b", 2, "l2").await.expect("second");
+
+    let err = client.create_collection("c", 2, "l2").await.expect_err("over the limit");
+    assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+    assert!(svc.state.catalog.get("c").is_none());
+
+    // Re-creating an existing collection is not a new one.
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "a".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: true,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("if_not_exists at the limit");
+    assert_eq!(svc.state.catalog.len(), 2);
+}
+
+#[tokio::test]
+#[serial]
+async fn u64_id_collections_canonicalize_and_reject_non_integer_ids() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    let client = EmbeddedClient::new(state);
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "numbered".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: "u64".into(),
+    }))
+    .await
+    .expect("create collection");
+
+    let resp = client
+        .upsert("numbered", vec![point("7", vec![1.0, 0.0], r#"{"n":7}"#), point("0042", vec![0.0, 1.0], r#"{"n":42}"#)])
+        .await
+        .expect("upsert");
+    assert_eq!(resp.ids, vec!["7", "42"]);
+    for bad in ["p1", "", "-3"] {
+        let err = client.upsert("numbered", vec![point(bad, vec![1.0, 1.0], "{}")]).await.expect_err("non-integer id");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument, "id {bad:?}");
+    }
+
+    let hits = client.query("numbered", vec![0.0, 1.0], 2).await.expect("query");
+    let hits: Vec<(&str, &str)> = hits.iter().map(|h| (h.id.as_str(), h.payload_json.as_str())).collect();
+    assert_eq!(hits, vec![("42", r#"{"n":42}"#), ("7", r#"{"n":7}"#)]);
+    let described = svc
+        .describe_collection(Request::new(DescribeCollectionRequest { collection: "numbered".into() }))
+        .await
+        .expect("describe")
+        .into_inner();
+    assert_eq!((described.id_type.as_str(), described.points), ("u64", 2));
+
+    svc.state.flush_wal().expect("flush wal");
+    let replayed = DbState::with_config(DbStateConfig { wal_path: Some(wal_path), enable_wal: true, ..DbStateConfig::default() });
+    let handle = replayed.catalog.get("numbered").expect("replayed");
+    assert_eq!(handle.with_ref(|c| (c.index.id_kind(), c.index.len())), Some((IdKind::U64, 2)));
+}
+
+#[tokio::test]
+#[serial]
+async fn group_by_caps_hits_per_payload_value() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let svc = VectorDbService { state, metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "books".into(),
+        dims: 1,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    // Closest first: three by ann, two by bob, two without an author.
+    let point = |id: &str, x: f32, payload: &str| Point { id: id.into(), vector: vec![x], payload_json: payload.into(), ttl_ms: 0 };
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "books".into(),
+        points: vec![
+            point("ann1", 1.0, r#"{"author":"ann"}"#),
+            point("ann2", 2.0, r#"{"author":"ann"}"#),
+            point("ann3", 3.0, r#"{"author":"ann"}"#),
+            point("none1", 4.0, "{}"),
+            point("bob1", 5.0, r#"{"author":"bob"}"#),
+            point("none2", 6.0, r#"{"author":null}"#),
+            point("bob2", 7.0, r#"{"author":"bob"}"#),
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+    let query = |top_k: u32, group_by: &str, per_group_limit: u32| QueryRequest {
+        collection: "books".into(),
+        vector: vec![0.0],
+        top_k: Some(top_k),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: group_by.into(),
+        per_group_limit,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+    let ids = |hits: Vec<vectaraft::pb::vectordb::v1::ScoredPoint>| hits.into_iter().map(|h| h.id).collect::<Vec<_>>();
+
+    let hits = svc.query(Request::new(query(10, "author", 1))).await.expect("query").into_inner().hits;
+    assert_eq!(ids(hits), vec!["ann1", "none1", "bob1"]);
+    let hits = svc.query(Request::new(query(10, "author", 2))).await.expect("query").into_inner().hits;
+    assert_eq!(ids(hits), vec!["ann1", "ann2", "none1", "bob1", "none2", "bob2"]);
+    // top_k still bounds the total.
+    let hits = svc.query(Request::new(query(3, "author", 2))).await.expect("query").into_inner().hits;
+    assert_eq!(ids(hits), vec!["ann1", "ann2", "none1"]);
+
+    for (group_by, limit) in [("author", 0), ("", 2)] {
+        let err = svc.query(Request::new(query(3, group_by, limit))).await.expect_err("invalid grouping");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn shrink_to_fit_releases_capacity_left_by_deletes() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state.clone());
+    client.create_collection("shrink", 8, "l2").await.expect("create collection");
+    let points = (0..2000).map(|i| point(&format!("p{i}"), vec![i as f32; 8], "{}")).collect();
+    client.upsert("shrink", points).await.expect("upsert");
+    let handle = state.catalog.get("shrink").expect("collection");
+    let doomed: Vec<String> = (100..2000).map(|i| format!("p{i}")).collect();
+    handle.with_mut(|coll| coll.remove_points(&doomed)).expect("delete");
+    let (len, capacity) = handle.with_ref(|coll| (coll.index.vectors.len(), coll.index.vectors.capacity())).expect("sizes");
+    assert_eq!(len, 100 * 8);
+    assert!(capacity >= 2000 * 8);
+
+    let resp = client
+        .service()
+        .shrink_to_fit(Request::new(ShrinkToFitRequest { collection: "shrink".into() }))
+        .await
+        .expect("shrink")
+        .into_inner();
+    let (len, capacity) = handle.with_ref(|coll| (coll.index.vectors.len(), coll.index.vectors.capacity())).expect("sizes");
+    assert_eq!(len, 100 * 8);
+    assert!(capacity <= len + 8, "capacity {capacity} for {len} floats");
+    assert!(resp.bytes_freed >= (1900 * 8 * std::mem::size_of::<f32>()) as u64);
+    assert_eq!(client.query("shrink", vec![5.0; 8], 1).await.expect("query")[0].id, "p5");
+
+    let err = client
+        .service()
+        .shrink_to_fit(Request::new(ShrinkToFitRequest { collection: "nope".into() }))
+        .await
+        .expect_err("missing collection");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+#[serial]
+async fn dedup_by_keeps_the_best_hit_per_payload_value() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state);
+    client.create_collection("chunks", 1, "l2").await.expect("create collection");
+    // Closest first; doc "a" and doc "b" are each split into several chunks.
+    let chunks = vec![
+        point("a1", vec![1.0], r#"{"doc_id":"a"}"#),
+        point("a2", vec![2.0], r#"{"doc_id":"a"}"#),
+        point("b1", vec![3.0], r#"{"doc_id":"b"}"#),
+        point("loose1", vec![4.0], "{}"),
+        point("a3", vec![5.0], r#"{"doc_id":"a"}"#),
+        point("loose2", vec![6.0], "{}"),
+        point("b2", vec![7.0], r#"{"doc_id":"b"}"#),
+        point("c1", vec![8.0], r#"{"doc_id":7}"#),
+    ];
+    client.upsert("chunks", chunks).await.expect("upsert");
+    let query = |top_k: u32, dedup_by: &str, group_by: &str| QueryRequest {
+        collection: "chunks".into(),
+        vector: vec![0.0],
+        top_k: Some(top_k),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: group_by.into(),
+        per_group_limit: if group_by.is_empty() { 0 } else { 1 },
+        dedup_by: dedup_by.into(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+    let svc = client.service();
+    let ids = |hits: Vec<vectaraft::pb::vectordb::v1::ScoredPoint>| hits.into_iter().map(|h| h.id).collect::<Vec<_>>();
+
+    // One hit per doc; points without a doc_id are not duplicates of each other.
+    let hits = svc.query(Request::new(query(10, "doc_id", ""))).await.expect("query").into_inner().hits;
+    assert_eq!(ids(hits), vec!["a1", "b1", "loose1", "loose2", "c1"]);
+    // Deduplication happens before top_k is taken.
+    let hits = svc.query(Request::new(query(3, "doc_id", ""))).await.expect("query").into_inner().hits;
+    assert_eq!(ids(hits), vec!["a1", "b1", "loose1"]);
+
+    let err = svc.query(Request::new(query(3, "doc_id", "doc_id"))).await.expect_err("dedup with group_by");
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+#[serial]
+async fn upsert_is_all_or_nothing_when_the_wal_write_fails() {
+    let (state, wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state.clone());
+    client.create_collection("atomic", 2, "l2").await.expect("create collection");
+    client.upsert("atomic", vec![point("kept", vec![0.0, 0.0], "{}")]).await.expect("upsert");
+    let logged = std::fs::read(&wal_path).expect("read wal");
+
+    // A directory in place of the log makes the next write fail.
+    std::fs::remove_file(&wal_path).expect("remove wal");
+    std::fs::create_dir(&wal_path).expect("block wal");
+    let batch = vec![point("a", vec![1.0, 0.0], "{}"), point("b", vec![2.0, 0.0], "{}"), point("c", vec![3.0, 0.0], "{}")];
+    let err = client.upsert("atomic", batch.clone()).await.expect_err("wal write fails");
+    assert_eq!(err.code(), tonic::Code::Internal);
+    let handle = state.catalog.get("atomic").expect("collection");
+    assert_eq!(handle.len(), Some(1));
+    let wal = state.wal.as_ref().expect("wal");
+    assert_eq!((wal.buffered_bytes(), wal.record_count()), (0, 2));
+
+    // Once the log is writable again the same batch goes through whole.
+    std::fs::remove_dir(&wal_path).expect("unblock wal");
+    std::fs::write(&wal_path, logged).expect("restore wal");
+    client.upsert("atomic", batch).await.expect("upsert");
+    assert_eq!(handle.len(), Some(4));
+    drop(client);
+    drop(state);
+    let reopened = DbState::with_config(DbStateConfig { wal_path: Some(wal_path), enable_wal: true, ..DbStateConfig::default() });
+    assert_eq!(reopened.catalog.get("atomic").expect("collection").len(), Some(4));
+}
+
+#[tokio::test]
+#[serial]
+async fn sampled_queries_report_approximate_results() {
+    let (state, _wal_path, _tmp) = state_with_temp_wal();
+    let client = EmbeddedClient::new(state);
+    client.create_collection("sampled", 1, "l2").await.expect("create collection");
+    let points = (0..100).map(|i| point(&format!("p{i}"), vec![i as f32], "{}")).collect();
+    client.upsert("sampled", points).await.expect("upsert");
+    let query = |max_scan: u32| QueryRequest {
+        collection: "sampled".into(),
+        vector: vec![0.0],
+        top_k: Some(3),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+
+    // A full flat scan (or a cap it never reaches) is exact.
+    for max_scan in [0, 100] {
+        let response = client.service().query(Request::new(query(max_scan))).await.expect("query").into_inner();
+        assert!(!response.approximate);
+        assert_eq!(response.hits.len(), 3);
+        assert!(response.hits.iter().all(|h| h.exact));
+        assert_eq!(response.hits[0].id, "p0");
+    }
+
+    let response = client.service().query(Request::new(query(10))).await.expect("query").into_inner();
+    assert!(response.approximate);
+    assert!(!response.hits.is_empty() && response.hits.iter().all(|h| !h.exact));
+}
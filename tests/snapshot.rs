@@ -0,0 +1,389 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tempfile::tempdir;
+use tonic::Request;
+
+use vectaraft::pb::vectordb::v1::{
+    vector_db_server::VectorDb, CreateCollectionRequest, DeleteCollectionsByPrefixRequest, Point,
+    RestoreCollectionRequest, SnapshotCollectionRequest, UpsertRequest,
+};
+use vectaraft::server::grpc::VectorDbService;
+use vectaraft::server::snapshot;
+use vectaraft::server::state::{DbState, DbStateConfig};
+use vectaraft::storage::snapshot::{self as snapshot_file, SnapshotError, HEADER_LEN, VERSION};
+use vectaraft::storage::wal::{Wal, WalRecord};
+
+fn config_in(dir: &std::path::Path) -> DbStateConfig {
+    let mut config = DbStateConfig { enable_wal: true, read_only: false, ..DbStateConfig::default() };
+    config.apply_data_dir(dir).expect("data dir");
+    config
+}
+
+fn upsert(id: &str, vector: Vec<f32>) -> Request<UpsertRequest> {
+    Request::new(UpsertRequest {
+        collection: "snap".into(),
+        points: vec![Point { id: id.into(), vector, payload_json: String::new(), ttl_ms: 0 }],
+        dry_run: false,
+    })
+}
+
+#[tokio::test]
+async fn scheduler_writes_snapshot_and_compacts_wal() {
+    let tmp = tempdir().expect("tempdir");
+    let state = Arc::new(DbState::with_config(config_in(tmp.path())));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "snap".into(),
+        dims: 2,
+        metric: "ip".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(upsert("before", vec![1.0, 0.0])).await.expect("upsert");
+
+    let wal_path = tmp.path().join("wal");
+    let snapshot_path = tmp.path().join("snapshot");
+    assert!(std::fs::metadata(&wal_path).expect("wal").len() > 0);
+
+    let task = snapshot::spawn(state.clone(), Duration::from_millis(50));
+    let mut written = false;
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        if std::fs::metadata(&snapshot_path).is_ok_and(|m| m.len() > 0) {
+            written = true;
+            break;
+        }
+    }
+    task.abort();
+    assert!(written, "no snapshot written within the interval");
+    assert!(Wal::open(&wal_path).expect("wal").replay().expect("replay").is_empty());
+
+    // Writes after the snapshot stay in the WAL and are replayed on top of it.
+    svc.upsert(upsert("after", vec![0.0, 1.0])).await.expect("upsert");
+    drop(svc);
+    drop(state);
+
+    let reopened = DbState::with_config(config_in(tmp.path()));
+    let handle = reopened.catalog.get("snap").expect("collection restored");
+    assert_eq!(handle.len(), Some(2));
+    assert_eq!(handle.metric(), Some(vectaraft::types::Metric::IP));
+}
+
+#[test]
+fn save_snapshot_reports_contents_and_refuses_when_read_only() {
+    let tmp = tempdir().expect("tempdir");
+    let state = DbState::with_config(config_in(tmp.path()));
+    let report = state.save_snapshot().expect("snapshot").expect("not skipped");
+    assert_eq!(report.collections, 0);
+
+    let read_only = DbState::with_config(DbStateConfig { read_only: true, ..config_in(tmp.path()) });
+    assert!(read_only.save_snapshot().is_err());
+}
+
+/// Writes a snapshot of one collection holding a single point and returns its path.
+async fn snapshot_with_one_point(dir: &std::path::Path) -> std::path::PathBuf {
+    let state = Arc::new(DbState::with_config(config_in(dir)));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "snap".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(upsert("only", vec![1.0, 2.0])).await.expect("upsert");
+    state.save_snapshot().expect("snapshot").expect("not skipped");
+    dir.join("snapshot")
+}
+
+#[tokio::test]
+async fn corrupted_snapshot_body_fails_crc_check() {
+    let tmp = tempdir().expect("tempdir");
+    let path = snapshot_with_one_point(tmp.path()).await;
+    assert_eq!(snapshot_file::read(&path).expect("valid snapshot").len(), 2);
+
+    let mut bytes = std::fs::read(&path).expect("read");
+    bytes[HEADER_LEN + 5] ^= 0x01;
+    std::fs::write(&path, &bytes).expect("write");
+    assert!(matches!(snapshot_file::read(&path), Err(SnapshotError::ChecksumMismatch { .. })));
+
+    bytes.truncate(bytes.len() - 3);
+    assert!(matches!(snapshot_file::decode(&bytes), Err(SnapshotError::Truncated { .. })));
+
+    // The rejected file is moved aside instead of being loaded.
+    let reopened = DbState::with_config(config_in(tmp.path()));
+    assert!(reopened.catalog.get("snap").is_none());
+    assert!(tmp.path().join("snapshot.rejected").exists());
+}
+
+#[tokio::test]
+async fn snapshot_with_other_version_is_rejected() {
+    let tmp = tempdir().expect("tempdir");
+    let path = snapshot_with_one_point(tmp.path()).await;
+
+    let mut bytes = std::fs::read(&path).expect("read");
+    bytes[8..12].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    match snapshot_file::decode(&bytes) {
+        Err(SnapshotError::UnsupportedVersion { found, expected }) => {
+            assert_eq!((found, expected), (VERSION + 1, VERSION));
+        }
+        other => panic!("expected version mismatch, got {other:?}"),
+    }
+
+    // Files from before the header existed start with a JSON record.
+    assert!(matches!(snapshot_file::decode(b"{\"type\":\"CreateCollection\"}\n"), Err(SnapshotError::BadMagic)));
+}
+
+#[tokio::test]
+async fn crash_before_wal_compaction_does_not_replay_covered_records() {
+    let tmp = tempdir().expect("tempdir");
+    let wal_path = tmp.path().join("wal");
+    let state = Arc::new(DbState::with_config(config_in(tmp.path())));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "snap".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(upsert("a", vec![1.0, 0.0])).await.expect("upsert");
+    svc.upsert(upsert("b", vec![0.0, 1.0])).await.expect("upsert");
+    state.flush_wal().expect("flush");
+
+    // Put the uncompacted log back, as if the process died right after the
+    // snapshot was renamed into place.
+    let uncompacted = std::fs::read(&wal_path).expect("wal");
+    state.save_snapshot().expect("snapshot").expect("not skipped");
+    std::fs::write(&wal_path, uncompacted).expect("restore wal");
+    drop(svc);
+    drop(state);
+
+    let reopened = Arc::new(DbState::with_config(config_in(tmp.path())));
+    assert!(reopened.wal_replay_counts.is_empty(), "{:?}", reopened.wal_replay_counts);
+    assert_eq!(reopened.catalog.get("snap").expect("collection").len(), Some(2));
+
+    // Records logged after the covered ones still replay.
+    let svc = VectorDbService { state: reopened.clone(), metrics: None };
+    svc.upsert(upsert("c", vec![1.0, 1.0])).await.expect("upsert");
+    reopened.flush_wal().expect("flush");
+    drop(svc);
+    drop(reopened);
+    let restarted = DbState::with_config(config_in(tmp.path()));
+    assert_eq!(restarted.wal_replay_counts.get("Upsert"), Some(&1));
+    assert_eq!(restarted.catalog.get("snap").expect("collection").len(), Some(3));
+}
+
+#[tokio::test]
+async fn in_memory_snapshot_restores_catalog_without_disk() {
+    let state = Arc::new(DbState::with_config(DbStateConfig { wal_path: None, enable_wal: false, ..DbStateConfig::default() }));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "snap".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: Some(7),
+        expected_count: 0,
+        layout: "blocked".into(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(upsert("kept", vec![1.0, 2.0])).await.expect("upsert");
+
+    let saved = state.snapshot_in_memory();
+    assert_eq!(saved.points(), 1);
+
+    svc.upsert(upsert("kept", vec![9.0, 9.0])).await.expect("overwrite");
+    svc.upsert(upsert("added", vec![3.0, 4.0])).await.expect("upsert");
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "scratch".into(),
+        dims: 3,
+        metric: String::new(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create scratch");
+
+    state.restore_in_memory(&saved);
+    assert_eq!(state.catalog.names(), vec!["snap".to_string()]);
+    let handle = state.catalog.get("snap").expect("snap");
+    let points: Vec<(String, Vec<f32>)> =
+        handle.iter_points().expect("view").iter().map(|(id, v, _)| (id.to_string(), v.to_vec())).collect();
+    assert_eq!(points, vec![("kept".to_string(), vec![1.0, 2.0])]);
+    assert_eq!(
+        handle.with_ref(|c| (c.parallel_threshold, c.index.layout)),
+        Some((Some(7), vectaraft::types::VectorLayout::Blocked))
+    );
+
+    // The snapshot can be restored again after further writes.
+    svc.upsert(upsert("added", vec![3.0, 4.0])).await.expect("upsert");
+    state.restore_in_memory(&saved);
+    assert_eq!(state.catalog.total_points(), 1);
+}
+
+#[tokio::test]
+async fn collection_snapshot_restores_only_that_collection() {
+    let tmp = tempdir().expect("tempdir");
+    let state = Arc::new(DbState::with_config(config_in(tmp.path())));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    for name in ["snap", "other"] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: name.into(),
+            dims: 2,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+    }
+    svc.upsert(upsert("a", vec![1.0, 2.0])).await.expect("upsert");
+    svc.upsert(upsert("b", vec![3.0, 4.0])).await.expect("upsert");
+
+    let saved = svc
+        .snapshot_collection(Request::new(SnapshotCollectionRequest { collection: "snap".into() }))
+        .await
+        .expect("snapshot collection")
+        .into_inner();
+    assert_eq!(saved.points, 2);
+    assert_eq!(std::path::PathBuf::from(&saved.path), tmp.path().join("collections").join("snap.snapshot"));
+
+    // Changes made after the snapshot are lost for "snap" only.
+    svc.upsert(upsert("c", vec![5.0, 6.0])).await.expect("upsert");
+    svc.delete_collections_by_prefix(Request::new(DeleteCollectionsByPrefixRequest { prefix: "snap".into() }))
+        .await
+        .expect("drop");
+    assert!(state.catalog.get("snap").is_none());
+
+    let restore = || RestoreCollectionRequest { collection: "snap".into() };
+    let restored = svc.restore_collection(Request::new(restore())).await.expect("restore").into_inner();
+    assert_eq!(restored.points, 2);
+    let ids = |state: &DbState| -> Vec<String> {
+        state.catalog.get("snap").expect("snap").iter_points().expect("view").iter().map(|(id, _, _)| id.to_string()).collect()
+    };
+    assert_eq!(ids(&state), vec!["a", "b"]);
+    assert_eq!(state.catalog.names().len(), 2);
+
+    let err = svc.restore_collection(Request::new(restore())).await.expect_err("already exists");
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+    let err = svc
+        .restore_collection(Request::new(RestoreCollectionRequest { collection: "other".into() }))
+        .await
+        .expect_err("never snapshotted");
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    // The restore is logged, so it survives a restart.
+    state.flush_wal().expect("flush wal");
+    assert_eq!(ids(&DbState::with_config(config_in(tmp.path()))), vec!["a", "b"]);
+
+    // Restores are held to the same limits as CreateCollection.
+    for (limits, code) in [
+        (DbStateConfig { max_dims: 1, ..config_in(tmp.path()) }, tonic::Code::InvalidArgument),
+        (DbStateConfig { max_collections: 1, ..config_in(tmp.path()) }, tonic::Code::ResourceExhausted),
+    ] {
+        let limited = Arc::new(DbState::with_config(limits));
+        limited.catalog.drop_collection("snap");
+        let svc = VectorDbService { state: limited.clone(), metrics: None };
+        let err = svc.restore_collection(Request::new(restore())).await.expect_err("over the limit");
+        assert_eq!(err.code(), code);
+        assert!(limited.catalog.get("snap").is_none());
+    }
+}
+
+#[tokio::test]
+async fn wal_retention_keeps_only_the_newest_records() {
+    let tmp = tempdir().expect("tempdir");
+    let config = || DbStateConfig { wal_retention_records: Some(5), ..config_in(tmp.path()) };
+    let state = Arc::new(DbState::with_config(config()));
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "snap".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+
+    let wal = state.wal.clone().expect("wal");
+    let ids: Vec<String> = (0..13).map(|i| format!("p{i}")).collect();
+    for id in &ids {
+        svc.upsert(upsert(id, vec![1.0, 2.0])).await.expect("upsert");
+        assert!(wal.record_count() <= 5, "{} records after {id}", wal.record_count());
+    }
+
+    // The create and the oldest upserts now live only in the snapshot.
+    let logged: Vec<String> = wal
+        .replay()
+        .expect("replay")
+        .into_iter()
+        .map(|rec| match rec {
+            WalRecord::Upsert { id, .. } => id,
+            other => panic!("unexpected record {other:?}"),
+        })
+        .collect();
+    assert!(!logged.is_empty() && logged.len() < ids.len(), "{logged:?}");
+    assert!(ids.ends_with(&logged), "{logged:?}");
+
+    drop(svc);
+    drop(state);
+    let reopened = DbState::with_config(config());
+    assert_eq!(reopened.catalog.get("snap").expect("collection").len(), Some(13));
+}
@@ -0,0 +1,662 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tonic::Request;
+
+use vectaraft::pb::vectordb::v1::{
+    vector_db_server::VectorDb,
+    CreateCollectionRequest,
+    DescribeCollectionRequest,
+    Filter,
+    Point,
+    QueryRequest,
+    TruncateCollectionRequest,
+    UpsertRequest,
+};
+use vectaraft::server::embedded::{point, EmbeddedClient};
+use vectaraft::server::grpc::VectorDbService;
+use vectaraft::server::inventory;
+use vectaraft::server::state::{DbState, DbStateConfig, Readiness};
+use vectaraft::storage::wal::{Wal, WalRecord};
+use vectaraft::telemetry::{self, Metrics, DEFAULT_LATENCY_BUCKETS};
+
+#[test]
+fn custom_latency_buckets_are_exported() {
+    let metrics = Metrics::new(&[0.0002, 0.003, 0.04]).expect("metrics");
+    metrics.observe_latency("Query", Duration::from_micros(150));
+
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("grpc_request_duration_seconds_bucket{method=\"Query\",le=\"0.0002\"} 1"));
+    assert!(body.contains("le=\"0.003\""));
+    assert!(body.contains("le=\"0.04\""));
+}
+
+#[test]
+fn invalid_latency_buckets_fall_back_to_defaults() {
+    for buckets in [&[0.5, 0.1][..], &[-1.0, 0.1][..], &[][..]] {
+        let metrics = Metrics::new(buckets).expect("metrics");
+        metrics.observe_latency("Ping", Duration::from_millis(1));
+
+        let body = metrics.encode().expect("encode");
+        let expected = format!("le=\"{}\"", DEFAULT_LATENCY_BUCKETS[0]);
+        assert!(body.contains(&expected), "missing default bucket in {body}");
+        assert!(!body.contains("le=\"-1\""));
+    }
+}
+
+async fn http_status(addr: SocketAddr, path: &str) -> u16 {
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let request = format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.expect("write request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.expect("read response");
+    response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .expect("status code")
+}
+
+#[tokio::test]
+async fn readyz_reports_readiness_flag() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("free port");
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let readiness = Readiness::default();
+    let server = telemetry::spawn(metrics, readiness.clone(), addr, telemetry::DEFAULT_METRICS_PATH);
+
+    let mut livez = 0;
+    for _ in 0..50 {
+        if TcpStream::connect(addr).await.is_ok() {
+            livez = http_status(addr, "/livez").await;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(livez, 200);
+    assert_eq!(http_status(addr, "/readyz").await, 503);
+
+    readiness.mark_ready();
+    assert_eq!(http_status(addr, "/readyz").await, 200);
+
+    server.abort();
+}
+
+async fn http_get(addr: SocketAddr, path: &str, accept: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let request = format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nAccept: {accept}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.expect("write request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.expect("read response");
+    response
+}
+
+#[tokio::test]
+async fn metrics_are_served_as_openmetrics_when_accepted() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("free port");
+    let metrics = Metrics::new(&[]).expect("metrics");
+    metrics.record_grpc_error("Ping", "INTERNAL");
+    metrics.observe_latency("Ping", Duration::from_millis(1));
+    let server = telemetry::spawn(metrics, Readiness::default(), addr, telemetry::DEFAULT_METRICS_PATH);
+    for _ in 0..50 {
+        if TcpStream::connect(addr).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let response = http_get(addr, "/metrics", "application/openmetrics-text;version=1.0.0,text/plain;q=0.5").await;
+    let (head, body) = response.split_once("\r\n\r\n").expect("http response");
+    assert!(head.to_lowercase().contains("content-type: application/openmetrics-text; version=1.0.0"), "{head}");
+    assert!(body.ends_with("# EOF\n"), "{body}");
+    assert!(body.contains("# TYPE grpc_errors counter\n"), "{body}");
+    assert!(body.contains("grpc_errors_total{code=\"INTERNAL\",method=\"Ping\"} 1.0\n"), "{body}");
+    assert!(body.contains("grpc_request_duration_seconds_bucket{method=\"Ping\",le=\"+Inf\"} 1.0\n"), "{body}");
+
+    let response = http_get(addr, "/metrics", "text/plain").await;
+    let (head, body) = response.split_once("\r\n\r\n").expect("http response");
+    assert!(!head.to_lowercase().contains("openmetrics"), "{head}");
+    assert!(!body.contains("# EOF"), "{body}");
+    assert!(body.contains("# TYPE grpc_errors_total counter\n"), "{body}");
+
+    server.abort();
+}
+
+#[test]
+fn namespace_prefixes_every_metric_name() {
+    let metrics = Metrics::with_namespace(&[], "vectaraft").expect("metrics");
+    metrics.observe_latency("Ping", Duration::from_millis(1));
+    metrics.record_grpc_error("Ping", "INTERNAL");
+
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("vectaraft_grpc_request_duration_seconds_bucket{method=\"Ping\""), "{body}");
+    assert!(body.contains("vectaraft_grpc_errors_total{code=\"INTERNAL\",method=\"Ping\"} 1"), "{body}");
+    assert!(!body.lines().any(|line| line.starts_with("grpc_")), "unprefixed metric in {body}");
+
+    assert!(Metrics::with_namespace(&[], "not a name").is_err());
+}
+
+#[test]
+fn wal_oldest_unflushed_age_tracks_the_buffer() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let wal = Wal::open(tmp.path().join("wal.log")).expect("open wal").with_buffer(1 << 20);
+    let metrics = Metrics::new(&[]).expect("metrics");
+    metrics.track_wal(wal.clone());
+    let gauge = |metrics: &Metrics| -> f64 {
+        let body = metrics.encode().expect("encode");
+        body.lines()
+            .find_map(|line| line.strip_prefix("wal_oldest_unflushed_seconds "))
+            .and_then(|value| value.parse().ok())
+            .expect("gauge exported")
+    };
+    assert_eq!(gauge(&metrics), 0.0);
+
+    let record = |ts_ms| WalRecord::Truncate { collection: "c".into(), ts_ms };
+    wal.append(&record(1)).expect("append");
+    std::thread::sleep(Duration::from_millis(30));
+    wal.append(&record(2)).expect("append");
+    // Measured from the first buffered record, not the latest.
+    assert!(gauge(&metrics) >= 0.03, "gauge {}", gauge(&metrics));
+
+    wal.flush().expect("flush");
+    assert_eq!(gauge(&metrics), 0.0);
+    assert_eq!(wal.oldest_unflushed_age(), None);
+}
+
+#[tokio::test]
+async fn metrics_are_served_on_configured_path() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("free port");
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let server = telemetry::spawn(metrics, Readiness::default(), addr, "/internal/prom");
+
+    let mut status = 0;
+    for _ in 0..50 {
+        if TcpStream::connect(addr).await.is_ok() {
+            status = http_status(addr, "/internal/prom").await;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, 200);
+    assert_eq!(http_status(addr, "/metrics").await, 404);
+    assert_eq!(http_status(addr, "/livez").await, 200);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn filter_rejections_are_counted_per_collection() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: Some(metrics.clone()) };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "selective".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "selective".into(),
+        points: (0..10)
+            .map(|i| Point {
+                id: format!("p{i}"),
+                vector: vec![i as f32, 0.0],
+                payload_json: format!("{{\"keep\":{}}}", i % 5 == 0),
+                ttl_ms: 0,
+            })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let hits = svc
+        .query(Request::new(QueryRequest {
+            collection: "selective".into(),
+            vector: vec![0.0, 0.0],
+            top_k: Some(10),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![Filter { key: "keep".into(), equals: "true".into() }],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+    assert_eq!(hits.len(), 2);
+
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("search_candidates_evaluated_total{collection=\"selective\"} 10"), "{body}");
+    assert!(body.contains("search_candidates_rejected_total{collection=\"selective\"} 8"), "{body}");
+}
+
+#[tokio::test]
+async fn upserted_and_deleted_points_are_counted() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: Some(metrics.clone()) };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "ingest".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    for batch in [3usize, 4] {
+        svc.upsert(Request::new(UpsertRequest {
+            collection: "ingest".into(),
+            points: (0..batch)
+                .map(|i| Point {
+                    id: format!("b{batch}-{i}"),
+                    vector: vec![i as f32, 1.0],
+                    payload_json: String::new(),
+                    ttl_ms: 0,
+                })
+                .collect(),
+            dry_run: false,
+        }))
+        .await
+        .expect("upsert");
+    }
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("points_upserted_total 7"), "{body}");
+    assert!(body.contains("points_deleted_total 0"), "{body}");
+
+    svc.truncate_collection(Request::new(TruncateCollectionRequest { collection: "ingest".into() }))
+        .await
+        .expect("truncate");
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("points_deleted_total 7"), "{body}");
+}
+
+#[tokio::test]
+async fn queries_and_upserts_are_counted_per_collection() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let client = EmbeddedClient::from_service(VectorDbService { state, metrics: Some(metrics.clone()) });
+    for name in ["hot", "cold"] {
+        client.create_collection(name, 2, "l2").await.expect("create collection");
+    }
+    for i in 0..3 {
+        client.upsert("hot", vec![point(&format!("h{i}"), vec![i as f32, 0.0], ""), point("", vec![0.0, 1.0], "")])
+            .await
+            .expect("upsert");
+    }
+    client.upsert("cold", vec![point("c", vec![1.0, 1.0], "")]).await.expect("upsert");
+    for _ in 0..5 {
+        client.query("hot", vec![0.0, 0.0], 2).await.expect("query");
+    }
+    // Rejected requests are not counted.
+    client.query("hot", vec![0.0], 2).await.expect_err("wrong dimension");
+
+    let counts = |name: &'static str| {
+        let svc = client.service().clone();
+        async move {
+            let described = svc
+                .describe_collection(Request::new(DescribeCollectionRequest { collection: name.into() }))
+                .await
+                .expect("describe")
+                .into_inner();
+            (described.queries, described.upserts)
+        }
+    };
+    assert_eq!(counts("hot").await, (5, 3));
+    assert_eq!(counts("cold").await, (0, 1));
+
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains(r#"collection_queries_total{collection="hot"} 5"#), "{body}");
+    assert!(body.contains(r#"collection_upserts_total{collection="hot"} 3"#), "{body}");
+    assert!(body.contains(r#"collection_upserts_total{collection="cold"} 1"#), "{body}");
+    assert!(!body.contains(r#"collection_queries_total{collection="cold"}"#), "{body}");
+}
+
+#[tokio::test]
+async fn payload_sizes_are_observed_per_point() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: Some(metrics.clone()) };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "payloads".into(),
+        dims: 1,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    let big = format!("{{\"blob\":\"{}\"}}", "x".repeat(5000));
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "payloads".into(),
+        points: vec![
+            Point { id: "empty".into(), vector: vec![0.0], payload_json: String::new(), ttl_ms: 0 },
+            Point { id: "small".into(), vector: vec![1.0], payload_json: "{\"a\":1}".into(), ttl_ms: 0 },
+            Point { id: "big".into(), vector: vec![2.0], payload_json: big.clone(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("point_payload_bytes_count 3"), "{body}");
+    assert!(body.contains(&format!("point_payload_bytes_sum {}", 7 + big.len())), "{body}");
+    assert!(body.contains("point_payload_bytes_bucket{le=\"16\"} 2"), "{body}");
+    assert!(body.contains("point_payload_bytes_bucket{le=\"4096\"} 2"), "{body}");
+    assert!(body.contains("point_payload_bytes_bucket{le=\"16384\"} 3"), "{body}");
+}
+
+#[tokio::test]
+async fn inventory_gauges_are_refreshed_in_the_background() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: Some(metrics.clone()) };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "sized".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 100,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "sized".into(),
+        points: (0..5)
+            .map(|i| Point { id: format!("p{i}"), vector: vec![i as f32, 0.0], payload_json: String::new(), ttl_ms: 0 })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    // Writes leave the inventory gauges alone; only the refresh task sets them.
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("points_total 0"), "{body}");
+    assert!(!body.contains("index_len{"), "{body}");
+
+    let refresher = inventory::spawn(svc.state.clone(), metrics.clone(), Duration::from_millis(10));
+    let gauge = |body: &str, name: &str| -> Option<f64> {
+        body.lines().find_map(|line| line.strip_prefix(name)?.strip_prefix(' ')?.parse().ok())
+    };
+    let mut body = String::new();
+    for _ in 0..200 {
+        body = metrics.encode().expect("encode");
+        if gauge(&body, "points_total") == Some(5.0) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    refresher.abort();
+    assert_eq!(gauge(&body, "collections_total"), Some(1.0), "{body}");
+    assert_eq!(gauge(&body, "points_total"), Some(5.0), "{body}");
+    assert_eq!(gauge(&body, "index_len{collection=\"sized\"}"), Some(5.0), "{body}");
+    assert!(gauge(&body, "index_capacity{collection=\"sized\"}").is_some_and(|c| c >= 100.0), "{body}");
+}
+
+#[tokio::test]
+async fn max_scan_caps_the_points_a_query_scores() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: Some(metrics.clone()) };
+
+    for layout in ["row_major", "blocked"] {
+        svc.create_collection(Request::new(CreateCollectionRequest {
+            name: layout.into(),
+            dims: 1,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: layout.into(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        }))
+        .await
+        .expect("create collection");
+        svc.upsert(Request::new(UpsertRequest {
+            collection: layout.into(),
+            points: (0..1000)
+                .map(|i| Point { id: format!("p{i}"), vector: vec![i as f32], payload_json: String::new(), ttl_ms: 0 })
+                .collect(),
+            dry_run: false,
+        }))
+        .await
+        .expect("upsert");
+
+        let query = |max_scan: u32, include_ids: Vec<String>| QueryRequest {
+            collection: layout.into(),
+            vector: vec![500.0],
+            top_k: Some(1000),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids,
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        };
+        let hits = svc.query(Request::new(query(30, vec![]))).await.expect("query").into_inner().hits;
+        assert!(hits.len() <= 30, "{layout}: {} hits", hits.len());
+        let include: Vec<String> = (0..100).map(|i| format!("p{i}")).collect();
+        let hits = svc.query(Request::new(query(30, include))).await.expect("query").into_inner().hits;
+        assert!(hits.len() <= 30, "{layout}: {} hits", hits.len());
+        // A cap at or above the collection size scans everything.
+        let hits = svc.query(Request::new(query(5000, vec![]))).await.expect("query").into_inner().hits;
+        assert_eq!(hits.len(), 1000);
+    }
+
+    let body = metrics.encode().expect("encode");
+    for layout in ["row_major", "blocked"] {
+        let evaluated = body
+            .lines()
+            .find_map(|l| l.strip_prefix(&format!("search_candidates_evaluated_total{{collection=\"{layout}\"}} ")))
+            .expect("evaluated counter");
+        // 30 strided rows (stride 34), 25 of the included ids (stride 4), then all 1000.
+        assert_eq!(evaluated, "1055", "{layout}: {body}");
+    }
+}
+
+#[tokio::test]
+async fn query_hit_counts_are_observed() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: Some(metrics.clone()) };
+
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "small".into(),
+        dims: 1,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "small".into(),
+        points: (0..3)
+            .map(|i| Point { id: format!("p{i}"), vector: vec![i as f32], payload_json: String::new(), ttl_ms: 0 })
+            .collect(),
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+
+    let hits = svc
+        .query(Request::new(QueryRequest {
+            collection: "small".into(),
+            vector: vec![0.0],
+            top_k: Some(10),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+    assert_eq!(hits.len(), 3);
+
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("query_hits_count 1"), "{body}");
+    assert!(body.contains("query_hits_sum 3"), "{body}");
+    assert!(body.contains("query_hits_bucket{le=\"1\"} 0"), "{body}");
+    assert!(body.contains("query_hits_bucket{le=\"5\"} 1"), "{body}");
+}
+
+#[tokio::test]
+async fn error_responses_are_counted_by_method_and_code() {
+    let metrics = Metrics::new(&[]).expect("metrics");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: Some(metrics.clone()) };
+    let create = |name: &str, metric: &str| CreateCollectionRequest {
+        name: name.into(),
+        dims: 2,
+        metric: metric.into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    };
+
+    svc.create_collection(Request::new(create("ok", "l2"))).await.expect("create collection");
+    svc.create_collection(Request::new(create("bad", "manhattan"))).await.expect_err("unknown metric");
+    svc.create_collection(Request::new(create("ok", "l2"))).await.expect_err("duplicate");
+    for _ in 0..2 {
+        svc.upsert(Request::new(UpsertRequest { collection: "ghost".into(), points: vec![], dry_run: false }))
+            .await
+            .expect_err("missing collection");
+    }
+
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("grpc_errors_total{code=\"INVALID_ARGUMENT\",method=\"CreateCollection\"} 1"), "{body}");
+    assert!(body.contains("grpc_errors_total{code=\"ALREADY_EXISTS\",method=\"CreateCollection\"} 1"), "{body}");
+    assert!(body.contains("grpc_errors_total{code=\"NOT_FOUND\",method=\"Upsert\"} 2"), "{body}");
+    assert!(!body.contains("code=\"OK\""), "{body}");
+}
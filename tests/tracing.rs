@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+use opentelemetry_sdk::trace::TracerProvider;
+use tonic::Request;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use vectaraft::pb::vectordb::v1::{
+    vector_db_server::VectorDb,
+    CreateCollectionRequest,
+    Filter,
+    PingRequest,
+    Point,
+    QueryRequest,
+    UpsertRequest,
+};
+use vectaraft::server::grpc::VectorDbService;
+use vectaraft::server::request_id::REQUEST_ID_HEADER;
+use vectaraft::server::state::{DbState, DbStateConfig};
+use vectaraft::telemetry::otel;
+
+fn in_memory_state() -> Arc<DbState> {
+    Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }))
+}
+
+#[tokio::test]
+async fn query_emits_one_otel_span() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry().with(otel::layer(&provider));
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let svc = VectorDbService { state: in_memory_state(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "traced".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "traced".into(),
+        points: vec![
+            Point { id: "a".into(), vector: vec![0.0, 1.0], payload_json: String::new(), ttl_ms: 0 },
+            Point { id: "b".into(), vector: vec![1.0, 0.0], payload_json: String::new(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+    svc.query(Request::new(QueryRequest {
+        collection: "traced".into(),
+        vector: vec![1.0, 0.0],
+        top_k: Some(5),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    }))
+    .await
+    .expect("query");
+
+    provider.force_flush();
+    let spans = exporter.get_finished_spans().expect("finished spans");
+    let queries: Vec<_> = spans.iter().filter(|s| s.name == "Query").collect();
+    assert_eq!(queries.len(), 1);
+
+    let attr = |key: &str| {
+        queries[0]
+            .attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.as_str().into_owned())
+    };
+    assert_eq!(attr("collection").as_deref(), Some("traced"));
+    assert_eq!(attr("top_k").as_deref(), Some("5"));
+    assert_eq!(attr("hits").as_deref(), Some("2"));
+}
+
+#[derive(Default)]
+struct FieldMap(HashMap<String, String>);
+
+impl Visit for FieldMap {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+/// Events keyed by the name of the span they were emitted in.
+type CapturedEvents = Vec<(Option<String>, FieldMap)>;
+
+#[derive(Clone, Default)]
+struct Captured {
+    spans: Arc<Mutex<HashMap<u64, (String, FieldMap)>>>,
+    events: Arc<Mutex<CapturedEvents>>,
+}
+
+impl<S> Layer<S> for Captured
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = FieldMap::default();
+        attrs.record(&mut fields);
+        let name = attrs.metadata().name().to_string();
+        self.spans.lock().unwrap().insert(id.into_u64(), (name, fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some((_, fields)) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = FieldMap::default();
+        event.record(&mut fields);
+        let span = ctx.event_span(event).map(|s| s.name().to_string());
+        self.events.lock().unwrap().push((span, fields));
+    }
+}
+
+#[tokio::test]
+async fn rpc_spans_carry_request_fields() {
+    let captured = Captured::default();
+    let _default = tracing::subscriber::set_default(tracing_subscriber::registry().with(captured.clone()));
+
+    let svc = VectorDbService { state: in_memory_state(), metrics: None };
+    svc.create_collection(Request::new(CreateCollectionRequest {
+        name: "spans".into(),
+        dims: 2,
+        metric: "l2".into(),
+        if_not_exists: false,
+        parallel_threshold: None,
+        expected_count: 0,
+        layout: String::new(),
+        assume_normalized: false,
+        default_top_k: None,
+        default_with_payloads: false,
+        payload_store: String::new(),
+        id_type: String::new(),
+    }))
+    .await
+    .expect("create collection");
+    svc.upsert(Request::new(UpsertRequest {
+        collection: "spans".into(),
+        points: vec![
+            Point { id: "a".into(), vector: vec![0.0, 1.0], payload_json: "{\"tag\":\"x\"}".into(), ttl_ms: 0 },
+            Point { id: "b".into(), vector: vec![1.0, 0.0], payload_json: "{\"tag\":\"y\"}".into(), ttl_ms: 0 },
+            Point { id: "c".into(), vector: vec![1.0, 1.0], payload_json: "{\"tag\":\"x\"}".into(), ttl_ms: 0 },
+        ],
+        dry_run: false,
+    }))
+    .await
+    .expect("upsert");
+    svc.query(Request::new(QueryRequest {
+        collection: "spans".into(),
+        vector: vec![1.0, 0.0],
+        top_k: Some(7),
+        metric_override: String::new(),
+        with_payloads: Some(false),
+        filters: vec![Filter { key: "tag".into(), equals: "x".into() }],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    }))
+    .await
+    .expect("query");
+    let _ = svc
+        .query(Request::new(QueryRequest {
+            collection: "missing".into(),
+            vector: vec![1.0, 0.0],
+            top_k: Some(1),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        }))
+        .await
+        .expect_err("unknown collection");
+
+    let spans = captured.spans.lock().unwrap();
+    let field = |name: &str, key: &str| -> Vec<String> {
+        spans
+            .values()
+            .filter(|(n, _)| n == name)
+            .filter_map(|(_, f)| f.0.get(key).cloned())
+            .collect()
+    };
+    assert_eq!(field("Upsert", "points"), vec!["3"]);
+    assert!(field("Query", "method").iter().all(|m| m == "Query"));
+    assert!(field("Query", "top_k").contains(&"7".to_string()));
+    assert!(field("Query", "filters").contains(&"1".to_string()));
+    assert!(field("Query", "hits").contains(&"2".to_string()));
+
+    let events = captured.events.lock().unwrap();
+    let completed: Vec<_> = events
+        .iter()
+        .filter(|(span, f)| span.as_deref() == Some("Query") && f.0.get("message").map(String::as_str) == Some("rpc completed"))
+        .collect();
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed[0].1 .0.get("results").map(String::as_str), Some("2"));
+    assert!(completed[0].1 .0.contains_key("elapsed_ms"));
+
+    let failed = events
+        .iter()
+        .filter(|(span, f)| span.as_deref() == Some("Query") && f.0.get("message").map(String::as_str) == Some("rpc failed"))
+        .count();
+    assert_eq!(failed, 1);
+}
+
+#[tokio::test]
+async fn supplied_request_id_is_recorded_on_the_rpc_span() {
+    let captured = Captured::default();
+    let _default = tracing::subscriber::set_default(tracing_subscriber::registry().with(captured.clone()));
+
+    let svc = VectorDbService { state: in_memory_state(), metrics: None };
+    let mut req = Request::new(PingRequest {});
+    req.metadata_mut().insert(REQUEST_ID_HEADER, "trace-me-7".parse().expect("metadata value"));
+    svc.ping(req).await.expect("ping");
+
+    let spans = captured.spans.lock().unwrap();
+    let ids: Vec<_> = spans
+        .values()
+        .filter(|(name, _)| name == "Ping")
+        .filter_map(|(_, f)| f.0.get("request_id").cloned())
+        .collect();
+    assert_eq!(ids, vec!["trace-me-7"]);
+}
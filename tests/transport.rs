@@ -0,0 +1,674 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio_stream::StreamExt;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Channel, Server};
+
+use vectaraft::pb::vectordb::v1::{
+    vector_db_client::VectorDbClient,
+    CollectionChecksumRequest,
+    CreateCollectionRequest,
+    DescribeCollectionRequest,
+    DescribeCollectionResponse,
+    ExportAllRequest,
+    ExportRecord,
+    PingRequest,
+    Point,
+    QueryRequest,
+    UpsertRequest,
+};
+use vectaraft::server::grpc::{
+    bind_listener, build_runtime, error_info, KeepaliveConfig, VectorDbService, DEFAULT_MAX_MESSAGE_BYTES, ERROR_DOMAIN, REASON_COLLECTION_NOT_FOUND,
+    REASON_DIMENSION_MISMATCH,
+};
+use vectaraft::server::rate_limit::{RateLimit, RateLimiter, API_KEY_HEADER};
+use vectaraft::server::request_id::{self, REQUEST_ID_HEADER};
+use vectaraft::server::shutdown::{serve_with_drain, InFlight};
+use vectaraft::server::state::{DbState, DbStateConfig};
+
+/// tonic's built-in decode limit.
+const TONIC_DEFAULT_LIMIT: usize = 4 * 1024 * 1024;
+
+async fn start_server(max_message_bytes: usize) -> VectorDbClient<Channel> {
+    start_server_with_keepalive(max_message_bytes, KeepaliveConfig::default()).await
+}
+
+async fn start_server_with_keepalive(max_message_bytes: usize, keepalive: KeepaliveConfig) -> VectorDbClient<Channel> {
+    let addr: SocketAddr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("free port");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: None };
+    tokio::spawn(
+        keepalive
+            .apply(Server::builder())
+            .add_service(svc.into_server(max_message_bytes))
+            .serve(addr),
+    );
+
+    for _ in 0..50 {
+        if let Ok(client) = VectorDbClient::connect(format!("http://{addr}")).await {
+            return client;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("server did not start on {addr}");
+}
+
+/// Builds an upsert of roughly 5 MiB, just over tonic's default limit.
+fn large_upsert() -> UpsertRequest {
+    let dims = 1024;
+    UpsertRequest {
+        collection: "big".into(),
+        points: (0..1300)
+            .map(|i| Point {
+                id: format!("p{i}"),
+                vector: vec![i as f32; dims],
+                payload_json: String::new(),
+                ttl_ms: 0,
+            })
+            .collect(),
+        dry_run: false,
+    }
+}
+
+async fn create_big(client: &mut VectorDbClient<Channel>) {
+    client
+        .create_collection(CreateCollectionRequest {
+            name: "big".into(),
+            dims: 1024,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        })
+        .await
+        .expect("create collection");
+}
+
+#[tokio::test]
+async fn large_upsert_succeeds_with_raised_limit() {
+    let mut client = start_server(DEFAULT_MAX_MESSAGE_BYTES).await;
+    create_big(&mut client).await;
+
+    let upserted = client.upsert(large_upsert()).await.expect("upsert").into_inner().upserted;
+    assert_eq!(upserted, 1300);
+}
+
+#[tokio::test]
+async fn large_upsert_fails_with_default_limit() {
+    let mut client = start_server(TONIC_DEFAULT_LIMIT).await;
+    create_big(&mut client).await;
+
+    let err = client.upsert(large_upsert()).await.expect_err("message too large");
+    assert_eq!(err.code(), tonic::Code::OutOfRange);
+}
+
+#[tokio::test]
+async fn upsert_stream_applies_every_batch() {
+    let mut client = start_server(DEFAULT_MAX_MESSAGE_BYTES).await;
+    client
+        .create_collection(CreateCollectionRequest {
+            name: "stream".into(),
+            dims: 2,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        })
+        .await
+        .expect("create collection");
+
+    let batches: Vec<UpsertRequest> = (0..4)
+        .map(|b| UpsertRequest {
+            collection: "stream".into(),
+            points: (0..25)
+                .map(|i| Point {
+                    id: format!("b{b}-p{i}"),
+                    vector: vec![b as f32, i as f32],
+                    payload_json: String::new(),
+                    ttl_ms: 0,
+                })
+                .collect(),
+            dry_run: false,
+        })
+        .collect();
+
+    let upserted = client
+        .upsert_stream(tokio_stream::iter(batches))
+        .await
+        .expect("upsert stream")
+        .into_inner()
+        .upserted;
+    assert_eq!(upserted, 100);
+
+    let hits = client
+        .query(QueryRequest {
+            collection: "stream".into(),
+            vector: vec![3.0, 24.0],
+            top_k: Some(200),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        })
+        .await
+        .expect("query")
+        .into_inner()
+        .hits;
+    assert_eq!(hits.len(), 100);
+    assert_eq!(hits[0].id, "b3-p24");
+}
+
+#[tokio::test]
+async fn export_all_round_trips_through_a_file() {
+    use prost::Message;
+
+    let mut source = start_server(DEFAULT_MAX_MESSAGE_BYTES).await;
+    let collections = [
+        CreateCollectionRequest {
+            name: "docs".into(),
+            dims: 2,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        },
+        CreateCollectionRequest {
+            name: "tagged".into(),
+            dims: 3,
+            metric: "cosine".into(),
+            if_not_exists: false,
+            parallel_threshold: Some(64),
+            expected_count: 0,
+            layout: "blocked".into(),
+            assume_normalized: false,
+            default_top_k: Some(5),
+            default_with_payloads: true,
+            payload_store: String::new(),
+            id_type: "u64".into(),
+        },
+        CreateCollectionRequest {
+            name: "empty".into(),
+            dims: 4,
+            metric: "ip".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        },
+    ];
+    for create in collections.clone() {
+        source.create_collection(create).await.expect("create collection");
+    }
+    // More points than one import batch.
+    let docs = (0..1500)
+        .map(|i| Point {
+            id: format!("d{i}"),
+            vector: vec![i as f32, -(i as f32)],
+            payload_json: format!(r#"{{"n":{i}}}"#),
+            ttl_ms: 0,
+        })
+        .collect();
+    source.upsert(UpsertRequest { collection: "docs".into(), points: docs, dry_run: false }).await.expect("upsert");
+    let tagged = (0..40)
+        .map(|i| Point {
+            id: i.to_string(),
+            vector: vec![1.0, i as f32, 0.5],
+            payload_json: String::new(),
+            ttl_ms: if i % 2 == 0 { 0 } else { 3_600_000 },
+        })
+        .collect();
+    source.upsert(UpsertRequest { collection: "tagged".into(), points: tagged, dry_run: false }).await.expect("upsert");
+
+    // The client frames each record with its length, as a backup file would.
+    let dir = tempfile::tempdir().expect("tempdir");
+    let backup = dir.path().join("db.export");
+    let mut exported = source.export_all(ExportAllRequest {}).await.expect("export").into_inner();
+    let mut bytes = Vec::new();
+    while let Some(frame) = exported.next().await {
+        frame.expect("frame").encode_length_delimited(&mut bytes).expect("encode");
+    }
+    std::fs::write(&backup, bytes).expect("write backup");
+
+    let mut restored = start_server(DEFAULT_MAX_MESSAGE_BYTES).await;
+    let mut file = bytes::Bytes::from(std::fs::read(&backup).expect("read backup"));
+    let mut frames = Vec::new();
+    while !file.is_empty() {
+        frames.push(ExportRecord::decode_length_delimited(&mut file).expect("decode"));
+    }
+    let imported = restored.import_all(tokio_stream::iter(frames.clone())).await.expect("import").into_inner();
+    assert_eq!((imported.collections, imported.points), (3, 1540));
+
+    for create in &collections {
+        // Request counters are per process, so they are not compared.
+        let describe = |mut client: VectorDbClient<Channel>| async move {
+            let described = client
+                .describe_collection(DescribeCollectionRequest { collection: create.name.clone() })
+                .await
+                .expect("describe")
+                .into_inner();
+            DescribeCollectionResponse { queries: 0, upserts: 0, ..described }
+        };
+        assert_eq!(describe(source.clone()).await, describe(restored.clone()).await);
+        let checksum = |mut client: VectorDbClient<Channel>| async move {
+            client
+                .collection_checksum(CollectionChecksumRequest { collection: create.name.clone() })
+                .await
+                .expect("checksum")
+                .into_inner()
+        };
+        assert_eq!(checksum(source.clone()).await, checksum(restored.clone()).await);
+    }
+
+    // Importing over existing collections is refused.
+    let err = restored.import_all(tokio_stream::iter(frames)).await.expect_err("collections exist");
+    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+}
+
+#[tokio::test]
+async fn query_stream_matches_unary_query() {
+    let mut client = start_server(DEFAULT_MAX_MESSAGE_BYTES).await;
+    client
+        .create_collection(CreateCollectionRequest {
+            name: "ranked".into(),
+            dims: 2,
+            metric: "cosine".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        })
+        .await
+        .expect("create collection");
+    client
+        .upsert(UpsertRequest {
+            collection: "ranked".into(),
+            points: (0..500)
+                .map(|i| {
+                    let angle = i as f32 * 0.01;
+                    Point {
+                        id: format!("p{i}"),
+                        vector: vec![angle.cos(), angle.sin()],
+                        payload_json: format!("{{\"i\":{i}}}"),
+                        ttl_ms: 0,
+                    }
+                })
+                .collect(),
+            dry_run: false,
+        })
+        .await
+        .expect("upsert");
+
+    let request = QueryRequest {
+        collection: "ranked".into(),
+        vector: vec![0.0, 1.0],
+        top_k: Some(400),
+        metric_override: String::new(),
+        with_payloads: Some(true),
+        filters: vec![],
+        exclude_ids: vec![],
+        hybrid: None,
+        include_ids: vec![],
+        explain: false,
+        boosts: vec![],
+        normalize_scores: false,
+        centroid_ids: vec![],
+        max_scan: 0,
+        group_by: String::new(),
+        per_group_limit: 0,
+        dedup_by: String::new(),
+        report_distance: false,
+        explain_dimensions: false,
+    };
+
+    let unary = client.query(request.clone()).await.expect("query").into_inner().hits;
+    let mut stream = client.query_stream(request).await.expect("query stream").into_inner();
+    let mut streamed = Vec::new();
+    while let Some(hit) = stream.next().await {
+        streamed.push(hit.expect("streamed hit"));
+    }
+
+    assert_eq!(streamed.len(), 400);
+    assert_eq!(streamed, unary);
+}
+
+#[tokio::test]
+async fn errors_carry_machine_readable_reasons() {
+    let mut client = start_server(DEFAULT_MAX_MESSAGE_BYTES).await;
+
+    let missing = client
+        .query(QueryRequest {
+            collection: "nope".into(),
+            vector: vec![0.0; 3],
+            top_k: Some(1),
+            metric_override: String::new(),
+            with_payloads: Some(false),
+            filters: vec![],
+            exclude_ids: vec![],
+            hybrid: None,
+            include_ids: vec![],
+            explain: false,
+            boosts: vec![],
+            normalize_scores: false,
+            centroid_ids: vec![],
+            max_scan: 0,
+            group_by: String::new(),
+            per_group_limit: 0,
+            dedup_by: String::new(),
+            report_distance: false,
+            explain_dimensions: false,
+        })
+        .await
+        .expect_err("unknown collection");
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+    assert_eq!(missing.message(), "collection not found");
+    let info = error_info(&missing).expect("error info");
+    assert_eq!(info.reason, REASON_COLLECTION_NOT_FOUND);
+    assert_eq!(info.domain, ERROR_DOMAIN);
+
+    create_big(&mut client).await;
+    let wrong_dim = client
+        .upsert(UpsertRequest {
+            collection: "big".into(),
+            points: vec![Point { id: "short".into(), vector: vec![1.0; 3], payload_json: String::new(), ttl_ms: 0 }],
+            dry_run: false,
+        })
+        .await
+        .expect_err("dimension mismatch");
+    assert_eq!(wrong_dim.code(), tonic::Code::InvalidArgument);
+    assert!(wrong_dim.message().contains("expected 1024, got 3"), "{}", wrong_dim.message());
+    assert_eq!(error_info(&wrong_dim).expect("error info").reason, REASON_DIMENSION_MISMATCH);
+}
+
+#[tokio::test]
+async fn idle_connection_survives_keepalive_pings() {
+    // Short periods so several PING round-trips happen while the client idles;
+    // a client that failed to ack would be disconnected after the timeout.
+    let keepalive = KeepaliveConfig {
+        http2_interval: Some(Duration::from_millis(200)),
+        http2_timeout: Some(Duration::from_millis(200)),
+        tcp: Some(Duration::from_secs(1)),
+    };
+    let mut client = start_server_with_keepalive(DEFAULT_MAX_MESSAGE_BYTES, keepalive).await;
+    client.ping(PingRequest {}).await.expect("ping before idling");
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    client.ping(PingRequest {}).await.expect("ping after idling");
+}
+
+#[test]
+fn server_runs_on_custom_worker_threads_and_backlog() {
+    let runtime = build_runtime(Some(3)).expect("runtime");
+    assert_eq!(runtime.metrics().num_workers(), 3);
+
+    runtime.block_on(async {
+        let listener = bind_listener("127.0.0.1:0".parse().unwrap(), 16).expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        let state = Arc::new(DbState::with_config(DbStateConfig {
+            wal_path: None,
+            enable_wal: false,
+            ..DbStateConfig::default()
+        }));
+        let svc = VectorDbService { state, metrics: None };
+        let incoming = TcpIncoming::from_listener(listener, true, None).expect("incoming");
+        tokio::spawn(
+            Server::builder()
+                .add_service(svc.into_server(DEFAULT_MAX_MESSAGE_BYTES))
+                .serve_with_incoming(incoming),
+        );
+
+        let mut client = VectorDbClient::connect(format!("http://{addr}")).await.expect("connect");
+        client.ping(PingRequest {}).await.expect("ping");
+    });
+}
+
+#[tokio::test]
+async fn shutdown_cuts_off_requests_still_running_after_drain_timeout() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let wal_path = tmp.path().join("wal.log");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: Some(wal_path.clone()),
+        enable_wal: true,
+        wal_buffer_bytes: 1 << 20,
+        ..DbStateConfig::default()
+    }));
+    let listener = bind_listener("127.0.0.1:0".parse().unwrap(), 16).expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let incoming = TcpIncoming::from_listener(listener, true, None).expect("incoming");
+    let in_flight = InFlight::default();
+    let svc = VectorDbService { state: state.clone(), metrics: None };
+    let router = Server::builder().add_service(in_flight.track(svc.into_server(DEFAULT_MAX_MESSAGE_BYTES)));
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let drain_timeout = Duration::from_millis(300);
+    let server = tokio::spawn({
+        let in_flight = in_flight.clone();
+        async move {
+            let signal = async {
+                let _ = stop_rx.await;
+            };
+            serve_with_drain(router, incoming, signal, drain_timeout, &in_flight, &state).await
+        }
+    });
+
+    let mut client = VectorDbClient::connect(format!("http://{addr}")).await.expect("connect");
+    client
+        .create_collection(CreateCollectionRequest {
+            name: "draining".into(),
+            dims: 2,
+            metric: "l2".into(),
+            if_not_exists: false,
+            parallel_threshold: None,
+            expected_count: 0,
+            layout: String::new(),
+            assume_normalized: false,
+            default_top_k: None,
+            default_with_payloads: false,
+            payload_store: String::new(),
+            id_type: String::new(),
+        })
+        .await
+        .expect("create collection");
+
+    // An upload stream whose client never finishes it keeps the handler running.
+    let (batch_tx, batch_rx) = tokio::sync::mpsc::channel::<UpsertRequest>(1);
+    let stuck = tokio::spawn(async move {
+        client.upsert_stream(tokio_stream::wrappers::ReceiverStream::new(batch_rx)).await
+    });
+    batch_tx
+        .send(UpsertRequest {
+            collection: "draining".into(),
+            points: vec![Point { id: "p".into(), vector: vec![1.0, 2.0], payload_json: String::new(), ttl_ms: 0 }],
+            dry_run: false,
+        })
+        .await
+        .expect("send batch");
+    for _ in 0..100 {
+        if in_flight.count() == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(in_flight.count(), 1);
+
+    let started = std::time::Instant::now();
+    stop_tx.send(()).expect("signal shutdown");
+    let cut_off = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("shutdown hung past the drain timeout")
+        .expect("server task")
+        .expect("serve");
+    assert!(started.elapsed() >= drain_timeout);
+    assert!(started.elapsed() < drain_timeout + Duration::from_secs(2));
+    assert_eq!(cut_off, 1);
+
+    let status = stuck.await.expect("client task").expect_err("stuck request should fail");
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+    drop(batch_tx);
+
+    // The buffered create-collection record reached disk.
+    let wal = std::fs::read_to_string(&wal_path).expect("read wal");
+    assert!(wal.contains("draining"), "{wal}");
+}
+
+#[tokio::test]
+async fn rate_limit_throttles_each_api_key_separately() {
+    let addr: SocketAddr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("free port");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: None };
+    // Effectively no refill during the test: each key gets its burst only.
+    let limiter = RateLimiter::new(Some(RateLimit { per_second: 0.001, burst: 3.0 }));
+    tokio::spawn(
+        Server::builder()
+            .add_service(InterceptedService::new(svc.into_server(DEFAULT_MAX_MESSAGE_BYTES), limiter))
+            .serve(addr),
+    );
+    let mut client = None;
+    for _ in 0..50 {
+        if let Ok(connected) = VectorDbClient::connect(format!("http://{addr}")).await {
+            client = Some(connected);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let mut client = client.expect("server did not start");
+    let ping = |key: Option<&'static str>| {
+        let mut req = tonic::Request::new(PingRequest {});
+        if let Some(key) = key {
+            req.metadata_mut().insert(API_KEY_HEADER, key.parse().expect("metadata value"));
+        }
+        let mut client = client.clone();
+        async move { client.ping(req).await.map(|_| ()).map_err(|status| status.code()) }
+    };
+
+    for _ in 0..3 {
+        ping(Some("tenant-a")).await.expect("within burst");
+    }
+    assert_eq!(ping(Some("tenant-a")).await, Err(tonic::Code::ResourceExhausted));
+
+    // Other keys, and keyless requests, draw from their own buckets.
+    for _ in 0..3 {
+        ping(Some("tenant-b")).await.expect("tenant-b unaffected");
+        ping(None).await.expect("global bucket unaffected");
+    }
+    assert_eq!(ping(None).await, Err(tonic::Code::ResourceExhausted));
+    assert_eq!(ping(Some("tenant-a")).await, Err(tonic::Code::ResourceExhausted));
+    client.ping(PingRequest {}).await.expect_err("still throttled");
+}
+
+#[test]
+fn rate_limit_buckets_refill_up_to_burst() {
+    let limiter = RateLimiter::new(Some(RateLimit { per_second: 50.0, burst: 2.0 }));
+    assert!(limiter.try_acquire(Some("k")));
+    assert!(limiter.try_acquire(Some("k")));
+    assert!(!limiter.try_acquire(Some("k")));
+
+    // 200ms refills 10 tokens' worth, capped at the burst of 2.
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(limiter.try_acquire(Some("k")));
+    assert!(limiter.try_acquire(Some("k")));
+    assert!(!limiter.try_acquire(Some("k")));
+
+    let unlimited = RateLimiter::new(None);
+    assert!((0..1_000).all(|_| unlimited.try_acquire(None)));
+}
+
+#[tokio::test]
+async fn request_ids_are_echoed_or_generated() {
+    let addr: SocketAddr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("free port");
+    let state = Arc::new(DbState::with_config(DbStateConfig {
+        wal_path: None,
+        enable_wal: false,
+        ..DbStateConfig::default()
+    }));
+    let svc = VectorDbService { state, metrics: None };
+    tokio::spawn(
+        Server::builder()
+            .add_service(request_id::tag(svc.into_server(DEFAULT_MAX_MESSAGE_BYTES)))
+            .serve(addr),
+    );
+    let mut client = None;
+    for _ in 0..50 {
+        if let Ok(connected) = VectorDbClient::connect(format!("http://{addr}")).await {
+            client = Some(connected);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let mut client = client.expect("server did not start");
+    let echoed = |metadata: &tonic::metadata::MetadataMap| {
+        metadata.get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+
+    let mut req = tonic::Request::new(PingRequest {});
+    req.metadata_mut().insert(REQUEST_ID_HEADER, "client-42".parse().expect("metadata value"));
+    let resp = client.ping(req).await.expect("ping");
+    assert_eq!(echoed(resp.metadata()).as_deref(), Some("client-42"));
+
+    let first = echoed(client.ping(PingRequest {}).await.expect("ping").metadata()).expect("generated id");
+    let second = echoed(client.ping(PingRequest {}).await.expect("ping").metadata()).expect("generated id");
+    assert!(!first.is_empty());
+    assert_ne!(first, second);
+
+    // Failed calls carry the id too.
+    let mut req = tonic::Request::new(DescribeCollectionRequest { collection: "missing".into() });
+    req.metadata_mut().insert(REQUEST_ID_HEADER, "client-43".parse().expect("metadata value"));
+    let status = client.describe_collection(req).await.expect_err("unknown collection");
+    assert_eq!(echoed(status.metadata()).as_deref(), Some("client-43"));
+}
@@ -0,0 +1,322 @@
+use tempfile::tempdir;
+
+use vectaraft::server::integrity::{ReplayMismatch, ReplayTally};
+use vectaraft::server::state::{DbState, DbStateConfig};
+use vectaraft::storage::wal::{Wal, WalRecord};
+
+fn create(name: &str) -> WalRecord {
+    WalRecord::CreateCollection { name: name.into(), dim: 2, metric: "l2".into(), parallel_threshold: None, layout: None, assume_normalized: false, default_top_k: None, default_with_payloads: false, payload_store: None, id_type: None, ts_ms: 0 }
+}
+
+fn names(records: &[WalRecord]) -> Vec<String> {
+    records
+        .iter()
+        .map(|r| match r {
+            WalRecord::CreateCollection { name, .. } => name.clone(),
+            other => format!("{other:?}"),
+        })
+        .collect()
+}
+
+fn write_lines(path: &std::path::Path, records: &[WalRecord], trailer: bool) {
+    let mut body: String = records
+        .iter()
+        .map(|r| serde_json::to_string(r).expect("serialize") + "\n")
+        .collect();
+    if trailer {
+        body.push_str("#vectaraft-compaction-complete\n");
+    }
+    std::fs::write(path, body).expect("write");
+}
+
+#[test]
+fn open_finalizes_complete_compaction_temp_file() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let temp = tmp.path().join("wal.log.tmp");
+    write_lines(&path, &[create("a"), create("b"), create("c")], false);
+    // Crash after the compacted log (with trailer) was synced but before rename.
+    write_lines(&temp, &[create("compacted")], true);
+
+    let wal = Wal::open(&path).expect("open");
+
+    assert!(!temp.exists());
+    assert_eq!(names(&wal.replay().expect("replay")), vec!["compacted"]);
+    assert!(!std::fs::read_to_string(&path).expect("read").contains("#vectaraft"));
+}
+
+#[test]
+fn open_discards_partial_compaction_temp_file() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let temp = tmp.path().join("wal.log.tmp");
+    write_lines(&path, &[create("a"), create("b")], false);
+    // Crash mid-write: no trailer and a torn final line.
+    write_lines(&temp, &[create("half")], false);
+    std::fs::write(&temp, std::fs::read_to_string(&temp).unwrap() + "{\"type\":\"Crea").expect("tear");
+
+    let wal = Wal::open(&path).expect("open");
+
+    assert!(!temp.exists());
+    assert_eq!(names(&wal.replay().expect("replay")), vec!["a", "b"]);
+}
+
+#[test]
+fn rewrite_replaces_log_atomically() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let wal = Wal::open(&path).expect("open");
+    wal.append(&create("old")).expect("append");
+
+    wal.rewrite(&[create("x"), create("y")]).expect("rewrite");
+    wal.append(&create("z")).expect("append after rewrite");
+
+    assert!(!tmp.path().join("wal.log.tmp").exists());
+    assert_eq!(names(&wal.replay().expect("replay")), vec!["x", "y", "z"]);
+}
+
+#[test]
+fn count_policy_makes_records_durable_in_groups() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let on_disk = || Wal::open(&path).expect("reopen").replay().expect("replay").len();
+    let wal = Wal::open(&path).expect("open").with_flush_every(100);
+
+    for i in 0..99 {
+        wal.append(&create(&format!("c{i}"))).expect("append");
+    }
+    assert_eq!(on_disk(), 0);
+    wal.append(&create("c99")).expect("append");
+    assert_eq!(on_disk(), 100);
+
+    // A batch may complete a group partway through; the whole batch is written.
+    let batch: Vec<_> = (100..250).map(|i| create(&format!("c{i}"))).collect();
+    wal.append_all(&batch[..110]).expect("append batch");
+    assert_eq!(on_disk(), 210);
+    wal.append_all(&batch[110..]).expect("append batch");
+    assert_eq!(on_disk(), 210);
+    assert_eq!(wal.record_count(), 250);
+
+    // Crash before the group fills: only the 40 buffered records are lost.
+    drop(wal);
+    let recovered = Wal::open(&path).expect("reopen").with_flush_every(100);
+    let records = recovered.replay().expect("replay");
+    assert_eq!(records.len(), 210);
+    assert_eq!(names(&records).last().map(String::as_str), Some("c209"));
+
+    // Flush (as on shutdown) writes a partial group early.
+    recovered.append(&create("tail")).expect("append");
+    assert_eq!(on_disk(), 210);
+    recovered.flush().expect("flush");
+    assert_eq!(on_disk(), 211);
+}
+
+fn upsert(collection: &str, id: &str, vector: Vec<f32>) -> WalRecord {
+    WalRecord::Upsert {
+        collection: collection.into(),
+        id: id.into(),
+        vector,
+        payload_json: format!("{{\"id\":\"{id}\"}}"),
+        expires_at_ms: None,
+        ts_ms: 0,
+    }
+}
+
+#[test]
+fn bulk_replay_preserves_record_order_across_collections() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    write_lines(
+        &path,
+        &[
+            create("a"),
+            create("b"),
+            upsert("a", "a1", vec![1.0, 0.0]),
+            upsert("a", "a2", vec![2.0, 0.0]),
+            upsert("b", "b1", vec![0.0, 1.0]),
+            upsert("a", "bad-dim", vec![1.0, 2.0, 3.0]),
+            upsert("a", "a3", vec![3.0, 0.0]),
+            WalRecord::Truncate { collection: "b".into(), ts_ms: 0 },
+            upsert("b", "b2", vec![0.0, 2.0]),
+            WalRecord::Delete { collection: "a".into(), ids: vec!["a2".into()], ts_ms: 0 },
+            upsert("a", "a4", vec![4.0, 0.0]),
+            upsert("missing", "m1", vec![0.0, 0.0]),
+        ],
+        false,
+    );
+
+    let state = DbState::with_config(DbStateConfig {
+        wal_path: Some(path),
+        enable_wal: true,
+        read_only: true,
+        ..DbStateConfig::default()
+    });
+    let ids = |name: &str| -> Vec<String> {
+        let handle = state.catalog.get(name).expect("collection");
+        let view = handle.iter_points().expect("view");
+        view.iter().map(|(id, _, _)| id.to_string()).collect()
+    };
+    assert_eq!(ids("a"), vec!["a1", "a3", "a4"]);
+    assert_eq!(ids("b"), vec!["b2"]);
+    assert!(state.catalog.get("missing").is_none());
+}
+
+#[test]
+fn bad_record_in_an_upsert_run_skips_only_itself() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let numbered = WalRecord::CreateCollection { name: "n".into(), dim: 2, metric: "l2".into(), parallel_threshold: None, layout: None, assume_normalized: false, default_top_k: None, default_with_payloads: false, payload_store: None, id_type: Some("u64".into()), ts_ms: 0 };
+    write_lines(
+        &path,
+        &[
+            numbered,
+            upsert("n", "1", vec![1.0, 0.0]),
+            upsert("n", "2", vec![2.0, 0.0]),
+            upsert("n", "not-a-number", vec![3.0, 0.0]),
+            upsert("n", "4", vec![4.0, 0.0]),
+            upsert("n", "5", vec![1.0, 2.0, 3.0]),
+            upsert("n", "6", vec![6.0, 0.0]),
+        ],
+        false,
+    );
+
+    let state = DbState::with_config(DbStateConfig {
+        wal_path: Some(path),
+        enable_wal: true,
+        read_only: true,
+        ..DbStateConfig::default()
+    });
+    let handle = state.catalog.get("n").expect("collection");
+    let view = handle.iter_points().expect("view");
+    let ids: Vec<String> = view.iter().map(|(id, _, _)| id.to_string()).collect();
+    assert_eq!(ids, vec!["1", "2", "4", "6"]);
+}
+
+#[test]
+fn parallel_replay_keeps_each_collection_in_log_order() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let names: Vec<String> = (0..8).map(|c| format!("c{c}")).collect();
+    let mut records: Vec<WalRecord> = names.iter().map(|n| create(n)).collect();
+    // Interleave the collections record by record so every shard is split
+    // across the whole log.
+    for i in 0..200 {
+        for (c, name) in names.iter().enumerate() {
+            records.push(upsert(name, &format!("{name}-{i}"), vec![i as f32, c as f32]));
+            if i % 10 == 9 {
+                records.push(WalRecord::Delete { collection: name.clone(), ids: vec![format!("{name}-{}", i - 1)], ts_ms: 0 });
+            }
+        }
+    }
+    records.push(WalRecord::DropCollection { collection: "c7".into(), ts_ms: 0 });
+    records.push(create("c7"));
+    records.push(upsert("c7", "fresh", vec![0.0, 0.0]));
+    records.push(WalRecord::SwapCollections { target: "c0".into(), source: "c1".into(), ts_ms: 0 });
+    records.push(upsert("c0", "after-swap", vec![0.0, 0.0]));
+    write_lines(&path, &records, false);
+
+    let state = DbState::with_config(DbStateConfig {
+        wal_path: Some(path),
+        enable_wal: true,
+        read_only: true,
+        ..DbStateConfig::default()
+    });
+    let ids = |name: &str| -> Vec<String> {
+        let handle = state.catalog.get(name).expect("collection");
+        let view = handle.iter_points().expect("view");
+        view.iter().map(|(id, _, _)| id.to_string()).collect()
+    };
+    let expected = |name: &str| -> Vec<String> {
+        (0..200).filter(|i| i % 10 != 8).map(|i| format!("{name}-{i}")).collect()
+    };
+    for name in &names[2..7] {
+        assert_eq!(ids(name), expected(name), "{name}");
+    }
+    assert_eq!(ids("c7"), vec!["fresh"]);
+    // The swap moved c1's points into c0; the upsert logged after it lands on top.
+    let mut c0 = expected("c1");
+    c0.push("after-swap".into());
+    assert_eq!(ids("c0"), c0);
+    assert!(state.catalog.get("c1").is_none());
+    assert!(state.wal_replay_duration > std::time::Duration::ZERO);
+}
+
+#[test]
+fn replay_counts_records_by_type() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let upsert = |collection: &str, id: &str| WalRecord::Upsert {
+        collection: collection.into(),
+        id: id.into(),
+        vector: vec![0.0, 1.0],
+        payload_json: String::new(),
+        expires_at_ms: None,
+        ts_ms: 0,
+    };
+    write_lines(
+        &path,
+        &[
+            create("a"),
+            create("b"),
+            upsert("a", "a1"),
+            upsert("a", "a2"),
+            upsert("b", "b1"),
+            WalRecord::Delete { collection: "a".into(), ids: vec!["a1".into()], ts_ms: 0 },
+        ],
+        false,
+    );
+
+    let state = DbState::with_config(DbStateConfig {
+        wal_path: Some(path),
+        enable_wal: true,
+        read_only: true,
+        ..DbStateConfig::default()
+    });
+    let counts: Vec<(&str, u64)> = state.wal_replay_counts.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(counts, vec![("CreateCollection", 2), ("Delete", 1), ("Upsert", 3)]);
+
+    let metrics = vectaraft::telemetry::Metrics::new(&[]).expect("metrics");
+    metrics.record_wal_replay(&state.wal_replay_counts);
+    let body = metrics.encode().expect("encode");
+    assert!(body.contains("wal_replayed_records_total{type=\"Upsert\"} 3"), "{body}");
+    assert!(body.contains("wal_replayed_records_total{type=\"CreateCollection\"} 2"), "{body}");
+}
+
+#[test]
+fn startup_verification_detects_points_dropped_by_replay() {
+    let tmp = tempdir().expect("tempdir");
+    let path = tmp.path().join("wal.log");
+    let records = vec![
+        create("a"),
+        create("b"),
+        create("c"),
+        upsert("a", "a1", vec![1.0, 0.0]),
+        upsert("a", "a1", vec![1.5, 0.0]),
+        upsert("a", "bad-dim", vec![1.0, 2.0, 3.0]),
+        upsert("b", "b1", vec![0.0, 1.0]),
+        upsert("b", "b2", vec![0.0, 2.0]),
+        WalRecord::Delete { collection: "b".into(), ids: vec!["b1".into()], ts_ms: 0 },
+        WalRecord::SwapCollections { target: "c".into(), source: "b".into(), ts_ms: 0 },
+    ];
+    write_lines(&path, &records, false);
+
+    let state = DbState::with_config(DbStateConfig {
+        wal_path: Some(path),
+        enable_wal: true,
+        read_only: true,
+        verify_on_start: true,
+        ..DbStateConfig::default()
+    });
+    assert!(state.replay_mismatches.is_empty(), "{:?}", state.replay_mismatches);
+
+    // Simulate a replay that silently lost a point.
+    let mut tally = ReplayTally::default();
+    records.iter().for_each(|rec| tally.record(rec));
+    assert_eq!(tally.expected().into_iter().collect::<Vec<_>>(), vec![("a".to_string(), 2), ("c".to_string(), 1)]);
+    let handle = state.catalog.get("a").expect("collection");
+    handle.with_mut(|coll| coll.remove_points(&["a1".to_string()])).expect("drop point");
+    assert_eq!(
+        tally.verify(&state.catalog),
+        vec![ReplayMismatch { collection: "a".into(), expected: Some(2), loaded: Some(0) }]
+    );
+}